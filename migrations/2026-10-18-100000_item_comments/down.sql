@@ -0,0 +1,7 @@
+DROP INDEX item_comments_parent_id_idx;
+
+ALTER TABLE item_comments DROP COLUMN types;
+ALTER TABLE item_comments DROP COLUMN username;
+ALTER TABLE item_comments DROP COLUMN c_user_id;
+
+ALTER TABLE item_comments ALTER COLUMN user_id SET NOT NULL;
@@ -0,0 +1,10 @@
+-- комментарии к объектам -------
+---------------
+---------------
+ALTER TABLE item_comments ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE item_comments ADD COLUMN c_user_id INT;                       -- куки пользователь (аноним)
+ALTER TABLE item_comments ADD COLUMN username VARCHAR(100) NOT NULL DEFAULT ''; -- имя анонима
+ALTER TABLE item_comments ADD COLUMN types SMALLINT NOT NULL DEFAULT 1;   -- обычный / изменен / удален
+
+CREATE INDEX item_comments_parent_id_idx ON item_comments (parent_id);
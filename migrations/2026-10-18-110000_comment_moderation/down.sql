@@ -0,0 +1,4 @@
+DROP INDEX item_comments_status_idx;
+
+ALTER TABLE item_comments DROP COLUMN spam_score;
+ALTER TABLE item_comments DROP COLUMN status;
@@ -0,0 +1,7 @@
+-- модерация комментариев -------
+---------------
+---------------
+ALTER TABLE item_comments ADD COLUMN status SMALLINT NOT NULL DEFAULT 2;     -- на модерации / опубликован / отклонен
+ALTER TABLE item_comments ADD COLUMN spam_score SMALLINT NOT NULL DEFAULT 0; -- оценка эвристики спама
+
+CREATE INDEX item_comments_status_idx ON item_comments (status);
@@ -0,0 +1,23 @@
+-- история изменений объектов -------
+---------------
+---------------
+CREATE TABLE item_revisions (
+    id          SERIAL PRIMARY KEY,
+    item_id     INT NOT NULL,
+    user_id     INT NOT NULL,        -- автор правки
+    title       VARCHAR(100) NOT NULL,
+    description VARCHAR,
+    content     VARCHAR(30000),
+    slug        VARCHAR(100) NOT NULL,
+    types       SMALLINT NOT NULL,   -- 1 правка объекта / 2 правка текста / 3 восстановление / 4 исходное состояние
+    created     TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_item_revision
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_user_item_revision
+        FOREIGN KEY(user_id)
+            REFERENCES users(id)
+);
+CREATE INDEX item_revisions_item_id_idx ON item_revisions (item_id);
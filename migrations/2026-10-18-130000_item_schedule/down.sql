@@ -0,0 +1,5 @@
+DROP INDEX items_unpublish_at_idx;
+DROP INDEX items_publish_at_idx;
+
+ALTER TABLE items DROP COLUMN unpublish_at;
+ALTER TABLE items DROP COLUMN publish_at;
@@ -0,0 +1,8 @@
+-- отложенная публикация объектов -------
+---------------
+---------------
+ALTER TABLE items ADD COLUMN publish_at TIMESTAMP;   -- когда опубликовать
+ALTER TABLE items ADD COLUMN unpublish_at TIMESTAMP; -- когда скрыть
+
+CREATE INDEX items_publish_at_idx ON items (publish_at);
+CREATE INDEX items_unpublish_at_idx ON items (unpublish_at);
@@ -0,0 +1,21 @@
+-- ссылки на просмотр черновиков -------
+---------------
+---------------
+CREATE TABLE item_previews (
+    id         SERIAL PRIMARY KEY,
+    item_id    INT NOT NULL,
+    user_id    INT NOT NULL,       -- кто выдал ссылку
+    expires    TIMESTAMP NOT NULL, -- до какого времени ссылка работает
+    is_revoked BOOLEAN NOT NULL DEFAULT false,
+    view       INT NOT NULL DEFAULT 0,
+    created    TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_item_preview
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_user_item_preview
+        FOREIGN KEY(user_id)
+            REFERENCES users(id)
+);
+CREATE INDEX item_previews_item_id_idx ON item_previews (item_id);
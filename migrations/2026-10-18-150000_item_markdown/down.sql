@@ -0,0 +1,2 @@
+ALTER TABLE item_revisions DROP COLUMN content_md;
+ALTER TABLE items DROP COLUMN content_md;
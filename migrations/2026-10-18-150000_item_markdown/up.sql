@@ -0,0 +1,5 @@
+-- исходный текст объектов в markdown -------
+---------------
+---------------
+ALTER TABLE items ADD COLUMN content_md VARCHAR(30000);          -- markdown, из него собирается content
+ALTER TABLE item_revisions ADD COLUMN content_md VARCHAR(30000);
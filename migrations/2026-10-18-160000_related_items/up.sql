@@ -0,0 +1,20 @@
+-- кэш похожих объектов -------
+---------------
+---------------
+CREATE TABLE related_items (
+    id         SERIAL PRIMARY KEY,
+    item_id    INT NOT NULL,       -- для какого объекта
+    related_id INT NOT NULL,       -- похожий объект
+    score      FLOAT NOT NULL,     -- чем больше, тем выше в списке
+    created    TIMESTAMP NOT NULL, -- когда посчитан
+
+    CONSTRAINT fk_related_item
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_related_item_related
+        FOREIGN KEY(related_id)
+            REFERENCES items(id)
+);
+CREATE INDEX related_items_item_id_idx ON related_items (item_id);
+CREATE INDEX related_items_related_id_idx ON related_items (related_id);
@@ -0,0 +1,6 @@
+DROP TABLE slug_redirects;
+
+ALTER TABLE categories DROP CONSTRAINT categories_slug_types_key;
+ALTER TABLE categories ADD CONSTRAINT categories_slug_key UNIQUE (slug);
+ALTER TABLE items DROP CONSTRAINT items_slug_types_key;
+ALTER TABLE items ADD CONSTRAINT items_slug_key UNIQUE (slug);
@@ -0,0 +1,19 @@
+-- уникальность ссылок в пределах типа и перенаправления со старых ссылок -------
+---------------
+---------------
+ALTER TABLE items DROP CONSTRAINT items_slug_key;
+ALTER TABLE items ADD CONSTRAINT items_slug_types_key UNIQUE (slug, types);
+ALTER TABLE categories DROP CONSTRAINT categories_slug_key;
+ALTER TABLE categories ADD CONSTRAINT categories_slug_types_key UNIQUE (slug, types);
+
+CREATE TABLE slug_redirects (
+    id          SERIAL PRIMARY KEY,
+    types       SMALLINT NOT NULL,     -- блог, услуга, товар ......
+    is_category BOOLEAN NOT NULL,      -- ссылка категории или объекта
+    old_slug    VARCHAR(100) NOT NULL,
+    new_slug    VARCHAR(100) NOT NULL,
+    created     TIMESTAMP NOT NULL,
+
+    UNIQUE(types, is_category, old_slug)
+);
+CREATE INDEX slug_redirects_new_slug_idx ON slug_redirects (new_slug);
@@ -0,0 +1,14 @@
+-- перенаправления со старых адресов -------
+---------------
+---------------
+CREATE TABLE redirects (
+    id       SERIAL PRIMARY KEY,
+    source   VARCHAR(500) NOT NULL, -- старый путь, например /old/page.html
+    target   VARCHAR(500) NOT NULL, -- куда ведем: путь или полный адрес
+    status   SMALLINT NOT NULL,     -- 301 / 302
+    hits     INT NOT NULL DEFAULT 0,
+    last_hit TIMESTAMP,
+    created  TIMESTAMP NOT NULL,
+
+    UNIQUE(source)
+);
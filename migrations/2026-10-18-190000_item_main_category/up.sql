@@ -0,0 +1,14 @@
+-- основная категория объекта -------
+---------------
+---------------
+-- по ней строится канонический адрес объекта, остальные
+-- категории дают только дополнительные пути к нему
+ALTER TABLE items ADD COLUMN category_id INT REFERENCES categories(id) ON DELETE SET NULL;
+CREATE INDEX items_category_idx ON items (category_id);
+
+-- основной становится первая привязанная категория
+UPDATE items SET category_id = (
+    SELECT category.categories_id FROM category
+    WHERE category.item_id = items.id AND category.types = items.types
+    ORDER BY category.id LIMIT 1
+);
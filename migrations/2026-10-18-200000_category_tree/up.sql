@@ -0,0 +1,7 @@
+-- вложенные категории -------
+---------------
+---------------
+-- при удалении родителя дети поднимаются на его уровень в коде,
+-- ON DELETE SET NULL - на случай удаления в обход сайта
+ALTER TABLE categories ADD COLUMN parent_id INT REFERENCES categories(id) ON DELETE SET NULL;
+CREATE INDEX categories_parent_idx ON categories (parent_id);
@@ -0,0 +1,2 @@
+ALTER TABLE categories DROP COLUMN deleted_at;
+ALTER TABLE items DROP COLUMN deleted_at;
@@ -0,0 +1,9 @@
+-- корзина для объектов и категорий -------
+---------------
+---------------
+-- удаленные записи остаются в базе со всеми связями и
+-- стираются фоновой задачей через TRASH_DAYS дней
+ALTER TABLE items ADD COLUMN deleted_at TIMESTAMP;      -- когда перенесен в корзину
+ALTER TABLE categories ADD COLUMN deleted_at TIMESTAMP; -- когда перенесена в корзину
+CREATE INDEX items_deleted_at_idx ON items (deleted_at);
+CREATE INDEX categories_deleted_at_idx ON categories (deleted_at);
@@ -0,0 +1 @@
+DROP TABLE wiki_links;
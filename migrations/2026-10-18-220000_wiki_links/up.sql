@@ -0,0 +1,22 @@
+-- внутренние ссылки вики [[...]] -------
+---------------
+---------------
+CREATE TABLE wiki_links (
+    id        SERIAL PRIMARY KEY,
+    item_id   INT NOT NULL,          -- статья, в тексте которой ссылка
+    target    VARCHAR(200) NOT NULL, -- текст внутри [[...]] в нижнем регистре
+    target_id INT,                   -- найденная статья, пусто - такой статьи нет
+    created   TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_wiki_link_item
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_wiki_link_target
+        FOREIGN KEY(target_id)
+            REFERENCES items(id)
+            ON DELETE SET NULL
+);
+CREATE INDEX wiki_links_item_id_idx ON wiki_links (item_id);
+CREATE INDEX wiki_links_target_id_idx ON wiki_links (target_id);
+CREATE INDEX wiki_links_target_idx ON wiki_links (target);
@@ -0,0 +1 @@
+DROP TABLE help_votes;
@@ -0,0 +1,22 @@
+-- "помогла ли статья" для справки -------
+---------------
+---------------
+CREATE TABLE help_votes (
+    id         SERIAL PRIMARY KEY,
+    item_id    INT NOT NULL,       -- объект справки (types 6)
+    c_user_id  INT NOT NULL,       -- куки пользователь, один голос на статью
+    is_helpful BOOLEAN NOT NULL,   -- да / нет
+    comment    VARCHAR(1000),      -- необязательный отзыв
+    created    TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_help_vote_item
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_help_vote_c_user
+        FOREIGN KEY(c_user_id)
+            REFERENCES cookie_users(id),
+
+    UNIQUE(item_id, c_user_id)
+);
+CREATE INDEX help_votes_item_id_idx ON help_votes (item_id);
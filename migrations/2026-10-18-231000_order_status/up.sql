@@ -0,0 +1,28 @@
+-- статусы заказа и их история -------
+---------------
+---------------
+-- 1 новый, 2 рассмотрен, 3 отправлено предложение, 4 принят,
+-- 5 в работе, 6 сдан, 7 закрыт, 8 отменен
+ALTER TABLE orders ADD COLUMN status SMALLINT NOT NULL DEFAULT 1;
+
+CREATE TABLE order_status_history (
+    id       SERIAL PRIMARY KEY,
+    order_id INT NOT NULL,
+    status   SMALLINT NOT NULL,  -- в какой статус перешел заказ
+    user_id  INT,                -- кто перевел; пусто - сам заказчик
+    comment  VARCHAR(1000),      -- пояснение, его видит заказчик
+    created  TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_order_status_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id),
+
+    CONSTRAINT fk_order_status_user
+        FOREIGN KEY(user_id)
+            REFERENCES users(id)
+);
+CREATE INDEX order_status_history_order_id_idx ON order_status_history (order_id);
+
+-- у старых заказов история начинается с создания
+INSERT INTO order_status_history (order_id, status, created)
+SELECT id, 1, created FROM orders;
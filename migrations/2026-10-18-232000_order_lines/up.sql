@@ -0,0 +1,32 @@
+-- строки заказа -------
+---------------
+---------------
+-- выбранные опции копируются в заказ целиком, так что
+-- правка или удаление опции в каталоге не меняет старые заказы
+CREATE TABLE order_lines (
+    id            SERIAL PRIMARY KEY,
+    order_id      INT NOT NULL,
+    serve_id      INT,                   -- опция каталога; пусто, если ее удалили
+    name          VARCHAR(100) NOT NULL, -- название опции на момент заказа
+    price         INT NOT NULL,
+    man_hours     SMALLINT NOT NULL,
+    tech_cat_id   INT NOT NULL,          -- тех. категория опции
+    tech_cat_name VARCHAR(100) NOT NULL,
+    position      SMALLINT NOT NULL,
+
+    CONSTRAINT fk_order_line_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id),
+
+    CONSTRAINT fk_order_line_serve
+        FOREIGN KEY(serve_id)
+            REFERENCES serve(id)
+            ON DELETE SET NULL
+);
+CREATE INDEX order_lines_order_id_idx ON order_lines (order_id);
+
+-- строки старых заказов не восстановить: create_order писал опции
+-- не к заказу, а к заказанному объекту (serve_items с item_id =
+-- object_id), и какому заказу они принадлежат, уже не узнать.
+-- У старых заказов остается цена, строк у них нет. Сами эти связи
+-- убирает из каталога 2026-10-18-238000_order_serve_items.
@@ -0,0 +1,21 @@
+-- правки заказа заказчиком -------
+---------------
+---------------
+CREATE TABLE order_changes (
+    id        SERIAL PRIMARY KEY,
+    order_id  INT NOT NULL,
+    c_user_id INT NOT NULL,           -- куки пользователь, владелец заказа
+    changes   VARCHAR(5000) NOT NULL, -- что изменено, по строке на поле
+    old_price INT NOT NULL,
+    new_price INT NOT NULL,
+    created   TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_order_change_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id),
+
+    CONSTRAINT fk_order_change_c_user
+        FOREIGN KEY(c_user_id)
+            REFERENCES cookie_users(id)
+);
+CREATE INDEX order_changes_order_id_idx ON order_changes (order_id);
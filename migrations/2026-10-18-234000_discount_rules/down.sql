@@ -0,0 +1,2 @@
+ALTER TABLE orders DROP COLUMN promo_code;
+DROP TABLE discount_rules;
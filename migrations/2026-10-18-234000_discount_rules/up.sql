@@ -0,0 +1,34 @@
+-- правила скидок и промокоды -------
+---------------
+---------------
+-- из нестыкуемых правил срабатывает самое выгодное,
+-- стыкуемые добавляются к нему. Скидка не больше цены.
+CREATE TABLE discount_rules (
+    id           SERIAL PRIMARY KEY,
+    name         VARCHAR(100) NOT NULL,
+    min_price    INT NOT NULL DEFAULT 0,  -- порог, включительно
+    percent      SMALLINT,                -- скидка в процентах
+    amount       INT,                     -- или фиксированной суммой
+    tech_cat_id  INT,                     -- только опции этой тех. категории
+    types        SMALLINT,                -- только объекты этого типа (2 услуга, 3 товар, 5 работа)
+    promo_code   VARCHAR(50),             -- работает только с этим промокодом
+    starts_at    TIMESTAMP,
+    ends_at      TIMESTAMP,
+    is_stackable BOOLEAN NOT NULL DEFAULT false,
+    is_active    BOOLEAN NOT NULL DEFAULT true,
+    created      TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_discount_rule_tech_cat
+        FOREIGN KEY(tech_cat_id)
+            REFERENCES tech_categories(id)
+            ON DELETE CASCADE
+);
+CREATE INDEX discount_rules_promo_code_idx ON discount_rules (promo_code);
+
+-- прежние зашитые в код ступени: 5% от 1 млн, 7% от 2 млн, 10% от 3 млн
+INSERT INTO discount_rules (name, min_price, percent, created) VALUES
+    ('5% от 1 000 000', 1000000, 5, NOW()),
+    ('7% от 2 000 000', 2000000, 7, NOW()),
+    ('10% от 3 000 000', 3000000, 10, NOW());
+
+ALTER TABLE orders ADD COLUMN promo_code VARCHAR(50);
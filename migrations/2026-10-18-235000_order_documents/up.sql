@@ -0,0 +1,22 @@
+-- документы заказа: КП и счета -------
+---------------
+---------------
+CREATE TABLE order_documents (
+    id        SERIAL PRIMARY KEY,
+    order_id  INT NOT NULL,
+    types     SMALLINT NOT NULL,     -- 1 коммерческое предложение, 2 счет
+    year      SMALLINT NOT NULL,     -- нумерация своя на каждый год
+    seq       INT NOT NULL,          -- порядковый номер в году
+    number    VARCHAR(50) NOT NULL,  -- номер для печати, КП-2026-0001
+    price     INT NOT NULL,          -- цена заказа на момент выдачи
+    price_acc INT,                   -- скидка на момент выдачи
+    lines     TEXT NOT NULL,         -- строки заказа на момент выдачи, json
+    created   TIMESTAMP NOT NULL,
+
+    UNIQUE(types, year, seq),
+
+    CONSTRAINT fk_order_document_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id)
+);
+CREATE INDEX order_documents_order_id_idx ON order_documents (order_id);
@@ -0,0 +1,2 @@
+ALTER TABLE item_revisions ALTER COLUMN content TYPE VARCHAR(30000);
+ALTER TABLE items ALTER COLUMN content TYPE VARCHAR(30000);
@@ -0,0 +1,5 @@
+-- html из markdown с подсветкой кода в разы длиннее исходника -------
+---------------
+---------------
+ALTER TABLE items ALTER COLUMN content TYPE TEXT;
+ALTER TABLE item_revisions ALTER COLUMN content TYPE TEXT;
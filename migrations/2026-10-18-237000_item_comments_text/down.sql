@@ -0,0 +1 @@
+ALTER TABLE item_comments ALTER COLUMN comment TYPE VARCHAR(1000);
@@ -0,0 +1,4 @@
+-- комментарий хранится экранированным: 1000 видимых символов -------
+-- могут занять в базе в несколько раз больше
+---------------
+ALTER TABLE item_comments ALTER COLUMN comment TYPE TEXT;
@@ -0,0 +1 @@
+-- удаленные связи не восстанавливаются
@@ -0,0 +1,19 @@
+-- связи опций, которые create_order дописывал к заказанному объекту -------
+---------------
+---------------
+-- Заказ писал их с types заказа (1 услуга, 2 товар, 3 работа), а каталог
+-- пишет types самого объекта (2 услуга, 3 товар, 5 работа). id объектов
+-- общие для всех типов, поэтому связь, чей types не совпадает с types
+-- объекта item_id, добавлена заказом - такие и удаляем.
+DELETE FROM serve_items si
+WHERE si.types IN (1, 2, 3)
+    AND NOT EXISTS (
+        SELECT 1 FROM items i
+        WHERE i.id = si.item_id AND i.types = si.types
+    );
+DELETE FROM tech_categories_items ti
+WHERE ti.types IN (1, 2, 3)
+    AND NOT EXISTS (
+        SELECT 1 FROM items i
+        WHERE i.id = ti.item_id AND i.types = ti.types
+    );
@@ -12,6 +12,7 @@ pub mod schema;
 pub mod models;
 pub mod routes;
 pub mod websocket;
+pub mod scheduler;
 mod errors;
 mod vars;
 
@@ -48,7 +49,14 @@ static SERVER_COUNTER: AtomicUsize = AtomicUsize::new(0);
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("debug"));
+
+    // разовая очистка html в уже сохраненных строках
+    if std::env::args().any(|arg| arg == "resanitize") {
+        utils::resanitize_all(std::env::args().any(|arg| arg == "--dry-run"));
+        return Ok(());
+    }
     let server = websocket::Server::new().start();
+    scheduler::Scheduler.start();
     let secret_key = Key::generate();
 
     HttpServer::new(move || {
@@ -104,6 +104,7 @@ impl Message {
     ) -> Result<Message, Error> {
         use chrono::Duration;
         use crate::models::NewFile;
+        use crate::utils::sanitize_plain_opt;
 
         let _connection = establish_connection();
 
@@ -111,7 +112,7 @@ impl Message {
             user_id: user_id,
             chat_id: chat_id,
             created: chrono::Local::now().naive_utc() + Duration::hours(3),
-            content: content,
+            content: sanitize_plain_opt(&content),
             view:    1,
             types:   1,
         };
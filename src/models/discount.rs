@@ -0,0 +1,289 @@
+use crate::schema;
+use crate::diesel::{
+    Queryable,
+    Insertable,
+    QueryDsl,
+    RunQueryDsl,
+    ExpressionMethods,
+};
+use serde::{Serialize, Deserialize};
+use crate::schema::discount_rules;
+use crate::utils::establish_connection;
+
+
+// правило скидки. Порог min_price сравнивается с суммой, к которой
+// правило применяется: со всей ценой или с опциями одной тех. категории.
+// Скидка - percent процентов от этой суммы или фиксированная amount.
+// Из нестыкуемых правил срабатывает самое выгодное, стыкуемые
+// (is_stackable) добавляются к нему. Правило с promo_code работает
+// только когда этот код введен в форме заказа.
+#[derive(Debug, Serialize, Queryable, Identifiable)]
+pub struct DiscountRule {
+    pub id:           i32,
+    pub name:         String,
+    pub min_price:    i32,
+    pub percent:      Option<i16>,
+    pub amount:       Option<i32>,
+    pub tech_cat_id:  Option<i32>,
+    pub types:        Option<i16>,
+    pub promo_code:   Option<String>,
+    pub starts_at:    Option<chrono::NaiveDateTime>,
+    pub ends_at:      Option<chrono::NaiveDateTime>,
+    pub is_stackable: bool,
+    pub is_active:    bool,
+    pub created:      chrono::NaiveDateTime,
+}
+
+// то, что считаем: тип объекта и выбранные опции парами
+// (тех. категория, цена)
+pub struct DiscountInput {
+    pub types:      i16,
+    pub lines:      Vec<(i32, i32)>,
+    pub promo_code: Option<String>,
+}
+
+impl DiscountInput {
+    pub fn get_total(&self) -> i32 {
+        return self.lines.iter().map(|l| l.1).sum();
+    }
+    pub fn get_tech_cat_total(&self, tech_cat_id: i32) -> i32 {
+        return self.lines.iter().filter(|l| l.0 == tech_cat_id).map(|l| l.1).sum();
+    }
+}
+
+impl DiscountRule {
+    pub fn get_rules_list(page: i32, limit: i32) -> (Vec<DiscountRule>, i32) {
+        let mut next_page_number = 0;
+        let have_next: i32;
+        let object_list: Vec<DiscountRule>;
+
+        if page > 1 {
+            let step = (page - 1) * 20;
+            have_next = page * limit + 1;
+            object_list = DiscountRule::get_rules(limit.into(), step.into());
+        }
+        else {
+            have_next = limit + 1;
+            object_list = DiscountRule::get_rules(limit.into(), 0);
+        }
+        if DiscountRule::get_rules(1, have_next.into()).len() > 0 {
+            next_page_number = page + 1;
+        }
+
+        return (object_list, next_page_number);
+    }
+    pub fn get_rules(limit: i64, offset: i64) -> Vec<DiscountRule> {
+        use crate::schema::discount_rules::dsl::discount_rules;
+
+        let _connection = establish_connection();
+        return discount_rules
+            .order((schema::discount_rules::is_active.desc(), schema::discount_rules::min_price.asc()))
+            .limit(limit)
+            .offset(offset)
+            .load::<DiscountRule>(&_connection)
+            .expect("E.");
+    }
+    // промокоды сравниваем без учета регистра и пробелов
+    pub fn normalize_code(code: &str) -> String {
+        return code.trim().to_uppercase();
+    }
+    // правила, которые действуют сейчас
+    fn get_active_rules() -> Vec<DiscountRule> {
+        use crate::schema::discount_rules::dsl::discount_rules;
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let now = chrono::Local::now().naive_utc() + Duration::hours(3);
+        let _rules = discount_rules
+            .filter(schema::discount_rules::is_active.eq(true))
+            .load::<DiscountRule>(&_connection)
+            .expect("E.");
+        return _rules
+            .into_iter()
+            .filter(|r| r.starts_at.map(|d| d <= now).unwrap_or(true))
+            .filter(|r| r.ends_at.map(|d| d > now).unwrap_or(true))
+            .collect();
+    }
+    fn is_applicable(&self, types: i16, promo_code: &Option<String>) -> bool {
+        if self.types.is_some() && self.types != Some(types) {
+            return false;
+        }
+        return match &self.promo_code {
+            Some(_code) => promo_code
+                .as_ref()
+                .map(|c| DiscountRule::normalize_code(c) == DiscountRule::normalize_code(_code))
+                .unwrap_or(false),
+            None => true,
+        };
+    }
+    // скидка правила для суммы base, 0 - если порог не пройден
+    fn get_value(&self, base: i32) -> i32 {
+        if base <= 0 || base < self.min_price {
+            return 0;
+        }
+        let value: i64 = match (self.percent, self.amount) {
+            (Some(_percent), _) => base as i64 * _percent as i64 / 100,
+            (None, Some(_amount)) => _amount as i64,
+            _ => 0,
+        };
+        return value.max(0).min(base as i64) as i32;
+    }
+    // есть ли сейчас правило с таким промокодом для этого типа
+    pub fn is_valid_promo(code: &str, types: i16) -> bool {
+        let code = Some(code.to_string());
+        return DiscountRule::get_active_rules()
+            .iter()
+            .any(|r| r.promo_code.is_some() && r.is_applicable(types, &code));
+    }
+    // сумма скидки. None, если ни одно правило не сработало -
+    // так же, как раньше писался price_acc
+    pub fn get_discount(input: &DiscountInput) -> Option<i32> {
+        return DiscountRule::combine(&DiscountRule::get_active_rules(), input);
+    }
+    // лучшее нестыкуемое правило плюс все стыкуемые, не больше всей суммы
+    fn combine(rules: &[DiscountRule], input: &DiscountInput) -> Option<i32> {
+        let total = input.get_total();
+        let mut best = 0;
+        let mut stacked = 0;
+        for rule in rules.iter() {
+            if !rule.is_applicable(input.types, &input.promo_code) {
+                continue;
+            }
+            let base = match rule.tech_cat_id {
+                Some(_id) => input.get_tech_cat_total(_id),
+                None => total,
+            };
+            let value = rule.get_value(base);
+            if rule.is_stackable {
+                stacked += value;
+            }
+            else if value > best {
+                best = value;
+            }
+        }
+        let discount = (best + stacked).min(total);
+        if discount > 0 {
+            return Some(discount);
+        }
+        return None;
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="discount_rules"]
+pub struct NewDiscountRule {
+    pub name:         String,
+    pub min_price:    i32,
+    pub percent:      Option<i16>,
+    pub amount:       Option<i32>,
+    pub tech_cat_id:  Option<i32>,
+    pub types:        Option<i16>,
+    pub promo_code:   Option<String>,
+    pub starts_at:    Option<chrono::NaiveDateTime>,
+    pub ends_at:      Option<chrono::NaiveDateTime>,
+    pub is_stackable: bool,
+    pub is_active:    bool,
+    pub created:      chrono::NaiveDateTime,
+}
+
+// пустые поля формы пишутся как NULL
+#[derive(Queryable, Serialize, Deserialize, AsChangeset, Debug)]
+#[table_name="discount_rules"]
+#[changeset_options(treat_none_as_null="true")]
+pub struct EditDiscountRule {
+    pub name:         String,
+    pub min_price:    i32,
+    pub percent:      Option<i16>,
+    pub amount:       Option<i32>,
+    pub tech_cat_id:  Option<i32>,
+    pub types:        Option<i16>,
+    pub promo_code:   Option<String>,
+    pub starts_at:    Option<chrono::NaiveDateTime>,
+    pub ends_at:      Option<chrono::NaiveDateTime>,
+    pub is_stackable: bool,
+    pub is_active:    bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(min_price: i32, percent: Option<i16>, amount: Option<i32>, is_stackable: bool) -> DiscountRule {
+        DiscountRule {
+            id:           0,
+            name:         "".to_string(),
+            min_price:    min_price,
+            percent:      percent,
+            amount:       amount,
+            tech_cat_id:  None,
+            types:        None,
+            promo_code:   None,
+            starts_at:    None,
+            ends_at:      None,
+            is_stackable: is_stackable,
+            is_active:    true,
+            created:      chrono::NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+    fn input(lines: Vec<(i32, i32)>) -> DiscountInput {
+        DiscountInput {
+            types:      1,
+            lines:      lines,
+            promo_code: None,
+        }
+    }
+
+    #[test]
+    fn get_total_sums_lines() {
+        let _input = input(vec![(1, 1000), (2, 500), (1, 250)]);
+        assert_eq!(_input.get_total(), 1750);
+        assert_eq!(_input.get_tech_cat_total(1), 1250);
+        assert_eq!(_input.get_tech_cat_total(3), 0);
+        assert_eq!(input(Vec::new()).get_total(), 0);
+    }
+
+    #[test]
+    fn get_value_includes_threshold() {
+        let _rule = rule(1000, Some(10), None, false);
+        assert_eq!(_rule.get_value(999), 0);
+        assert_eq!(_rule.get_value(1000), 100);
+        assert_eq!(_rule.get_value(0), 0);
+    }
+
+    #[test]
+    fn get_value_caps_amount_at_base() {
+        let _rule = rule(0, None, Some(5000), false);
+        assert_eq!(_rule.get_value(3000), 3000);
+        assert_eq!(rule(0, None, None, false).get_value(3000), 0);
+    }
+
+    #[test]
+    fn combine_takes_best_and_adds_stackable() {
+        let rules = vec![
+            rule(0, Some(10), None, false),
+            rule(0, None, Some(300), false),
+            rule(0, None, Some(50), true),
+        ];
+        assert_eq!(DiscountRule::combine(&rules, &input(vec![(1, 2000)])), Some(350));
+        assert_eq!(DiscountRule::combine(&rules, &input(vec![(1, 5000)])), Some(550));
+    }
+
+    #[test]
+    fn combine_caps_at_total() {
+        let rules = vec![
+            rule(0, None, Some(800), false),
+            rule(0, None, Some(800), true),
+        ];
+        assert_eq!(DiscountRule::combine(&rules, &input(vec![(1, 1000)])), Some(1000));
+        assert_eq!(DiscountRule::combine(&rules, &input(Vec::new())), None);
+    }
+
+    #[test]
+    fn normalize_code_ignores_case_and_spaces() {
+        assert_eq!(DiscountRule::normalize_code("  spring2026 "), "SPRING2026");
+        let mut _rule = rule(0, Some(5), None, false);
+        _rule.promo_code = Some("Spring".to_string());
+        assert!(_rule.is_applicable(1, &Some(" SPRING ".to_string())));
+        assert!(!_rule.is_applicable(1, &None));
+    }
+}
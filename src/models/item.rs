@@ -7,9 +7,12 @@ use crate::diesel::{
     ExpressionMethods,
     NullableExpressionMethods,
     PgTextExpressionMethods,
+    BoolExpressionMethods,
 };
 use serde::{Serialize,Deserialize};
 use crate::models::{
+    get_item_url,
+    get_category_prefix,
     User,
     Tag,
     TechCategories,
@@ -23,9 +26,15 @@ use crate::schema::{
     items,
     category,
     item_comments,
+    item_previews,
+    item_revisions,
+    related_items,
+    wiki_links,
+    help_votes,
 };
 use crate::utils::establish_connection;
 use crate::errors::Error;
+use std::collections::HashMap;
 
 
 ///////////
@@ -63,11 +72,12 @@ impl CatDetail {
 
 #[derive(Serialize, Queryable)]
 pub struct Cat {
-    pub name:  String,
-    pub slug:  String,
-    pub count: i16,
-    pub id:    i32,
-    pub image: Option<String>,
+    pub name:      String,
+    pub slug:      String,
+    pub count:     i16,
+    pub id:        i32,
+    pub image:     Option<String>,
+    pub parent_id: Option<i32>,
 }
 impl Cat {
     pub fn get_image(&self) -> String {
@@ -97,6 +107,10 @@ pub struct Blog {
     pub description: Option<String>,
 }
 impl Blog {
+    // канонический адрес, а не через категорию, которую сейчас листают
+    pub fn get_url(&self) -> String {
+        return Item::get_url_by_id(self.id);
+    }
     pub fn get_image(&self) -> String {
         if self.image.is_some() {
             return self.image.as_deref().unwrap().to_string();
@@ -105,6 +119,9 @@ impl Blog {
             return "/static/images/img.jpg".to_string();
         }
     }
+    pub fn get_comments_count(&self) -> i64 {
+        return ItemComment::count_for_item(self.id);
+    }
     pub fn get_tags(&self) -> Vec<SmallTag> {
         use crate::schema::{
             tags_items::dsl::tags_items,
@@ -137,6 +154,10 @@ pub struct Service {
     pub description: Option<String>,
 }
 impl Service {
+    // канонический адрес, а не через категорию, которую сейчас листают
+    pub fn get_url(&self) -> String {
+        return Item::get_url_by_id(self.id);
+    }
     pub fn get_image(&self) -> String {
         if self.image.is_some() {
             return self.image.as_deref().unwrap().to_string();
@@ -179,6 +200,10 @@ pub struct Store {
     pub price_acc:   Option<i32>,
 }
 impl Store {
+    // канонический адрес, а не через категорию, которую сейчас листают
+    pub fn get_url(&self) -> String {
+        return Item::get_url_by_id(self.id);
+    }
     pub fn get_image(&self) -> String {
         if self.image.is_some() {
             return self.image.as_deref().unwrap().to_string();
@@ -187,6 +212,9 @@ impl Store {
             return "/static/images/img.jpg".to_string();
         }
     }
+    pub fn get_comments_count(&self) -> i64 {
+        return ItemComment::count_for_item(self.id);
+    }
     pub fn get_tags(&self) -> Vec<SmallTag> {
         use crate::schema::{
             tags_items::dsl::tags_items,
@@ -220,6 +248,10 @@ pub struct Wiki {
     pub created:     chrono::NaiveDateTime,
 }
 impl Wiki {
+    // канонический адрес, а не через категорию, которую сейчас листают
+    pub fn get_url(&self) -> String {
+        return Item::get_url_by_id(self.id);
+    }
     pub fn get_image(&self) -> String {
         if self.image.is_some() {
             return self.image.as_deref().unwrap().to_string();
@@ -228,6 +260,9 @@ impl Wiki {
             return "/static/images/img.jpg".to_string();
         }
     }
+    pub fn get_comments_count(&self) -> i64 {
+        return ItemComment::count_for_item(self.id);
+    }
     pub fn get_tags(&self) -> Vec<SmallTag> {
         use crate::schema::{
             tags_items::dsl::tags_items,
@@ -260,6 +295,10 @@ pub struct Work {
     pub description: Option<String>,
 }
 impl Work {
+    // канонический адрес, а не через категорию, которую сейчас листают
+    pub fn get_url(&self) -> String {
+        return Item::get_url_by_id(self.id);
+    }
     pub fn get_image(&self) -> String {
         if self.image.is_some() {
             return self.image.as_deref().unwrap().to_string();
@@ -268,6 +307,9 @@ impl Work {
             return "/static/images/img.jpg".to_string();
         }
     }
+    pub fn get_comments_count(&self) -> i64 {
+        return ItemComment::count_for_item(self.id);
+    }
     pub fn get_tags(&self) -> Vec<SmallTag> {
         use crate::schema::{
             tags_items::dsl::tags_items,
@@ -346,9 +388,15 @@ impl Help {
 
 #[derive(Serialize, Queryable)]
 pub struct FeaturedItem {
+    pub id:    i32,
     pub slug:  String,
     pub title: String,
 }
+impl FeaturedItem {
+    pub fn get_url(&self) -> String {
+        return Item::get_url_by_id(self.id);
+    }
+}
 
 #[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
 #[table_name="categories"]
@@ -365,6 +413,8 @@ pub struct Categories {
     pub types:       i16,
     pub slug:        String,
     pub now_u:       i16,
+    pub parent_id:   Option<i32>,
+    pub deleted_at:  Option<chrono::NaiveDateTime>,
 }
 
 impl Categories {
@@ -414,6 +464,7 @@ impl Categories {
                         .filter(schema::items::types.eq(types))
                         .filter(schema::items::is_active.eq(true))
                         .select((
+                            schema::items::id,
                             schema::items::slug,
                             schema::items::title,
                         ))
@@ -427,6 +478,7 @@ impl Categories {
                         .filter(schema::items::types.eq(types))
                         .filter(schema::items::is_active.eq(true))
                         .select((
+                            schema::items::id,
                             schema::items::slug,
                             schema::items::title,
                         ))
@@ -497,6 +549,7 @@ impl Categories {
         if is_admin {
              _items = items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -579,6 +632,7 @@ impl Categories {
         if is_admin {
              _items = items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -660,6 +714,7 @@ impl Categories {
         if is_admin {
              _items = items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -745,6 +800,7 @@ impl Categories {
         if is_admin {
              _items = items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -828,6 +884,7 @@ impl Categories {
         if is_admin {
              _items = items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -909,6 +966,7 @@ impl Categories {
         if is_admin {
              _items = items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::position.asc())
                 .limit(limit)
                 .offset(offset)
@@ -947,22 +1005,311 @@ impl Categories {
             return "/static/images/img.jpg".to_string();
         }
     }
+    // счетчики в списке категорий включают объекты подкатегорий
     pub fn get_categories_for_types(types: i16) -> Result<Vec<Cat>, Error> {
         use crate::schema::categories::dsl::categories;
         let _connection = establish_connection();
-        let cats = categories
+        let mut cats = categories
             .filter(schema::categories::types.eq(types))
+            .filter(schema::categories::deleted_at.is_null())
+            .order(schema::categories::position.asc())
             .select((
                 schema::categories::name,
                 schema::categories::slug,
                 schema::categories::count,
                 schema::categories::id,
-                schema::categories::image
+                schema::categories::image,
+                schema::categories::parent_id,
             ))
             .load::<Cat>(&_connection)
             .expect("E");
+        let counts = Categories::get_total_counts(types);
+        for cat in cats.iter_mut() {
+            cat.count = *counts.get(&cat.id).unwrap_or(&cat.count);
+        }
         return Ok(cats);
     }
+
+    // id и родители всех категорий типа
+    fn get_parents_map(types: i16) -> HashMap<i32, Option<i32>> {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        return categories
+            .filter(schema::categories::types.eq(types))
+            .select((schema::categories::id, schema::categories::parent_id))
+            .load::<(i32, Option<i32>)>(&_connection)
+            .expect("E")
+            .into_iter()
+            .collect();
+    }
+    // сама категория и все ее подкатегории на любой глубине
+    pub fn get_descendant_ids(cat_id: i32, types: i16) -> Vec<i32> {
+        let parents = Categories::get_parents_map(types);
+        let mut ids = vec![cat_id];
+        let mut i = 0;
+        while i < ids.len() {
+            let current = ids[i];
+            for (id, parent_id) in parents.iter() {
+                if *parent_id == Some(current) && !ids.contains(id) {
+                    ids.push(*id);
+                }
+            }
+            i += 1;
+        }
+        return ids;
+    }
+    // цепочка от корня до самой категории - для хлебных крошек
+    pub fn get_breadcrumbs(cat_id: i32) -> Vec<Cat> {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        let mut list: Vec<Cat> = Vec::new();
+        let mut current = Some(cat_id);
+        // ограничение глубины на случай испорченных данных
+        while current.is_some() && list.len() < 20 {
+            let _cat = categories
+                .filter(schema::categories::id.eq(current.unwrap()))
+                .select((
+                    schema::categories::name,
+                    schema::categories::slug,
+                    schema::categories::count,
+                    schema::categories::id,
+                    schema::categories::image,
+                    schema::categories::parent_id,
+                ))
+                .first::<Cat>(&_connection);
+            if _cat.is_err() {
+                break;
+            }
+            let _cat = _cat.expect("E");
+            current = _cat.parent_id;
+            list.insert(0, _cat);
+        }
+        return list;
+    }
+    // вложенный путь категории: "родитель/категория"
+    pub fn get_path(cat_id: i32) -> String {
+        return Categories::get_breadcrumbs(cat_id)
+            .into_iter()
+            .map(|c| c.slug)
+            .collect::<Vec<String>>()
+            .join("/");
+    }
+    pub fn get_category_url(cat_id: i32, types: i16) -> String {
+        return get_category_prefix(types) + &Categories::get_path(cat_id) + &"/".to_string();
+    }
+    // число опубликованных объектов в категории вместе с подкатегориями.
+    // Объект, привязанный к нескольким из них, считается один раз.
+    pub fn get_total_counts(types: i16) -> HashMap<i32, i16> {
+        use crate::schema::{
+            items::dsl::items,
+            category::dsl::category,
+        };
+        use std::collections::HashSet;
+
+        let _connection = establish_connection();
+        let parents = Categories::get_parents_map(types);
+        let active_ids = items
+            .filter(schema::items::types.eq(types))
+            .filter(schema::items::is_active.eq(true))
+            .select(schema::items::id);
+        let links = category
+            .filter(schema::category::types.eq(types))
+            .filter(schema::category::item_id.eq_any(active_ids))
+            .select((schema::category::categories_id, schema::category::item_id))
+            .load::<(i32, i32)>(&_connection)
+            .expect("E");
+
+        let mut sets: HashMap<i32, HashSet<i32>> = HashMap::new();
+        for (cat_id, item_id) in links.into_iter() {
+            // объект попадает в категорию и во всех ее предков
+            let mut current = Some(cat_id);
+            let mut depth = 0;
+            while current.is_some() && depth < 20 {
+                let id = current.unwrap();
+                sets.entry(id).or_insert(HashSet::new()).insert(item_id);
+                current = *parents.get(&id).unwrap_or(&None);
+                depth += 1;
+            }
+        }
+        let mut counts = HashMap::new();
+        for id in parents.keys() {
+            let count = sets.get(id).map(|s| s.len()).unwrap_or(0);
+            counts.insert(*id, count as i16);
+        }
+        return counts;
+    }
+    // то же для одной категории - одним запросом с COUNT(DISTINCT)
+    pub fn get_total_count(cat_id: i32, types: i16) -> i16 {
+        use crate::schema::{
+            items::dsl::items,
+            category::dsl::category,
+        };
+        use diesel::dsl::sql;
+        use diesel::sql_types::BigInt;
+
+        let _connection = establish_connection();
+        let active_ids = items
+            .filter(schema::items::types.eq(types))
+            .filter(schema::items::is_active.eq(true))
+            .select(schema::items::id);
+        let count = category
+            .filter(schema::category::types.eq(types))
+            .filter(schema::category::categories_id.eq_any(Categories::get_descendant_ids(cat_id, types)))
+            .filter(schema::category::item_id.eq_any(active_ids))
+            .select(sql::<BigInt>("COUNT(DISTINCT category.item_id)"))
+            .first::<i64>(&_connection)
+            .expect("E");
+        return count.min(i16::MAX as i64) as i16;
+    }
+    // дерево категорий типа в порядке обхода, с глубиной -
+    // для выбора родителя в редакторе. exclude_id убирает
+    // категорию вместе с поддеревом (нельзя стать своим потомком).
+    pub fn get_tree(types: i16, exclude_id: i32) -> Vec<CatNode> {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        let _cats = categories
+            .filter(schema::categories::types.eq(types))
+            .filter(schema::categories::deleted_at.is_null())
+            .order(schema::categories::position.asc())
+            .select((
+                schema::categories::id,
+                schema::categories::name,
+                schema::categories::slug,
+                schema::categories::parent_id,
+            ))
+            .load::<(i32, String, String, Option<i32>)>(&_connection)
+            .expect("E");
+
+        let mut tree = Vec::new();
+        let mut visited: Vec<i32> = Vec::new();
+        // обход в глубину: дети сразу за родителем
+        fn walk(
+            cats: &Vec<(i32, String, String, Option<i32>)>,
+            types: i16,
+            parent: Option<i32>,
+            depth: i16,
+            exclude_id: i32,
+            visited: &mut Vec<i32>,
+            tree: &mut Vec<CatNode>,
+        ) {
+            for (id, name, slug, parent_id) in cats.iter() {
+                if *parent_id != parent || *id == exclude_id || visited.contains(id) {
+                    continue;
+                }
+                visited.push(*id);
+                tree.push(CatNode {
+                    id:        *id,
+                    name:      name.clone(),
+                    slug:      slug.clone(),
+                    parent_id: *parent_id,
+                    types:     types,
+                    depth:     depth,
+                });
+                walk(cats, types, Some(*id), depth + 1, exclude_id, visited, tree);
+            }
+        }
+        walk(&_cats, types, None, 0, exclude_id, &mut visited, &mut tree);
+        return tree;
+    }
+    // категория в корзине пропадает из списков и своей страницы,
+    // но сохраняет привязки объектов. Подкатегории сразу переходят
+    // к ее родителю, как при purge - иначе до них не дойти ни из
+    // дерева, ни по адресу.
+    pub fn move_to_trash(&self) -> () {
+        use crate::schema::categories::dsl::categories;
+
+        if self.deleted_at.is_some() {
+            return;
+        }
+        let _connection = establish_connection();
+        diesel::update(categories.filter(schema::categories::parent_id.eq(self.id)))
+            .set(schema::categories::parent_id.eq(self.parent_id))
+            .execute(&_connection)
+            .expect("E.");
+        diesel::update(self)
+            .set(schema::categories::deleted_at.eq(chrono::Local::now().naive_utc() + chrono::Duration::hours(3)))
+            .execute(&_connection)
+            .expect("E.");
+    }
+    pub fn restore_from_trash(&self) -> () {
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set(schema::categories::deleted_at.eq(None::<chrono::NaiveDateTime>))
+            .execute(&_connection)
+            .expect("E.");
+    }
+    pub fn get_purge_date(&self) -> Option<chrono::NaiveDateTime> {
+        use crate::vars::trash_days;
+
+        return self.deleted_at.map(|d| d + chrono::Duration::days(trash_days()));
+    }
+    pub fn get_trash() -> Vec<Categories> {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        return categories
+            .filter(schema::categories::deleted_at.is_not_null())
+            .order(schema::categories::deleted_at.desc())
+            .load::<Categories>(&_connection)
+            .expect("E.");
+    }
+    pub fn get_categories_for_purge(before: chrono::NaiveDateTime) -> Vec<Categories> {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        return categories
+            .filter(schema::categories::deleted_at.le(before))
+            .load::<Categories>(&_connection)
+            .expect("E.");
+    }
+    // окончательное удаление: подкатегории переходят к родителю,
+    // объекты теряют только привязку к этой категории
+    pub fn purge(&self) -> () {
+        use crate::schema::{
+            categories::dsl::categories,
+            category::dsl::category,
+        };
+
+        let _connection = establish_connection();
+        diesel::update(categories.filter(schema::categories::parent_id.eq(self.id)))
+            .set(schema::categories::parent_id.eq(self.parent_id))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(category.filter(schema::category::categories_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(self).execute(&_connection).expect("E");
+    }
+    // можно ли сделать parent_id родителем категории cat_id
+    pub fn is_valid_parent(cat_id: i32, parent_id: i32, types: i16) -> bool {
+        use crate::schema::categories::dsl::categories;
+
+        if parent_id == cat_id {
+            return false;
+        }
+        let _connection = establish_connection();
+        let _parent_types = categories
+            .filter(schema::categories::id.eq(parent_id))
+            .select(schema::categories::types)
+            .first::<i16>(&_connection);
+        if _parent_types.is_err() || _parent_types.expect("E") != types {
+            return false;
+        }
+        return cat_id == 0 || !Categories::get_descendant_ids(cat_id, types).contains(&parent_id);
+    }
+}
+
+#[derive(Serialize)]
+pub struct CatNode {
+    pub id:        i32,
+    pub name:      String,
+    pub slug:      String,
+    pub parent_id: Option<i32>,
+    pub types:     i16,
+    pub depth:     i16,
 }
 
 #[derive(Insertable)]
@@ -979,16 +1326,21 @@ pub struct NewCategories {
     pub types:       i16,
     pub slug:        String,
     pub now_u:       i16,
+    pub parent_id:   Option<i32>,
 }
 
+// parent_id = None переносит категорию на верхний уровень,
+// поэтому None здесь пишется как NULL
 #[derive(Queryable, Serialize, Deserialize, AsChangeset, Debug)]
 #[table_name="categories"]
+#[changeset_options(treat_none_as_null="true")]
 pub struct EditCategories {
     pub name:        String,
     pub description: Option<String>,
     pub position:    i16,
     pub image:       Option<String>,
     pub slug:        String,
+    pub parent_id:   Option<i32>,
 }
 
 #[derive(Debug, Serialize, Clone, Queryable, Identifiable, Associations)]
@@ -1012,9 +1364,28 @@ pub struct Item {
     pub types:       i16,
     pub slug:        String,
     pub now_u:       i16,
+    pub publish_at:   Option<chrono::NaiveDateTime>,
+    pub unpublish_at: Option<chrono::NaiveDateTime>,
+    pub content_md:   Option<String>,
+    pub category_id:  Option<i32>,
+    pub deleted_at:   Option<chrono::NaiveDateTime>,
 }
 
 impl Item {
+    // markdown хранится вместе с собранным из него html,
+    // html правится руками, только если markdown пустой
+    pub fn render_content(content: &Option<String>, content_md: &Option<String>) -> Option<String> {
+        use crate::utils::{render_markdown, sanitize_rich, sanitize_rich_opt};
+
+        return match content_md {
+            Some(_md) => Some(sanitize_rich(&render_markdown(_md))),
+            None => sanitize_rich_opt(content),
+        };
+    }
+    // цена для покупателя: price_acc - это сумма скидки
+    pub fn get_price_with_discount(&self) -> i32 {
+        return self.price - self.price_acc.unwrap_or(0);
+    }
     pub fn get_type(&self) -> String {
         return match self.types {
             1 => "блог".to_string(),
@@ -1116,102 +1487,809 @@ impl Item {
         }
     }
 
-    pub fn get_categories(&self) -> Result<Vec<SmallCat>, Error> {
-        use crate::schema::{
-            category::dsl::category,
-            categories::dsl::categories,
-        };
+    pub fn get_comments_count(&self) -> i64 {
+        return ItemComment::count_for_item(self.id);
+    }
+    pub fn get_revisions_count(&self) -> i64 {
+        return ItemRevision::count_for_item(self.id);
+    }
+    // до первой правки в истории ничего нет - сохраняем исходное
+    // состояние объекта, чтобы к нему тоже можно было вернуться.
+    pub fn create_initial_revision(&self) -> () {
+        if ItemRevision::count_for_item(self.id) == 0 {
+            NewItemRevision::create(self, self.user_id, 4);
+        }
+    }
+    // запись ревизии по текущему состоянию объекта в базе
+    pub fn save_revision(item_id: i32, user_id: i32, types: i16) -> () {
+        use crate::schema::items::dsl::items;
+
         let _connection = establish_connection();
-        let ids = category
-            .filter(schema::category::item_id.eq(self.id))
-            .filter(schema::category::types.eq(self.types))
-            .select(schema::category::categories_id)
-            .load::<i32>(&_connection)
-            .expect("E");
+        let _item = items
+            .filter(schema::items::id.eq(item_id))
+            .first::<Item>(&_connection)
+            .expect("E.");
+        NewItemRevision::create(&_item, user_id, types);
+    }
 
-        let _categories = categories
-            .filter(schema::categories::id.eq_any(ids))
-            .select((
-                schema::categories::name,
-                schema::categories::slug,
-                schema::categories::count
+    // публикация объекта: вручную или по расписанию.
+    // счетчики категорий и тегов растут только если объект был скрыт.
+    pub fn publish(&self) -> () {
+        if self.is_active || self.deleted_at.is_some() {
+            return;
+        }
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set((
+                schema::items::is_active.eq(true),
+                schema::items::publish_at.eq(None::<chrono::NaiveDateTime>),
             ))
-            .load::<SmallCat>(&_connection)
-            .expect("E");
-        return Ok(_categories);
+            .execute(&_connection)
+            .expect("E.");
+
+        for _category in self.get_categories_obj().expect("E").iter() {
+            diesel::update(_category)
+                .set(schema::categories::count.eq(_category.count + 1))
+                .execute(&_connection)
+                .expect("E.");
+        }
+        for _tag in self.get_tags_obj().expect("E").iter() {
+            diesel::update(_tag)
+                .set(schema::tags::count.eq(_tag.count + 1))
+                .execute(&_connection)
+                .expect("E.");
+        }
     }
-    pub fn get_categories_obj(&self) -> Result<Vec<Categories>, Error> {
-        use crate::schema::{
-            category::dsl::category,
-            categories::dsl::categories,
-        };
+    pub fn hide(&self) -> () {
+        if !self.is_active {
+            return;
+        }
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set((
+                schema::items::is_active.eq(false),
+                schema::items::unpublish_at.eq(None::<chrono::NaiveDateTime>),
+            ))
+            .execute(&_connection)
+            .expect("E.");
 
+        for _category in self.get_categories_obj().expect("E").iter() {
+            diesel::update(_category)
+                .set(schema::categories::count.eq(_category.count - 1))
+                .execute(&_connection)
+                .expect("E.");
+        }
+        for _tag in self.get_tags_obj().expect("E").iter() {
+            diesel::update(_tag)
+                .set(schema::tags::count.eq(_tag.count - 1))
+                .execute(&_connection)
+                .expect("E.");
+        }
+    }
+    // в корзину: объект скрывается (счетчики категорий и тегов
+    // уменьшаются как при скрытии), связи и файлы остаются на месте
+    pub fn move_to_trash(&self) -> () {
+        if self.deleted_at.is_some() {
+            return;
+        }
+        self.hide();
         let _connection = establish_connection();
-        let ids = category
-            .filter(schema::category::item_id.eq(self.id))
-            .filter(schema::category::types.eq(self.types))
-            .select(schema::category::categories_id)
-            .load::<i32>(&_connection)
-            .expect("E");
+        diesel::update(self)
+            .set((
+                schema::items::deleted_at.eq(chrono::Local::now().naive_utc() + chrono::Duration::hours(3)),
+                schema::items::publish_at.eq(None::<chrono::NaiveDateTime>),
+                schema::items::unpublish_at.eq(None::<chrono::NaiveDateTime>),
+            ))
+            .execute(&_connection)
+            .expect("E.");
+        Item::clear_related_cache(self.id);
+    }
+    // из корзины объект возвращается скрытым черновиком
+    pub fn restore_from_trash(&self) -> () {
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set(schema::items::deleted_at.eq(None::<chrono::NaiveDateTime>))
+            .execute(&_connection)
+            .expect("E.");
+    }
+    // когда объект будет стерт фоновой задачей
+    pub fn get_purge_date(&self) -> Option<chrono::NaiveDateTime> {
+        use crate::vars::trash_days;
 
-        let _categories = categories
-            .filter(schema::categories::id.eq_any(ids))
-            .load::<Categories>(&_connection)
-            .expect("E");
-        return Ok(_categories);
+        return self.deleted_at.map(|d| d + chrono::Duration::days(trash_days()));
     }
+    pub fn get_trash() -> Vec<Item> {
+        use crate::schema::items::dsl::items;
 
-    pub fn get_tags(&self) -> Result<Vec<SmallTag>, Error> {
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::deleted_at.is_not_null())
+            .order(schema::items::deleted_at.desc())
+            .load::<Item>(&_connection)
+            .expect("E.");
+    }
+    // объекты, пролежавшие в корзине дольше срока
+    pub fn get_items_for_purge(before: chrono::NaiveDateTime) -> Vec<Item> {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::deleted_at.le(before))
+            .load::<Item>(&_connection)
+            .expect("E.");
+    }
+    // окончательное удаление объекта со всеми связями и файлами.
+    // Счетчики не трогаем - они уменьшены при переносе в корзину.
+    pub fn purge(&self) -> () {
         use crate::schema::{
             tags_items::dsl::tags_items,
-            tags::dsl::tags,
+            category::dsl::category,
+            files::dsl::files,
+            serve_items::dsl::serve_items,
+            tech_categories_items::dsl::tech_categories_items,
+            item_comments::dsl::item_comments,
+            item_revisions::dsl::item_revisions,
+            item_previews::dsl::item_previews,
+            wiki_links::dsl::wiki_links,
+            help_votes::dsl::help_votes,
         };
+
         let _connection = establish_connection();
+        let _src_list = files
+            .filter(schema::files::item_id.eq(self.id))
+            .filter(schema::files::item_types.eq(self.types))
+            .select(schema::files::src)
+            .load::<String>(&_connection)
+            .expect("E");
+        // копии объекта ссылаются на те же файлы - такие
+        // файлы оставляем на диске
+        let _shared = files
+            .filter(schema::files::src.eq_any(&_src_list))
+            .filter(schema::files::item_id.ne(self.id))
+            .select(schema::files::src)
+            .load::<String>(&_connection)
+            .expect("E");
+        for f in _src_list.iter() {
+            if !_shared.contains(f) {
+                std::fs::remove_file(f).ok();
+            }
+        }
 
-        let _tag_items = tags_items
-            .filter(schema::tags_items::item_id.eq(&self.id))
-            .filter(schema::tags_items::types.eq(self.types))
-            .select(schema::tags_items::tag_id)
-            .load::<i32>(&_connection)
+        diesel::delete (
+            files
+                .filter(schema::files::item_id.eq(self.id))
+                .filter(schema::files::item_types.eq(self.types))
+            )
+            .execute(&_connection)
             .expect("E");
-        let _tags = tags
-            .filter(schema::tags::id.eq_any(_tag_items))
-            .select((schema::tags::name, schema::tags::count))
-            .load::<SmallTag>(&_connection)
+        diesel::delete (
+            tags_items
+                .filter(schema::tags_items::item_id.eq(self.id))
+                .filter(schema::tags_items::types.eq(self.types))
+            )
+            .execute(&_connection)
             .expect("E");
-        return Ok(_tags);
+        diesel::delete (
+            category
+                .filter(schema::category::item_id.eq(self.id))
+                .filter(schema::category::types.eq(self.types))
+            )
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete (
+            serve_items
+                .filter(schema::serve_items::item_id.eq(self.id))
+                .filter(schema::serve_items::types.eq(self.types))
+            )
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete (
+            tech_categories_items
+                .filter(schema::tech_categories_items::item_id.eq(self.id))
+                .filter(schema::tech_categories_items::types.eq(self.types))
+            )
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(item_comments.filter(schema::item_comments::item_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(item_revisions.filter(schema::item_revisions::item_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(item_previews.filter(schema::item_previews::item_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(wiki_links.filter(schema::wiki_links::item_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(help_votes.filter(schema::help_votes::item_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E");
+        Item::clear_related_cache(self.id);
+        diesel::delete(self).execute(&_connection).expect("E");
     }
-    pub fn get_tags_obj(&self) -> Result<Vec<Tag>, Error> {
+    // копия объекта скрытым черновиком: категории, теги, файлы,
+    // опции и тех категории - те же связи, что строит create_item.
+    // Файлы на диске не копируются, копия ссылается на них.
+    pub fn duplicate(&self, user_id: i32) -> Item {
         use crate::schema::{
+            category::dsl::category,
             tags_items::dsl::tags_items,
-            tags::dsl::tags,
+            files::dsl::files,
+            serve_items::dsl::serve_items,
+            tech_categories_items::dsl::tech_categories_items,
         };
+        use crate::models::{
+            NewTagItems,
+            NewFile,
+            NewServeItems,
+            NewTechCategoriesItem,
+        };
+        use crate::utils::get_unique_item_slug;
+
         let _connection = establish_connection();
+        let title = self.title.clone() + &" (копия)".to_string();
+        let slug = get_unique_item_slug(&self.slug, &title, self.types, 0);
+        let new_item = NewItem::create (
+            title,
+            self.description.clone(),
+            self.link.clone(),
+            self.image.clone(),
+            user_id,
+            self.position,
+            self.types,
+            slug,
+        );
+        let _item = diesel::insert_into(schema::items::table)
+            .values(&new_item)
+            .get_result::<Item>(&_connection)
+            .expect("E.");
+        let _item = diesel::update(&_item)
+            .set((
+                schema::items::content.eq(self.content.clone()),
+                schema::items::content_md.eq(self.content_md.clone()),
+                schema::items::price.eq(self.price),
+                schema::items::price_acc.eq(self.price_acc),
+                schema::items::category_id.eq(self.category_id),
+            ))
+            .get_result::<Item>(&_connection)
+            .expect("E.");
 
-        let _tag_items = tags_items
-            .filter(schema::tags_items::item_id.eq(&self.id))
+        for cat_id in self.get_category_ids().iter() {
+            let new_category = NewCategory {
+                categories_id: *cat_id,
+                item_id:       _item.id,
+                types:         self.types,
+            };
+            diesel::insert_into(schema::category::table)
+                .values(&new_category)
+                .execute(&_connection)
+                .expect("E.");
+        }
+
+        let _tag_ids = tags_items
+            .filter(schema::tags_items::item_id.eq(self.id))
             .filter(schema::tags_items::types.eq(self.types))
             .select(schema::tags_items::tag_id)
             .load::<i32>(&_connection)
             .expect("E");
-        let _tags = tags
-            .filter(schema::tags::id.eq_any(_tag_items))
-            .load::<Tag>(&_connection)
-            .expect("E");
-        return Ok(_tags);
-    }
+        for tag_id in _tag_ids.iter() {
+            let new_tag = NewTagItems {
+                tag_id:  *tag_id,
+                item_id: _item.id,
+                types:   self.types,
+                created: chrono::Local::now().naive_utc(),
+            };
+            diesel::insert_into(schema::tags_items::table)
+                .values(&new_tag)
+                .execute(&_connection)
+                .expect("E.");
+        }
 
-    pub fn get_blogs (
-        limit:    i64,
-        offset:   i64,
-        is_admin: bool
-    ) -> Vec<Blog> {
-        use crate::schema::items::dsl::items;
+        let _files = files
+            .filter(schema::files::item_id.eq(self.id))
+            .filter(schema::files::item_types.eq(self.types))
+            .select((
+                schema::files::types,
+                schema::files::src,
+                schema::files::description,
+                schema::files::position,
+            ))
+            .load::<(i16, String, Option<String>, i16)>(&_connection)
+            .expect("E");
+        for (types, src, description, position) in _files.into_iter() {
+            let mut new_file = NewFile::create (
+                user_id,
+                _item.id,
+                self.types,
+                types,
+                src,
+            );
+            new_file.description = description;
+            new_file.position = position;
+            diesel::insert_into(schema::files::table)
+                .values(&new_file)
+                .execute(&_connection)
+                .expect("E.");
+        }
 
-        let _connection = establish_connection();
-        if is_admin {
-             return items
-                .filter(schema::items::types.eq(1))
+        let _serve_ids = serve_items
+            .filter(schema::serve_items::item_id.eq(self.id))
+            .filter(schema::serve_items::types.eq(self.types))
+            .select(schema::serve_items::serve_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+        for serve_id in _serve_ids.iter() {
+            let new_serve = NewServeItems {
+                serve_id: *serve_id,
+                item_id:  _item.id,
+                types:    self.types,
+            };
+            diesel::insert_into(schema::serve_items::table)
+                .values(&new_serve)
+                .execute(&_connection)
+                .expect("E.");
+        }
+
+        // и открытые (1), и закрытые (2) тех категории
+        let _tech_cats = tech_categories_items
+            .filter(schema::tech_categories_items::item_id.eq(self.id))
+            .filter(schema::tech_categories_items::types.eq(self.types))
+            .select((
+                schema::tech_categories_items::category_id,
+                schema::tech_categories_items::is_active,
+            ))
+            .load::<(i32, i16)>(&_connection)
+            .expect("E");
+        for (cat_id, is_active) in _tech_cats.iter() {
+            let new_cat = NewTechCategoriesItem {
+                category_id: *cat_id,
+                item_id:     _item.id,
+                types:       self.types,
+                is_active:   *is_active,
+            };
+            diesel::insert_into(schema::tech_categories_items::table)
+                .values(&new_cat)
+                .execute(&_connection)
+                .expect("E.");
+        }
+
+        _item.create_initial_revision();
+        _item.update_wiki_links();
+        return _item;
+    }
+    // похожие объекты того же типа: общие теги весят больше общих
+    // категорий, свежие и популярные объекты поднимаются выше.
+    fn compute_related_items(&self) -> Vec<(i32, f64)> {
+        use crate::schema::{
+            tags_items::dsl::tags_items,
+            category::dsl::category,
+            items::dsl::items,
+        };
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let tag_ids = tags_items
+            .filter(schema::tags_items::item_id.eq(self.id))
+            .filter(schema::tags_items::types.eq(self.types))
+            .select(schema::tags_items::tag_id)
+            .load::<i32>(&_connection)
+            .expect("E.");
+        let cat_ids = category
+            .filter(schema::category::item_id.eq(self.id))
+            .filter(schema::category::types.eq(self.types))
+            .select(schema::category::categories_id)
+            .load::<i32>(&_connection)
+            .expect("E.");
+
+        // (общих тегов, общих категорий)
+        let mut overlap: HashMap<i32, (i32, i32)> = HashMap::new();
+        let _tag_items = tags_items
+            .filter(schema::tags_items::tag_id.eq_any(tag_ids))
+            .filter(schema::tags_items::types.eq(self.types))
+            .filter(schema::tags_items::item_id.ne(self.id))
+            .select(schema::tags_items::item_id)
+            .load::<i32>(&_connection)
+            .expect("E.");
+        for id in _tag_items.iter() {
+            overlap.entry(*id).or_insert((0, 0)).0 += 1;
+        }
+        let _cat_items = category
+            .filter(schema::category::categories_id.eq_any(cat_ids))
+            .filter(schema::category::types.eq(self.types))
+            .filter(schema::category::item_id.ne(self.id))
+            .select(schema::category::item_id)
+            .load::<i32>(&_connection)
+            .expect("E.");
+        for id in _cat_items.iter() {
+            overlap.entry(*id).or_insert((0, 0)).1 += 1;
+        }
+
+        let ids: Vec<i32> = overlap.keys().cloned().collect();
+        let _candidates = items
+            .filter(schema::items::id.eq_any(ids))
+            .filter(schema::items::is_active.eq(true))
+            .select((
+                schema::items::id,
+                schema::items::created,
+                schema::items::view,
+                schema::items::seconds,
+            ))
+            .load::<(i32, chrono::NaiveDateTime, i32, i32)>(&_connection)
+            .expect("E.");
+
+        let now = chrono::Local::now().naive_utc() + Duration::hours(3);
+        let mut scores = Vec::new();
+        for (id, created, view, seconds) in _candidates.iter() {
+            let (tags_count, cats_count) = overlap[id];
+            let days = (now - *created).num_days().max(0) as f64;
+            let recency = 1.0 / (1.0 + days / 30.0);
+            let engagement = 0.5 * (1.0 + *view as f64).ln() + 0.5 * (1.0 + *seconds as f64 / 60.0).ln();
+            let score = (3.0 * tags_count as f64 + 2.0 * cats_count as f64) * (1.0 + recency) + engagement;
+            scores.push((*id, score));
+        }
+        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scores.truncate(10);
+        return scores;
+    }
+    // похожие объекты берутся из кэша, если он моложе суток,
+    // иначе пересчитываются. Кэш сбрасывается при смене тегов.
+    // Пустой результат тоже кэшируется: строкой-меткой, где
+    // related_id - сам объект. Такая строка в список не попадает.
+    pub fn get_related_items(&self, limit: usize) -> Vec<RelatedItem> {
+        use crate::schema::{
+            related_items::dsl::related_items,
+            items::dsl::items,
+        };
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let now = chrono::Local::now().naive_utc() + Duration::hours(3);
+        let _cached = related_items
+            .filter(schema::related_items::item_id.eq(self.id))
+            .order(schema::related_items::score.desc())
+            .select((schema::related_items::related_id, schema::related_items::created))
+            .load::<(i32, chrono::NaiveDateTime)>(&_connection)
+            .expect("E.");
+
+        let related_ids: Vec<i32>;
+        if _cached.len() > 0 && _cached[0].1 > now - Duration::hours(24) {
+            related_ids = _cached.into_iter().map(|c| c.0).filter(|id| *id != self.id).collect();
+        }
+        else {
+            Item::clear_related_cache(self.id);
+            let mut scores = self.compute_related_items();
+            if scores.is_empty() {
+                scores.push((self.id, -1.0));
+            }
+            for (related_id, score) in scores.iter() {
+                let new_related = NewRelatedItem {
+                    item_id:    self.id,
+                    related_id: *related_id,
+                    score:      *score,
+                    created:    now,
+                };
+                diesel::insert_into(schema::related_items::table)
+                    .values(&new_related)
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+            related_ids = scores.into_iter().map(|s| s.0).filter(|id| *id != self.id).collect();
+        }
+
+        let mut list = Vec::new();
+        for related_id in related_ids.iter().take(limit) {
+            let _item = items
+                .filter(schema::items::id.eq(related_id))
+                .filter(schema::items::is_active.eq(true))
+                .first::<Item>(&_connection);
+            if _item.is_ok() {
+                list.push(RelatedItem::from_item(&_item.expect("E.")));
+            }
+        }
+        return list;
+    }
+    // кэш объекта и все списки, где объект сам был похожим
+    pub fn clear_related_cache(item_id: i32) -> () {
+        use crate::schema::related_items::dsl::related_items;
+
+        let _connection = establish_connection();
+        diesel::delete (
+            related_items
+                .filter(schema::related_items::item_id.eq(item_id))
+                .or_filter(schema::related_items::related_id.eq(item_id))
+            )
+            .execute(&_connection)
+            .expect("E.");
+    }
+    // статья вики по тексту из [[...]]: сначала slug, потом
+    // название без учета регистра. Скрытые статьи не находятся.
+    pub fn find_wiki_article(target: &str) -> Option<Item> {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        // % и _ в названии не должны работать как шаблон
+        let title = target
+            .replace("\\", "\\\\")
+            .replace("%", "\\%")
+            .replace("_", "\\_");
+        return items
+            .filter(schema::items::types.eq(4))
+            .filter(schema::items::is_active.eq(true))
+            .filter(schema::items::deleted_at.is_null())
+            .filter(schema::items::slug.eq(target).or(schema::items::title.ilike(&title)))
+            .order(schema::items::id.asc())
+            .first::<Item>(&_connection)
+            .ok();
+    }
+    // таблица ссылок статьи пересобирается при каждом сохранении текста
+    pub fn update_wiki_links(&self) -> () {
+        use crate::schema::wiki_links::dsl::wiki_links;
+        use crate::utils::parse_wiki_links;
+        use chrono::Duration;
+
+        if self.types != 4 {
+            return;
+        }
+        let _connection = establish_connection();
+        diesel::delete(wiki_links.filter(schema::wiki_links::item_id.eq(self.id)))
+            .execute(&_connection)
+            .expect("E.");
+        let text = match &self.content {
+            Some(_content) => _content,
+            None => return,
+        };
+        let now = chrono::Local::now().naive_utc() + Duration::hours(3);
+        for target in parse_wiki_links(text).into_iter() {
+            let new_link = NewWikiLink {
+                item_id:   self.id,
+                target_id: Item::find_wiki_article(&target).map(|i| i.id),
+                target:    target.chars().take(200).collect(),
+                created:   now,
+            };
+            diesel::insert_into(schema::wiki_links::table)
+                .values(&new_link)
+                .execute(&_connection)
+                .expect("E.");
+        }
+    }
+    // "что ссылается сюда". Ищем и по тексту ссылки, чтобы
+    // подхватить ссылки, написанные раньше самой статьи.
+    pub fn get_backlinks(&self) -> Vec<RelatedItem> {
+        use crate::schema::{
+            wiki_links::dsl::wiki_links,
+            items::dsl::items,
+        };
+
+        let _connection = establish_connection();
+        let names = vec![self.title.trim().to_lowercase(), self.slug.clone()];
+        let _ids = wiki_links
+            .filter(schema::wiki_links::target_id.eq(self.id))
+            .or_filter(schema::wiki_links::target.eq_any(&names))
+            .select(schema::wiki_links::item_id)
+            .distinct()
+            .load::<i32>(&_connection)
+            .expect("E.");
+        let _items = items
+            .filter(schema::items::id.eq_any(&_ids))
+            .filter(schema::items::id.ne(self.id))
+            .filter(schema::items::types.eq(4))
+            .filter(schema::items::is_active.eq(true))
+            .filter(schema::items::deleted_at.is_null())
+            .order(schema::items::title.asc())
+            .load::<Item>(&_connection)
+            .expect("E.");
+        return _items.iter().map(|i| RelatedItem::from_item(i)).collect();
+    }
+    // id категорий, к которым привязан объект
+    pub fn get_category_ids(&self) -> Vec<i32> {
+        use crate::schema::category::dsl::category;
+
+        let _connection = establish_connection();
+        return category
+            .filter(schema::category::item_id.eq(self.id))
+            .filter(schema::category::types.eq(self.types))
+            .order(schema::category::id.asc())
+            .select(schema::category::categories_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+    }
+    // основная категория. Если она не выбрана - первая привязанная
+    pub fn get_main_category_id(&self) -> Option<i32> {
+        if self.category_id.is_some() {
+            return self.category_id;
+        }
+        return self.get_category_ids().into_iter().nth(0);
+    }
+    pub fn get_main_category_slug(&self) -> String {
+        use crate::schema::categories::dsl::categories;
+
+        let _cat_id = self.get_main_category_id();
+        if _cat_id.is_none() {
+            return "".to_string();
+        }
+        let _connection = establish_connection();
+        return categories
+            .filter(schema::categories::id.eq(_cat_id.unwrap()))
+            .select(schema::categories::slug)
+            .first::<String>(&_connection)
+            .unwrap_or("".to_string());
+    }
+    // канонический адрес объекта - через основную категорию.
+    // Все ссылки на объект надо строить через него.
+    pub fn get_url(&self) -> String {
+        return get_item_url(self.types, &self.get_main_category_slug(), &self.slug);
+    }
+    // то же для карточек списков и ссылок назад/вперед,
+    // в которых нет основной категории
+    pub fn get_url_by_id(item_id: i32) -> String {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::id.eq(item_id))
+            .first::<Item>(&_connection)
+            .map(|i| i.get_url())
+            .unwrap_or_default();
+    }
+    // привязан ли объект к категории с таким slug
+    pub fn has_category_slug(&self, cat_slug: &str) -> bool {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        return categories
+            .filter(schema::categories::id.eq_any(self.get_category_ids()))
+            .filter(schema::categories::slug.eq(cat_slug))
+            .select(schema::categories::id)
+            .first::<i32>(&_connection)
+            .is_ok();
+    }
+    // выбираем основную категорию после правки привязок:
+    // выбранную в форме, если объект к ней привязан, иначе
+    // прежнюю, иначе первую из привязанных.
+    pub fn update_main_category(&self, preferred: i32) -> () {
+        let ids = self.get_category_ids();
+        let main_id = if ids.contains(&preferred) {
+            Some(preferred)
+        }
+        else if self.category_id.is_some() && ids.contains(&self.category_id.unwrap()) {
+            self.category_id
+        }
+        else {
+            ids.into_iter().nth(0)
+        };
+        if main_id == self.category_id {
+            return;
+        }
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set(schema::items::category_id.eq(main_id))
+            .execute(&_connection)
+            .expect("E");
+    }
+    pub fn is_scheduled(&self) -> bool {
+        return self.publish_at.is_some() || self.unpublish_at.is_some();
+    }
+    // скрытые объекты, время публикации которых уже наступило
+    pub fn get_items_for_publish(now: chrono::NaiveDateTime) -> Vec<Item> {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::is_active.eq(false))
+            .filter(schema::items::publish_at.le(now))
+            .load::<Item>(&_connection)
+            .expect("E.");
+    }
+    // опубликованные объекты, которые пора скрыть
+    pub fn get_items_for_hide(now: chrono::NaiveDateTime) -> Vec<Item> {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::is_active.eq(true))
+            .filter(schema::items::unpublish_at.le(now))
+            .load::<Item>(&_connection)
+            .expect("E.");
+    }
+
+    pub fn get_categories(&self) -> Result<Vec<SmallCat>, Error> {
+        use crate::schema::{
+            category::dsl::category,
+            categories::dsl::categories,
+        };
+        let _connection = establish_connection();
+        let ids = category
+            .filter(schema::category::item_id.eq(self.id))
+            .filter(schema::category::types.eq(self.types))
+            .select(schema::category::categories_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+
+        let _categories = categories
+            .filter(schema::categories::id.eq_any(ids))
+            .select((
+                schema::categories::name,
+                schema::categories::slug,
+                schema::categories::count
+            ))
+            .load::<SmallCat>(&_connection)
+            .expect("E");
+        return Ok(_categories);
+    }
+    pub fn get_categories_obj(&self) -> Result<Vec<Categories>, Error> {
+        use crate::schema::{
+            category::dsl::category,
+            categories::dsl::categories,
+        };
+
+        let _connection = establish_connection();
+        let ids = category
+            .filter(schema::category::item_id.eq(self.id))
+            .filter(schema::category::types.eq(self.types))
+            .select(schema::category::categories_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+
+        let _categories = categories
+            .filter(schema::categories::id.eq_any(ids))
+            .load::<Categories>(&_connection)
+            .expect("E");
+        return Ok(_categories);
+    }
+
+    pub fn get_tags(&self) -> Result<Vec<SmallTag>, Error> {
+        use crate::schema::{
+            tags_items::dsl::tags_items,
+            tags::dsl::tags,
+        };
+        let _connection = establish_connection();
+
+        let _tag_items = tags_items
+            .filter(schema::tags_items::item_id.eq(&self.id))
+            .filter(schema::tags_items::types.eq(self.types))
+            .select(schema::tags_items::tag_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+        let _tags = tags
+            .filter(schema::tags::id.eq_any(_tag_items))
+            .select((schema::tags::name, schema::tags::count))
+            .load::<SmallTag>(&_connection)
+            .expect("E");
+        return Ok(_tags);
+    }
+    pub fn get_tags_obj(&self) -> Result<Vec<Tag>, Error> {
+        use crate::schema::{
+            tags_items::dsl::tags_items,
+            tags::dsl::tags,
+        };
+        let _connection = establish_connection();
+
+        let _tag_items = tags_items
+            .filter(schema::tags_items::item_id.eq(&self.id))
+            .filter(schema::tags_items::types.eq(self.types))
+            .select(schema::tags_items::tag_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+        let _tags = tags
+            .filter(schema::tags::id.eq_any(_tag_items))
+            .load::<Tag>(&_connection)
+            .expect("E");
+        return Ok(_tags);
+    }
+
+    pub fn get_blogs (
+        limit:    i64,
+        offset:   i64,
+        is_admin: bool
+    ) -> Vec<Blog> {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        if is_admin {
+             return items
+                .filter(schema::items::types.eq(1))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -1260,6 +2338,7 @@ impl Item {
                 .filter(schema::items::title.ilike(&q))
                 .or_filter(schema::items::description.ilike(&q))
                 .or_filter(schema::items::content.ilike(&q))
+                .filter(schema::items::deleted_at.is_null())
                 .filter(schema::items::types.eq(1))
                 .order(schema::items::created.desc())
                 .limit(limit)
@@ -1310,6 +2389,7 @@ impl Item {
         if is_admin {
              return items
                 .filter(schema::items::types.eq(2))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -1357,6 +2437,7 @@ impl Item {
                 .filter(schema::items::title.ilike(&q))
                 .or_filter(schema::items::description.ilike(&q))
                 .or_filter(schema::items::content.ilike(&q))
+                .filter(schema::items::deleted_at.is_null())
                 .filter(schema::items::types.eq(2))
                 .order(schema::items::created.desc())
                 .limit(limit)
@@ -1405,6 +2486,7 @@ impl Item {
           if is_admin {
                return items
                   .filter(schema::items::types.eq(3))
+                  .filter(schema::items::deleted_at.is_null())
                   .order(schema::items::created.desc())
                   .limit(limit)
                   .offset(offset)
@@ -1455,6 +2537,7 @@ impl Item {
                   .filter(schema::items::title.ilike(&q))
                   .or_filter(schema::items::description.ilike(&q))
                   .or_filter(schema::items::content.ilike(&q))
+                  .filter(schema::items::deleted_at.is_null())
                   .filter(schema::items::types.eq(3))
                   .order(schema::items::created.desc())
                   .limit(limit)
@@ -1507,6 +2590,7 @@ impl Item {
           if is_admin {
                return items
                   .filter(schema::items::types.eq(5))
+                  .filter(schema::items::deleted_at.is_null())
                   .order(schema::items::created.desc())
                   .limit(limit)
                   .offset(offset)
@@ -1553,6 +2637,7 @@ impl Item {
                   .filter(schema::items::title.ilike(&q))
                   .or_filter(schema::items::description.ilike(&q))
                   .or_filter(schema::items::content.ilike(&q))
+                  .filter(schema::items::deleted_at.is_null())
                   .filter(schema::items::types.eq(5))
                   .order(schema::items::created.desc())
                   .limit(limit)
@@ -1601,6 +2686,7 @@ impl Item {
           if is_admin {
                return items
                   .filter(schema::items::types.eq(4))
+                  .filter(schema::items::deleted_at.is_null())
                   .order(schema::items::created.desc())
                   .limit(limit)
                   .offset(offset)
@@ -1649,6 +2735,7 @@ impl Item {
                   .filter(schema::items::title.ilike(&q))
                   .or_filter(schema::items::description.ilike(&q))
                   .or_filter(schema::items::content.ilike(&q))
+                  .filter(schema::items::deleted_at.is_null())
                   .filter(schema::items::types.eq(4))
                   .order(schema::items::created.desc())
                   .limit(limit)
@@ -1699,6 +2786,7 @@ impl Item {
         if is_admin {
              return items
                 .filter(schema::items::types.eq(6))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -1742,6 +2830,7 @@ impl Item {
                 .filter(schema::items::title.ilike(&q))
                 .or_filter(schema::items::description.ilike(&q))
                 .or_filter(schema::items::content.ilike(&q))
+                .filter(schema::items::deleted_at.is_null())
                 .filter(schema::items::types.eq(6))
                 .order(schema::items::created.desc())
                 .limit(limit)
@@ -1811,6 +2900,7 @@ impl Item {
         if is_admin {
             return items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -1884,6 +2974,7 @@ impl Item {
         if is_admin {
             return items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -1955,6 +3046,7 @@ impl Item {
         if is_admin {
             return items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -2030,6 +3122,7 @@ impl Item {
         if is_admin {
             return items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -2103,6 +3196,7 @@ impl Item {
         if is_admin {
             return items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -2174,6 +3268,7 @@ impl Item {
         if is_admin {
             return items
                 .filter(schema::items::id.eq_any(ids))
+                .filter(schema::items::deleted_at.is_null())
                 .order(schema::items::created.desc())
                 .limit(limit)
                 .offset(offset)
@@ -2292,6 +3387,218 @@ impl Item {
     }
 }
 
+// блок "похожее" на странице объекта
+#[derive(Debug, Serialize)]
+pub struct RelatedItem {
+    pub id:       i32,
+    pub title:    String,
+    pub slug:     String,
+    pub image:    Option<String>,
+    pub types:    i16,
+    pub cat_slug: String,
+}
+
+impl RelatedItem {
+    pub fn from_item(item: &Item) -> RelatedItem {
+        RelatedItem {
+            id:       item.id,
+            title:    item.title.clone(),
+            slug:     item.slug.clone(),
+            image:    item.image.clone(),
+            types:    item.types,
+            cat_slug: item.get_main_category_slug(),
+        }
+    }
+    pub fn get_image(&self) -> String {
+        if self.image.is_some() {
+            return self.image.as_deref().unwrap().to_string();
+        }
+        else {
+            return "/static/images/img.jpg".to_string();
+        }
+    }
+    pub fn get_url(&self) -> String {
+        return get_item_url(self.types, &self.cat_slug, &self.slug);
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="related_items"]
+pub struct NewRelatedItem {
+    pub item_id:    i32,
+    pub related_id: i32,
+    pub score:      f64,
+    pub created:    chrono::NaiveDateTime,
+}
+
+// голос "помогла ли статья" для объектов справки (types 6).
+// Один куки пользователь - один голос, повторный голос его меняет.
+#[derive(Debug, Serialize, Queryable, Identifiable)]
+pub struct HelpVote {
+    pub id:         i32,
+    pub item_id:    i32,
+    pub c_user_id:  i32,
+    pub is_helpful: bool,
+    pub comment:    Option<String>,
+    pub created:    chrono::NaiveDateTime,
+}
+
+impl HelpVote {
+    pub fn vote(item_id: i32, c_user_id: i32, is_helpful: bool, comment: Option<String>) -> () {
+        use crate::schema::help_votes::dsl::help_votes;
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let now = chrono::Local::now().naive_utc() + Duration::hours(3);
+        let _vote = help_votes
+            .filter(schema::help_votes::item_id.eq(item_id))
+            .filter(schema::help_votes::c_user_id.eq(c_user_id))
+            .first::<HelpVote>(&_connection);
+        if _vote.is_ok() {
+            let _vote = _vote.expect("E");
+            // без нового отзыва оставляем прежний
+            let comment = if comment.is_some() { comment } else { _vote.comment.clone() };
+            diesel::update(&_vote)
+                .set((
+                    schema::help_votes::is_helpful.eq(is_helpful),
+                    schema::help_votes::comment.eq(comment),
+                    schema::help_votes::created.eq(now),
+                ))
+                .execute(&_connection)
+                .expect("E.");
+        }
+        else {
+            let new_vote = NewHelpVote {
+                item_id:    item_id,
+                c_user_id:  c_user_id,
+                is_helpful: is_helpful,
+                comment:    comment,
+                created:    now,
+            };
+            diesel::insert_into(schema::help_votes::table)
+                .values(&new_vote)
+                .execute(&_connection)
+                .expect("E.");
+        }
+    }
+    // голос куки пользователя по статье, если он уже голосовал
+    pub fn get_user_vote(item_id: i32, c_user_id: i32) -> Option<bool> {
+        use crate::schema::help_votes::dsl::help_votes;
+
+        let _connection = establish_connection();
+        return help_votes
+            .filter(schema::help_votes::item_id.eq(item_id))
+            .filter(schema::help_votes::c_user_id.eq(c_user_id))
+            .select(schema::help_votes::is_helpful)
+            .first::<bool>(&_connection)
+            .ok();
+    }
+    // отчет по статьям справки: сверху те, что чаще всего
+    // не помогают. Доля "нет" сглажена (+1 / +2), чтобы статья
+    // с одним голосом "нет" не обгоняла статью с сотней.
+    pub fn get_stats() -> Vec<HelpVoteStat> {
+        use crate::schema::{
+            help_votes::dsl::help_votes,
+            items::dsl::items,
+        };
+
+        let _connection = establish_connection();
+        let _votes = help_votes
+            .select((
+                schema::help_votes::item_id,
+                schema::help_votes::is_helpful,
+                schema::help_votes::comment.is_not_null(),
+            ))
+            .load::<(i32, bool, bool)>(&_connection)
+            .expect("E.");
+
+        let mut counts: HashMap<i32, (i32, i32, i32)> = HashMap::new();
+        for (item_id, is_helpful, has_comment) in _votes.iter() {
+            let c = counts.entry(*item_id).or_insert((0, 0, 0));
+            if *is_helpful {
+                c.0 += 1;
+            }
+            else {
+                c.1 += 1;
+            }
+            if *has_comment {
+                c.2 += 1;
+            }
+        }
+
+        let ids: Vec<i32> = counts.keys().cloned().collect();
+        let _items = items
+            .filter(schema::items::id.eq_any(&ids))
+            .filter(schema::items::types.eq(6))
+            .filter(schema::items::deleted_at.is_null())
+            .load::<Item>(&_connection)
+            .expect("E.");
+
+        let mut list = Vec::new();
+        for _item in _items.iter() {
+            let (yes, no, comments) = counts[&_item.id];
+            let url = match _item.get_main_category_id() {
+                Some(_cat_id) => Categories::get_category_url(_cat_id, 6),
+                None => "/".to_string(),
+            };
+            list.push(HelpVoteStat {
+                item_id:  _item.id,
+                title:    _item.title.clone(),
+                url:      url,
+                yes:      yes,
+                no:       no,
+                comments: comments,
+                score:    (no as f64 + 1.0) / ((yes + no) as f64 + 2.0),
+            });
+        }
+        list.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap().then(b.no.cmp(&a.no)));
+        return list;
+    }
+    // отзывы по статье, новые сверху
+    pub fn get_item_votes(item_id: i32, limit: i64, offset: i64) -> Vec<HelpVote> {
+        use crate::schema::help_votes::dsl::help_votes;
+
+        let _connection = establish_connection();
+        return help_votes
+            .filter(schema::help_votes::item_id.eq(item_id))
+            .order(schema::help_votes::created.desc())
+            .limit(limit)
+            .offset(offset)
+            .load::<HelpVote>(&_connection)
+            .expect("E.");
+    }
+}
+
+#[derive(Serialize)]
+pub struct HelpVoteStat {
+    pub item_id:  i32,
+    pub title:    String,
+    pub url:      String, // категория справки, где стоит статья
+    pub yes:      i32,
+    pub no:       i32,
+    pub comments: i32,
+    pub score:    f64,    // сглаженная доля голосов "нет"
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="help_votes"]
+pub struct NewHelpVote {
+    pub item_id:    i32,
+    pub c_user_id:  i32,
+    pub is_helpful: bool,
+    pub comment:    Option<String>,
+    pub created:    chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="wiki_links"]
+pub struct NewWikiLink {
+    pub item_id:   i32,
+    pub target:    String,
+    pub target_id: Option<i32>,
+    pub created:   chrono::NaiveDateTime,
+}
+
 #[derive(Serialize, Insertable)]
 #[table_name="items"]
 pub struct NewItem {
@@ -2390,37 +3697,578 @@ pub struct NewCategory {
     pub types:         i16,
 }
 
+///////////
+// types:
+// 1. обычный
+// 2. измененный
+// 3. удаленный
+
+// status:
+// 1. на модерации
+// 2. опубликован
+// 3. отклонен
 #[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
 pub struct ItemComment {
-    pub id:        i32,
-    pub comment:   String,
-    pub blog_id:   i32,
-    pub user_id:   i32,
-    pub parent_id: Option<i32>,
-    pub created:   chrono::NaiveDateTime,
+    pub id:         i32,
+    pub comment:    String,
+    pub item_id:    i32,
+    pub user_id:    Option<i32>,
+    pub parent_id:  Option<i32>,
+    pub created:    chrono::NaiveDateTime,
+    pub c_user_id:  Option<i32>,
+    pub username:   String,
+    pub types:      i16,
+    pub status:     i16,
+    pub spam_score: i16,
+}
+
+impl ItemComment {
+    pub fn get_comments_list (
+        item_id: i32,
+        page:    i32,
+        limit:   i32
+    ) -> (Vec<ItemComment>, i32) {
+        let mut next_page_number = 0;
+        let have_next: i32;
+        let object_list: Vec<ItemComment>;
+
+        if page > 1 {
+            let step = (page - 1) * 20;
+            have_next = page * limit + 1;
+            object_list = ItemComment::get_comments(item_id, limit.into(), step.into());
+        }
+        else {
+            have_next = limit + 1;
+            object_list = ItemComment::get_comments(item_id, limit.into(), 0);
+        }
+        if ItemComment::get_comments(item_id, 1, have_next.into()).len() > 0 {
+            next_page_number = page + 1;
+        }
+
+        return (object_list, next_page_number);
+    }
+    pub fn get_comments(item_id: i32, limit: i64, offset: i64) -> Vec<ItemComment> {
+        // только корневые комментарии, ответы подгружаются через get_replies
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        return item_comments
+            .filter(schema::item_comments::item_id.eq(item_id))
+            .filter(schema::item_comments::parent_id.is_null())
+            .filter(schema::item_comments::status.eq(2))
+            .order(schema::item_comments::created.asc())
+            .limit(limit)
+            .offset(offset)
+            .load::<ItemComment>(&_connection)
+            .expect("E.");
+    }
+    pub fn get_replies(&self) -> Vec<ItemComment> {
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        return item_comments
+            .filter(schema::item_comments::parent_id.eq(self.id))
+            .filter(schema::item_comments::status.eq(2))
+            .order(schema::item_comments::created.asc())
+            .load::<ItemComment>(&_connection)
+            .expect("E.");
+    }
+    pub fn count_replies(&self) -> i64 {
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        return item_comments
+            .filter(schema::item_comments::parent_id.eq(self.id))
+            .count()
+            .get_result::<i64>(&_connection)
+            .expect("E.");
+    }
+    pub fn count_for_item(item_id: i32) -> i64 {
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        return item_comments
+            .filter(schema::item_comments::item_id.eq(item_id))
+            .filter(schema::item_comments::types.ne(3))
+            .filter(schema::item_comments::status.eq(2))
+            .count()
+            .get_result::<i64>(&_connection)
+            .expect("E.");
+    }
+    pub fn get_username(&self) -> String {
+        if self.user_id.is_some() {
+            use crate::schema::users::dsl::users;
+
+            let _connection = establish_connection();
+            let _username = users
+                .filter(schema::users::id.eq(self.user_id.unwrap()))
+                .select(schema::users::username)
+                .first::<String>(&_connection);
+            if _username.is_ok() {
+                return _username.expect("E.");
+            }
+        }
+        if self.username.is_empty() {
+            return "Аноним".to_string();
+        }
+        return self.username.clone();
+    }
+    pub fn get_comment(&self) -> String {
+        if self.is_deleted() {
+            return "Комментарий удален".to_string();
+        }
+        return self.comment.clone();
+    }
+    pub fn is_deleted(&self) -> bool {
+        return self.types == 3;
+    }
+    pub fn is_edited(&self) -> bool {
+        return self.types == 2;
+    }
+    // автор комментария - зарегистрированный пользователь
+    // или аноним, опознанный по куки
+    pub fn is_owner(&self, user_id: i32, c_user_id: i32) -> bool {
+        if self.user_id.is_some() {
+            return self.user_id.unwrap() == user_id;
+        }
+        return c_user_id != 0 && self.c_user_id == Some(c_user_id);
+    }
+    pub fn is_pending(&self) -> bool {
+        return self.status == 1;
+    }
+    pub fn get_status(&self) -> String {
+        return match self.status {
+            1 => "На модерации".to_string(),
+            2 => "Опубликован".to_string(),
+            3 => "Отклонен".to_string(),
+            _ => "Непонятно".to_string(),
+        };
+    }
+    pub fn get_item(&self) -> Item {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::id.eq(self.item_id))
+            .first::<Item>(&_connection)
+            .expect("E.");
+    }
+
+    // очередь модерации: сначала самые подозрительные
+    pub fn get_moderation_list (
+        status: i16,
+        page:   i32,
+        limit:  i32
+    ) -> (Vec<ItemComment>, i32) {
+        let mut next_page_number = 0;
+        let have_next: i32;
+        let object_list: Vec<ItemComment>;
+
+        if page > 1 {
+            let step = (page - 1) * 20;
+            have_next = page * limit + 1;
+            object_list = ItemComment::get_moderation_comments(status, limit.into(), step.into());
+        }
+        else {
+            have_next = limit + 1;
+            object_list = ItemComment::get_moderation_comments(status, limit.into(), 0);
+        }
+        if ItemComment::get_moderation_comments(status, 1, have_next.into()).len() > 0 {
+            next_page_number = page + 1;
+        }
+
+        return (object_list, next_page_number);
+    }
+    pub fn get_moderation_comments(status: i16, limit: i64, offset: i64) -> Vec<ItemComment> {
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        return item_comments
+            .filter(schema::item_comments::status.eq(status))
+            .order((
+                schema::item_comments::spam_score.desc(),
+                schema::item_comments::created.desc(),
+            ))
+            .limit(limit)
+            .offset(offset)
+            .load::<ItemComment>(&_connection)
+            .expect("E.");
+    }
+    pub fn count_for_status(status: i16) -> i64 {
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        return item_comments
+            .filter(schema::item_comments::status.eq(status))
+            .count()
+            .get_result::<i64>(&_connection)
+            .expect("E.");
+    }
+    pub fn set_status(&self, status: i16) -> () {
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set(schema::item_comments::status.eq(status))
+            .execute(&_connection)
+            .expect("E.");
+    }
+    // у комментария есть ответы - помечаем удаленным, чтобы не рвать ветку.
+    // если ответов нет - удаляем насовсем.
+    pub fn remove(&self) -> () {
+        let _connection = establish_connection();
+        if self.count_replies() > 0 {
+            diesel::update(self)
+                .set(schema::item_comments::types.eq(3))
+                .execute(&_connection)
+                .expect("E.");
+        }
+        else {
+            diesel::delete(self).execute(&_connection).expect("E.");
+        }
+    }
 }
 
 #[derive(Serialize, Insertable)]
 #[table_name="item_comments"]
 pub struct NewItemComment {
-    pub comment:   String,
-    pub item_id:   i32,
-    pub user_id:   i32,
-    pub parent_id: Option<i32>,
-    pub created:   chrono::NaiveDateTime,
+    pub comment:    String,
+    pub item_id:    i32,
+    pub user_id:    Option<i32>,
+    pub parent_id:  Option<i32>,
+    pub created:    chrono::NaiveDateTime,
+    pub c_user_id:  Option<i32>,
+    pub username:   String,
+    pub types:      i16,
+    pub status:     i16,
+    pub spam_score: i16,
 }
 
 impl NewItemComment {
-    pub fn new (comment: String, item_id: i32,
-        user_id: i32, parent_id: Option<i32>) -> Self {
+    pub fn new (
+        comment:    String,
+        item_id:    i32,
+        user_id:    Option<i32>,
+        c_user_id:  Option<i32>,
+        username:   String,
+        parent_id:  Option<i32>,
+        status:     i16,
+        spam_score: i16
+    ) -> Self {
         use chrono::Duration;
 
         NewItemComment {
-            comment:   comment,
-            item_id:   item_id,
-            user_id:   user_id,
-            parent_id: parent_id,
-            created:   chrono::Local::now().naive_utc() + Duration::hours(3),
+            comment:    comment,
+            item_id:    item_id,
+            user_id:    user_id,
+            parent_id:  parent_id,
+            created:    chrono::Local::now().naive_utc() + Duration::hours(3),
+            c_user_id:  c_user_id,
+            username:   username,
+            types:      1,
+            status:     status,
+            spam_score: spam_score,
+        }
+    }
+}
+
+#[derive(Queryable, Serialize, Deserialize, AsChangeset, Debug)]
+#[table_name="item_comments"]
+pub struct EditItemComment {
+    pub comment: String,
+    pub types:   i16,
+}
+
+///////////
+// types:
+// 1. правка объекта
+// 2. правка текста
+// 3. восстановление из истории
+// 4. исходное состояние
+#[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
+pub struct ItemRevision {
+    pub id:          i32,
+    pub item_id:     i32,
+    pub user_id:     i32,
+    pub title:       String,
+    pub description: Option<String>,
+    pub content:     Option<String>,
+    pub slug:        String,
+    pub types:       i16,
+    pub created:     chrono::NaiveDateTime,
+    pub content_md:  Option<String>,
+}
+
+impl ItemRevision {
+    pub fn get_revisions_list (
+        item_id: i32,
+        page:    i32,
+        limit:   i32
+    ) -> (Vec<ItemRevision>, i32) {
+        let mut next_page_number = 0;
+        let have_next: i32;
+        let object_list: Vec<ItemRevision>;
+
+        if page > 1 {
+            let step = (page - 1) * 20;
+            have_next = page * limit + 1;
+            object_list = ItemRevision::get_revisions(item_id, limit.into(), step.into());
+        }
+        else {
+            have_next = limit + 1;
+            object_list = ItemRevision::get_revisions(item_id, limit.into(), 0);
+        }
+        if ItemRevision::get_revisions(item_id, 1, have_next.into()).len() > 0 {
+            next_page_number = page + 1;
+        }
+
+        return (object_list, next_page_number);
+    }
+    pub fn get_revisions(item_id: i32, limit: i64, offset: i64) -> Vec<ItemRevision> {
+        use crate::schema::item_revisions::dsl::item_revisions;
+
+        let _connection = establish_connection();
+        return item_revisions
+            .filter(schema::item_revisions::item_id.eq(item_id))
+            .order(schema::item_revisions::id.desc())
+            .limit(limit)
+            .offset(offset)
+            .load::<ItemRevision>(&_connection)
+            .expect("E.");
+    }
+    pub fn count_for_item(item_id: i32) -> i64 {
+        use crate::schema::item_revisions::dsl::item_revisions;
+
+        let _connection = establish_connection();
+        return item_revisions
+            .filter(schema::item_revisions::item_id.eq(item_id))
+            .count()
+            .get_result::<i64>(&_connection)
+            .expect("E.");
+    }
+    // предыдущая ревизия того же объекта - с ней сравниваем по умолчанию
+    pub fn get_previous(&self) -> Option<ItemRevision> {
+        use crate::schema::item_revisions::dsl::item_revisions;
+
+        let _connection = establish_connection();
+        return item_revisions
+            .filter(schema::item_revisions::item_id.eq(self.item_id))
+            .filter(schema::item_revisions::id.lt(self.id))
+            .order(schema::item_revisions::id.desc())
+            .first::<ItemRevision>(&_connection)
+            .ok();
+    }
+    pub fn get_username(&self) -> String {
+        use crate::schema::users::dsl::users;
+
+        let _connection = establish_connection();
+        return users
+            .filter(schema::users::id.eq(self.user_id))
+            .select(schema::users::username)
+            .first::<String>(&_connection)
+            .expect("E.");
+    }
+    pub fn get_types(&self) -> String {
+        return match self.types {
+            1 => "Правка объекта".to_string(),
+            2 => "Правка текста".to_string(),
+            3 => "Восстановление".to_string(),
+            4 => "Исходное состояние".to_string(),
+            _ => "Непонятно".to_string(),
+        };
+    }
+    pub fn get_diff(&self, other: &ItemRevision) -> Vec<crate::utils::FieldDiff> {
+        use crate::utils::get_field_diff;
+
+        let empty = "".to_string();
+        return vec![
+            get_field_diff("Название", &self.title, &other.title),
+            get_field_diff("Ссылка", &self.slug, &other.slug),
+            get_field_diff (
+                "Описание",
+                self.description.as_ref().unwrap_or(&empty),
+                other.description.as_ref().unwrap_or(&empty),
+            ),
+            get_field_diff (
+                "Текст",
+                self.content.as_ref().unwrap_or(&empty),
+                other.content.as_ref().unwrap_or(&empty),
+            ),
+            get_field_diff (
+                "Markdown",
+                self.content_md.as_ref().unwrap_or(&empty),
+                other.content_md.as_ref().unwrap_or(&empty),
+            ),
+        ];
+    }
+    // возвращаем объекту состояние ревизии. Сам откат тоже
+    // попадает в историю, так что его можно отменить.
+    // Сохраняется так же, как при правке: slug проверяется на
+    // уникальность и со сменяемого ставится перенаправление,
+    // html собирается из markdown заново.
+    pub fn restore(&self, user_id: i32) -> () {
+        use crate::schema::items::dsl::items;
+        use crate::models::SlugRedirect;
+        use crate::utils::get_unique_item_slug;
+
+        let _connection = establish_connection();
+        let _item = items
+            .filter(schema::items::id.eq(self.item_id))
+            .first::<Item>(&_connection)
+            .expect("E.");
+
+        let slug = get_unique_item_slug(&self.slug, &self.title, _item.types, _item.id);
+        let _new_item = diesel::update(&_item)
+            .set((
+                schema::items::title.eq(self.title.clone()),
+                schema::items::description.eq(self.description.clone()),
+                schema::items::content.eq(Item::render_content(&self.content, &self.content_md)),
+                schema::items::slug.eq(slug.clone()),
+                schema::items::content_md.eq(self.content_md.clone()),
+            ))
+            .get_result::<Item>(&_connection)
+            .expect("E.");
+        SlugRedirect::create(_item.types, false, &_item.slug, &slug);
+        _new_item.update_wiki_links();
+        Item::save_revision(self.item_id, user_id, 3);
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="item_revisions"]
+pub struct NewItemRevision {
+    pub item_id:     i32,
+    pub user_id:     i32,
+    pub title:       String,
+    pub description: Option<String>,
+    pub content:     Option<String>,
+    pub slug:        String,
+    pub types:       i16,
+    pub created:     chrono::NaiveDateTime,
+    pub content_md:  Option<String>,
+}
+
+impl NewItemRevision {
+    pub fn create(item: &Item, user_id: i32, types: i16) -> () {
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let new_revision = NewItemRevision {
+            item_id:     item.id,
+            user_id:     user_id,
+            title:       item.title.clone(),
+            description: item.description.clone(),
+            content:     item.content.clone(),
+            slug:        item.slug.clone(),
+            types:       types,
+            created:     chrono::Local::now().naive_utc() + Duration::hours(3),
+            content_md:  item.content_md.clone(),
+        };
+        diesel::insert_into(schema::item_revisions::table)
+            .values(&new_revision)
+            .execute(&_connection)
+            .expect("E.");
+    }
+}
+
+// ссылка на просмотр неопубликованного объекта без аккаунта
+#[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
+pub struct ItemPreview {
+    pub id:         i32,
+    pub item_id:    i32,
+    pub user_id:    i32,
+    pub expires:    chrono::NaiveDateTime,
+    pub is_revoked: bool,
+    pub view:       i32,
+    pub created:    chrono::NaiveDateTime,
+}
+
+impl ItemPreview {
+    pub fn get_expires_ts(&self) -> i64 {
+        return self.expires.timestamp();
+    }
+    // пустая строка, если ссылки на черновики отключены (нет SECRET_KEY)
+    pub fn get_url(&self) -> String {
+        use crate::utils::sign_preview;
+
+        let expires = self.get_expires_ts();
+        return match sign_preview(self.id, self.item_id, expires) {
+            Some(_signature) => "/preview/".to_string()
+                + &self.id.to_string() + &"/".to_string()
+                + &expires.to_string() + &"/".to_string()
+                + &_signature + &"/".to_string(),
+            None => String::new(),
+        };
+    }
+    pub fn is_expired(&self) -> bool {
+        use chrono::Duration;
+
+        return self.expires < chrono::Local::now().naive_utc() + Duration::hours(3);
+    }
+    pub fn is_valid(&self) -> bool {
+        return !self.is_revoked && !self.is_expired();
+    }
+    pub fn get_status(&self) -> String {
+        if self.is_revoked {
+            return "Отозвана".to_string();
         }
+        else if self.is_expired() {
+            return "Истекла".to_string();
+        }
+        return "Действует".to_string();
+    }
+    pub fn get_previews(item_id: i32) -> Vec<ItemPreview> {
+        use crate::schema::item_previews::dsl::item_previews;
+
+        let _connection = establish_connection();
+        return item_previews
+            .filter(schema::item_previews::item_id.eq(item_id))
+            .order(schema::item_previews::created.desc())
+            .load::<ItemPreview>(&_connection)
+            .expect("E.");
+    }
+    pub fn revoke(&self) -> () {
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set(schema::item_previews::is_revoked.eq(true))
+            .execute(&_connection)
+            .expect("E.");
+    }
+    pub fn plus_view(&self) -> () {
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set(schema::item_previews::view.eq(self.view + 1))
+            .execute(&_connection)
+            .expect("E.");
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="item_previews"]
+pub struct NewItemPreview {
+    pub item_id:    i32,
+    pub user_id:    i32,
+    pub expires:    chrono::NaiveDateTime,
+    pub is_revoked: bool,
+    pub view:       i32,
+    pub created:    chrono::NaiveDateTime,
+}
+
+impl NewItemPreview {
+    pub fn create(item_id: i32, user_id: i32, hours: i64) -> ItemPreview {
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let now = chrono::Local::now().naive_utc() + Duration::hours(3);
+        let new_preview = NewItemPreview {
+            item_id:    item_id,
+            user_id:    user_id,
+            expires:    now + Duration::hours(hours),
+            is_revoked: false,
+            view:       0,
+            created:    now,
+        };
+        return diesel::insert_into(schema::item_previews::table)
+            .values(&new_preview)
+            .get_result::<ItemPreview>(&_connection)
+            .expect("E.");
     }
 }
@@ -6,6 +6,8 @@ mod order;
 mod serve;
 mod media;
 mod chat;
+mod redirect;
+mod discount;
 
 pub use self::{
     item::*,
@@ -16,4 +18,6 @@ pub use self::{
     order::*,
     media::*,
     chat::*,
+    redirect::*,
+    discount::*,
 };
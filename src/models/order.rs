@@ -11,6 +11,10 @@ use crate::models::{Serve, TechCategories};
 use crate::schema::{
     orders,
     order_files,
+    order_status_history,
+    order_lines,
+    order_changes,
+    order_documents,
 };
 use crate::utils::establish_connection;
 
@@ -29,9 +33,121 @@ pub struct Order {
     pub user_id:     i32,
     pub price:       i32,
     pub price_acc:   Option<i32>,
+    pub status:      i16,
+    pub promo_code:  Option<String>,
+}
+
+///////////
+// status:
+// 1. новый
+// 2. рассмотрен
+// 3. отправлено предложение (КП)
+// 4. принят заказчиком
+// 5. в работе
+// 6. сдан
+// 7. закрыт
+// 8. отменен
+
+pub fn get_order_status_name(status: i16) -> String {
+    return match status {
+        1 => "Новый".to_string(),
+        2 => "Рассмотрен".to_string(),
+        3 => "Отправлено предложение".to_string(),
+        4 => "Принят".to_string(),
+        5 => "В работе".to_string(),
+        6 => "Сдан".to_string(),
+        7 => "Закрыт".to_string(),
+        8 => "Отменен".to_string(),
+        _ => "Неизвестно".to_string(),
+    };
+}
+
+// куда можно перевести заказ из статуса. Кроме движения вперед
+// можно пересмотреть предложение (3 -> 2) и вернуть сданный
+// заказ на доработку (6 -> 5). Закрытый и отмененный - конечные.
+pub fn get_order_next_statuses(status: i16) -> Vec<i16> {
+    return match status {
+        1 => vec![2, 8],
+        2 => vec![3, 8],
+        3 => vec![4, 2, 8],
+        4 => vec![5, 8],
+        5 => vec![6, 8],
+        6 => vec![7, 5],
+        _ => Vec::new(),
+    };
 }
 
 impl Order {
+    pub fn get_status_name(&self) -> String {
+        return get_order_status_name(self.status);
+    }
+    pub fn get_next_statuses(&self) -> Vec<i16> {
+        return get_order_next_statuses(self.status);
+    }
+    pub fn is_closed(&self) -> bool {
+        return self.status == 7 || self.status == 8;
+    }
+    // заказчик правит заказ, пока по нему не отправлено предложение
+    pub fn is_editable(&self) -> bool {
+        return self.status == 1 || self.status == 2;
+    }
+    // правки заказчика, новые сверху
+    pub fn get_changes(&self) -> Vec<OrderChange> {
+        use crate::schema::order_changes::dsl::order_changes;
+
+        let _connection = establish_connection();
+        return order_changes
+            .filter(schema::order_changes::order_id.eq(self.id))
+            .order(schema::order_changes::created.desc())
+            .load::<OrderChange>(&_connection)
+            .expect("E.");
+    }
+    pub fn get_documents(&self) -> Vec<OrderDocument> {
+        use crate::schema::order_documents::dsl::order_documents;
+
+        let _connection = establish_connection();
+        return order_documents
+            .filter(schema::order_documents::order_id.eq(self.id))
+            .order(schema::order_documents::created.desc())
+            .load::<OrderDocument>(&_connection)
+            .expect("E.");
+    }
+    // переход по машине статусов с записью в историю.
+    // Недопустимый переход ничего не меняет и возвращает false.
+    // Статус меняется только если он еще тот, из которого проверяли
+    // переход - иначе параллельный запрос уже перевел заказ.
+    pub fn change_status(&self, status: i16, user_id: Option<i32>, comment: Option<String>) -> bool {
+        use crate::schema::orders::dsl::orders;
+
+        if !self.get_next_statuses().contains(&status) {
+            return false;
+        }
+        let _connection = establish_connection();
+        let updated = diesel::update (
+                orders
+                    .filter(schema::orders::id.eq(self.id))
+                    .filter(schema::orders::status.eq(self.status))
+            )
+            .set(schema::orders::status.eq(status))
+            .execute(&_connection)
+            .expect("E.");
+        if updated == 0 {
+            return false;
+        }
+        OrderStatusHistory::create(self.id, status, user_id, comment);
+        return true;
+    }
+    // история для ленты на странице заказа, по порядку
+    pub fn get_status_history(&self) -> Vec<OrderStatusHistory> {
+        use crate::schema::order_status_history::dsl::order_status_history;
+
+        let _connection = establish_connection();
+        return order_status_history
+            .filter(schema::order_status_history::order_id.eq(self.id))
+            .order(schema::order_status_history::created.asc())
+            .load::<OrderStatusHistory>(&_connection)
+            .expect("E.");
+    }
     pub fn get_orders_list(page: i32, limit: i32) -> (Vec<Order>, i32) {
         let mut next_page_number = 0;
         let have_next: i32;
@@ -95,48 +211,50 @@ impl Order {
             .load::<Order>(&_connection)
             .expect("E.");
     }
-    pub fn get_serves(&self) -> Vec<Serve> {
-        use schema::serve_items::dsl::serve_items;
-        use schema::serve::dsl::serve;
+    // строки заказа - копии выбранных опций
+    pub fn get_lines(&self) -> Vec<OrderLine> {
+        use schema::order_lines::dsl::order_lines;
 
         let _connection = establish_connection();
-        let _serve_items = serve_items
-            .filter(schema::serve_items::item_id.eq(&self.id))
-            .filter(schema::serve_items::types.eq(7))
-            .select(schema::serve_items::serve_id)
-            .load::<i32>(&_connection)
+        return order_lines
+            .filter(schema::order_lines::order_id.eq(self.id))
+            .order(schema::order_lines::id.asc())
+            .load::<OrderLine>(&_connection)
             .expect("E");
+    }
+    // опции каталога, которые еще существуют
+    pub fn get_serves(&self) -> Vec<Serve> {
+        use schema::serve::dsl::serve;
 
+        let _connection = establish_connection();
         return serve
-            .filter(schema::serve::id.eq_any(_serve_items))
+            .filter(schema::serve::id.eq_any(self.get_serves_ids()))
             .order(schema::serve::position.desc())
             .load::<Serve>(&_connection)
             .expect("E");
     }
     pub fn get_serves_ids(&self) -> Vec<i32> {
-        use schema::serve_items::dsl::serve_items;
+        use schema::order_lines::dsl::order_lines;
 
         let _connection = establish_connection();
-        return serve_items
-            .filter(schema::serve_items::item_id.eq(&self.id))
-            .filter(schema::serve_items::types.eq(7))
-            .select(schema::serve_items::serve_id)
-            .load::<i32>(&_connection)
+        let _ids = order_lines
+            .filter(schema::order_lines::order_id.eq(self.id))
+            .select(schema::order_lines::serve_id)
+            .load::<Option<i32>>(&_connection)
             .expect("E");
+        return _ids.into_iter().filter_map(|i| i).collect();
     }
     pub fn get_open_tech_categories(&self) -> Vec<TechCategories> {
-        // получаем открытые тех.категории элемента
+        // тех. категории, опции которых есть в заказе
         use schema::{
-            tech_categories_items::dsl::tech_categories_items,
+            order_lines::dsl::order_lines,
             tech_categories::dsl::tech_categories,
         };
 
         let _connection = establish_connection();
-        let ids = tech_categories_items
-            .filter(schema::tech_categories_items::item_id.eq(&self.id))
-            .filter(schema::tech_categories_items::types.eq(7))
-            .filter(schema::tech_categories_items::is_active.eq(1))
-            .select(schema::tech_categories_items::category_id)
+        let ids = order_lines
+            .filter(schema::order_lines::order_id.eq(self.id))
+            .select(schema::order_lines::tech_cat_id)
             .load::<i32>(&_connection)
             .expect("E");
 
@@ -146,6 +264,120 @@ impl Order {
             .load::<TechCategories>(&_connection)
             .expect("E");
     }
+    pub fn get_man_hours(&self) -> i32 {
+        return self.get_lines().iter().map(|l| l.man_hours as i32).sum();
+    }
+    // приводит строки заказа к опциям serve_ids: строки убранных опций
+    // удаляются, оставшиеся сохраняют цену на момент заказа, копии
+    // снимаются только с новых опций. Затем пересчитывает цену.
+    pub fn set_lines(&self, serve_ids: &Vec<i32>) -> Order {
+        use schema::{
+            order_lines::dsl::order_lines,
+            serve::dsl::serve,
+            tech_categories::dsl::tech_categories,
+        };
+
+        let _connection = establish_connection();
+        diesel::delete (
+            order_lines
+                .filter(schema::order_lines::order_id.eq(self.id))
+                .filter(schema::order_lines::serve_id.ne_all(serve_ids).or(schema::order_lines::serve_id.is_null()))
+            )
+            .execute(&_connection)
+            .expect("E");
+        let _kept_ids = order_lines
+            .filter(schema::order_lines::order_id.eq(self.id))
+            .filter(schema::order_lines::serve_id.is_not_null())
+            .select(schema::order_lines::serve_id)
+            .load::<Option<i32>>(&_connection)
+            .expect("E");
+        let _new_ids: Vec<i32> = serve_ids
+            .iter()
+            .filter(|id| !_kept_ids.contains(&Some(**id)))
+            .map(|id| *id)
+            .collect();
+
+        let _serves = serve
+            .filter(schema::serve::id.eq_any(_new_ids))
+            .order((schema::serve::tech_cat_id.asc(), schema::serve::position.desc()))
+            .load::<Serve>(&_connection)
+            .expect("E");
+        let tech_cat_ids: Vec<i32> = _serves.iter().map(|s| s.tech_cat_id).collect();
+        let _tech_cats = tech_categories
+            .filter(schema::tech_categories::id.eq_any(tech_cat_ids))
+            .select((schema::tech_categories::id, schema::tech_categories::name))
+            .load::<(i32, String)>(&_connection)
+            .expect("E");
+
+        for _serve in _serves.iter() {
+            let tech_cat_name = _tech_cats
+                .iter()
+                .find(|c| c.0 == _serve.tech_cat_id)
+                .map(|c| c.1.clone())
+                .unwrap_or_default();
+            let new_line = NewOrderLine {
+                order_id:      self.id,
+                serve_id:      Some(_serve.id),
+                name:          _serve.name.clone(),
+                price:         _serve.price,
+                man_hours:     _serve.man_hours,
+                tech_cat_id:   _serve.tech_cat_id,
+                tech_cat_name: tech_cat_name,
+                position:      _serve.position,
+            };
+            diesel::insert_into(schema::order_lines::table)
+                .values(&new_line)
+                .execute(&_connection)
+                .expect("E.");
+        }
+        return self.update_price();
+    }
+    // у заказов своя нумерация типов (1 услуга, 2 товар, 3 работа),
+    // а правила скидок пишутся по типам объектов (2, 3, 5)
+    pub fn get_item_types(order_types: i16) -> i16 {
+        return match order_types {
+            1 => 2,
+            2 => 3,
+            3 => 5,
+            _ => 0,
+        };
+    }
+    // цена заказа - сумма строк, скидка считается от нее
+    // промокод из формы, если сейчас есть правило с ним для этого
+    // типа заказа
+    pub fn get_valid_promo(code: &Option<String>, order_types: i16) -> Option<String> {
+        use crate::models::DiscountRule;
+
+        let types = Order::get_item_types(order_types);
+        return code
+            .as_ref()
+            .filter(|c| DiscountRule::is_valid_promo(c, types))
+            .map(|c| DiscountRule::normalize_code(c));
+    }
+    pub fn set_promo_code(&self, code: Option<String>) -> Order {
+        let _connection = establish_connection();
+        return diesel::update(self)
+            .set(schema::orders::promo_code.eq(code))
+            .get_result::<Order>(&_connection)
+            .expect("E.");
+    }
+    pub fn update_price(&self) -> Order {
+        use crate::models::{DiscountRule, DiscountInput};
+
+        let _connection = establish_connection();
+        let input = DiscountInput {
+            types:      Order::get_item_types(self.types),
+            lines:      self.get_lines().iter().map(|l| (l.tech_cat_id, l.price)).collect(),
+            promo_code: self.promo_code.clone(),
+        };
+        return diesel::update(self)
+            .set((
+                schema::orders::price.eq(input.get_total()),
+                schema::orders::price_acc.eq(DiscountRule::get_discount(&input)),
+            ))
+            .get_result::<Order>(&_connection)
+            .expect("E.");
+    }
 }
 
 #[derive(Insertable)]
@@ -172,22 +404,26 @@ impl NewOrder {
         user_id:     i32,
     ) -> Self {
         use chrono::Duration;
+        use crate::utils::{sanitize_plain, sanitize_plain_opt};
 
+        // всё это приходит из публичной формы заказа
         NewOrder {
-            title:       title,
+            title:       sanitize_plain(&title),
             types:       types,
             object_id:   object_id,
-            username:    username,
-            email:       email,
-            description: description,
+            username:    sanitize_plain(&username),
+            email:       sanitize_plain(&email),
+            description: sanitize_plain_opt(&description),
             created:     chrono::Local::now().naive_utc() + Duration::hours(3),
             user_id:     user_id,
             price:       0,
         }
     }
 }
+// описание можно стереть, поэтому None здесь пишется как NULL
 #[derive(Queryable, Serialize, Deserialize, AsChangeset, Debug)]
 #[table_name="orders"]
+#[changeset_options(treat_none_as_null="true")]
 pub struct EditOrder {
     pub username:    String,
     pub email:       String,
@@ -195,6 +431,125 @@ pub struct EditOrder {
 }
 
 
+#[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
+#[table_name="order_status_history"]
+pub struct OrderStatusHistory {
+    pub id:       i32,
+    pub order_id: i32,
+    pub status:   i16,
+    pub user_id:  Option<i32>,
+    pub comment:  Option<String>,
+    pub created:  chrono::NaiveDateTime,
+}
+
+impl OrderStatusHistory {
+    pub fn create(order_id: i32, status: i16, user_id: Option<i32>, comment: Option<String>) -> () {
+        use chrono::Duration;
+        use crate::utils::sanitize_plain_opt;
+
+        let _connection = establish_connection();
+        let new_status = NewOrderStatusHistory {
+            order_id: order_id,
+            status:   status,
+            user_id:  user_id,
+            comment:  sanitize_plain_opt(&comment),
+            created:  chrono::Local::now().naive_utc() + Duration::hours(3),
+        };
+        diesel::insert_into(schema::order_status_history::table)
+            .values(&new_status)
+            .execute(&_connection)
+            .expect("E.");
+    }
+    pub fn get_status_name(&self) -> String {
+        return get_order_status_name(self.status);
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="order_status_history"]
+pub struct NewOrderStatusHistory {
+    pub order_id: i32,
+    pub status:   i16,
+    pub user_id:  Option<i32>,
+    pub comment:  Option<String>,
+    pub created:  chrono::NaiveDateTime,
+}
+
+
+#[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
+pub struct OrderChange {
+    pub id:        i32,
+    pub order_id:  i32,
+    pub c_user_id: i32,
+    pub changes:   String,
+    pub old_price: i32,
+    pub new_price: i32,
+    pub created:   chrono::NaiveDateTime,
+}
+
+impl OrderChange {
+    pub fn create(order_id: i32, c_user_id: i32, changes: Vec<String>, old_price: i32, new_price: i32) -> () {
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        let new_change = NewOrderChange {
+            order_id:  order_id,
+            c_user_id: c_user_id,
+            changes:   changes.join("\n").chars().take(5000).collect(),
+            old_price: old_price,
+            new_price: new_price,
+            created:   chrono::Local::now().naive_utc() + Duration::hours(3),
+        };
+        diesel::insert_into(schema::order_changes::table)
+            .values(&new_change)
+            .execute(&_connection)
+            .expect("E.");
+    }
+    pub fn get_changes_list(&self) -> Vec<String> {
+        return self.changes.split("\n").map(|c| c.to_string()).collect();
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="order_changes"]
+pub struct NewOrderChange {
+    pub order_id:  i32,
+    pub c_user_id: i32,
+    pub changes:   String,
+    pub old_price: i32,
+    pub new_price: i32,
+    pub created:   chrono::NaiveDateTime,
+}
+
+// опция на момент заказа: название, цена и трудоемкость
+// не меняются вслед за каталогом
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Associations)]
+pub struct OrderLine {
+    pub id:            i32,
+    pub order_id:      i32,
+    pub serve_id:      Option<i32>,
+    pub name:          String,
+    pub price:         i32,
+    pub man_hours:     i16,
+    pub tech_cat_id:   i32,
+    pub tech_cat_name: String,
+    pub position:      i16,
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="order_lines"]
+pub struct NewOrderLine {
+    pub order_id:      i32,
+    pub serve_id:      Option<i32>,
+    pub name:          String,
+    pub price:         i32,
+    pub man_hours:     i16,
+    pub tech_cat_id:   i32,
+    pub tech_cat_name: String,
+    pub position:      i16,
+}
+
+
 #[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
 pub struct OrderFile {
     pub id:       i32,
@@ -217,3 +572,124 @@ impl NewOrderFile {
         }
     }
 }
+
+
+///////////
+// types:
+// 1. коммерческое предложение (КП)
+// 2. счет
+
+// документ заказа. Цена, скидка и строки заказа запоминаются на момент
+// выдачи, номер сквозной внутри года для каждого вида документов.
+#[derive(Debug, Serialize, Queryable, Identifiable, Associations)]
+pub struct OrderDocument {
+    pub id:        i32,
+    pub order_id:  i32,
+    pub types:     i16,
+    pub year:      i16,
+    pub seq:       i32,
+    pub number:    String,
+    pub price:     i32,
+    pub price_acc: Option<i32>,
+    pub lines:     String,
+    pub created:   chrono::NaiveDateTime,
+}
+
+impl OrderDocument {
+    pub fn is_valid_type(types: i16) -> bool {
+        return types == 1 || types == 2;
+    }
+    pub fn get_type_name(&self) -> String {
+        return match self.types {
+            2 => "Счет".to_string(),
+            _ => "Коммерческое предложение".to_string(),
+        };
+    }
+    // имя файла для скачивания: в заголовке ответа только латиница
+    pub fn get_file_name(&self) -> String {
+        let prefix = match self.types {
+            2 => "invoice",
+            _ => "proposal",
+        };
+        return format!("{}-{}-{:04}.pdf", prefix, self.year, self.seq);
+    }
+    pub fn get_total_price(&self) -> i32 {
+        return self.price - self.price_acc.unwrap_or(0);
+    }
+    // строки заказа на момент выдачи - по ним документ и печатается
+    pub fn get_lines(&self) -> Vec<OrderLine> {
+        return serde_json::from_str(&self.lines).unwrap_or_default();
+    }
+    // выдача документа. Пока строки и цена заказа не менялись, отдается
+    // уже выданный документ с тем же номером, иначе выдается новый.
+    // Номер берется под блокировкой таблицы, чтобы параллельные
+    // запросы не получили один seq.
+    pub fn create(order: &Order, types: i16) -> OrderDocument {
+        use crate::schema::order_documents::dsl::order_documents;
+        use diesel::dsl::max;
+        use diesel::{Connection, OptionalExtension};
+        use chrono::{Datelike, Duration};
+
+        let _connection = establish_connection();
+        let lines = serde_json::to_string(&order.get_lines()).expect("E.");
+        return _connection.transaction::<OrderDocument, diesel::result::Error, _>(|| {
+            diesel::sql_query("LOCK TABLE order_documents IN SHARE ROW EXCLUSIVE MODE")
+                .execute(&_connection)?;
+
+            let _last = order_documents
+                .filter(schema::order_documents::order_id.eq(order.id))
+                .filter(schema::order_documents::types.eq(types))
+                .order(schema::order_documents::id.desc())
+                .first::<OrderDocument>(&_connection)
+                .optional()?;
+            if let Some(_document) = _last {
+                if _document.price == order.price
+                    && _document.price_acc == order.price_acc
+                    && _document.lines == lines {
+                    return Ok(_document);
+                }
+            }
+
+            let created = chrono::Local::now().naive_utc() + Duration::hours(3);
+            let year = created.year() as i16;
+            let seq = order_documents
+                .filter(schema::order_documents::types.eq(types))
+                .filter(schema::order_documents::year.eq(year))
+                .select(max(schema::order_documents::seq))
+                .first::<Option<i32>>(&_connection)?
+                .unwrap_or(0) + 1;
+            let prefix = match types {
+                2 => "СЧ",
+                _ => "КП",
+            };
+            let new_document = NewOrderDocument {
+                order_id:  order.id,
+                types:     types,
+                year:      year,
+                seq:       seq,
+                number:    format!("{}-{}-{:04}", prefix, year, seq),
+                price:     order.price,
+                price_acc: order.price_acc,
+                lines:     lines.clone(),
+                created:   created,
+            };
+            diesel::insert_into(schema::order_documents::table)
+                .values(&new_document)
+                .get_result::<OrderDocument>(&_connection)
+        }).expect("E.");
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="order_documents"]
+pub struct NewOrderDocument {
+    pub order_id:  i32,
+    pub types:     i16,
+    pub year:      i16,
+    pub seq:       i32,
+    pub number:    String,
+    pub price:     i32,
+    pub price_acc: Option<i32>,
+    pub lines:     String,
+    pub created:   chrono::NaiveDateTime,
+}
@@ -0,0 +1,344 @@
+use crate::schema;
+use crate::diesel::{
+    Queryable,
+    Insertable,
+    QueryDsl,
+    RunQueryDsl,
+    ExpressionMethods,
+};
+use serde::{Serialize, Deserialize};
+use crate::schema::{
+    slug_redirects,
+    redirects,
+};
+use crate::utils::establish_connection;
+
+
+// префиксы ссылок на объекты и категории по типу
+pub fn get_item_prefix(types: i16) -> String {
+    return match types {
+        1 => "/blog/".to_string(),
+        2 => "/service/".to_string(),
+        3 => "/store/".to_string(),
+        4 => "/wiki/".to_string(),
+        5 => "/work/".to_string(),
+        _ => "/".to_string(),
+    };
+}
+// адрес объекта в категории cat_slug
+pub fn get_item_url(types: i16, cat_slug: &str, slug: &str) -> String {
+    return get_item_prefix(types) + cat_slug + &"/".to_string() + slug + &"/".to_string();
+}
+pub fn get_category_prefix(types: i16) -> String {
+    return match types {
+        1 => "/blogs/".to_string(),
+        2 => "/services/".to_string(),
+        3 => "/stores/".to_string(),
+        4 => "/wikis/".to_string(),
+        5 => "/works/".to_string(),
+        6 => "/helps/".to_string(),
+        _ => "/".to_string(),
+    };
+}
+
+// старая ссылка объекта или категории -> новая.
+// Пишется автоматически при смене slug.
+#[derive(Debug, Serialize, Queryable, Identifiable)]
+pub struct SlugRedirect {
+    pub id:          i32,
+    pub types:       i16,
+    pub is_category: bool,
+    pub old_slug:    String,
+    pub new_slug:    String,
+    pub created:     chrono::NaiveDateTime,
+}
+
+impl SlugRedirect {
+    pub fn get_new_slug(types: i16, is_category: bool, old_slug: &str) -> Option<String> {
+        use crate::schema::slug_redirects::dsl::slug_redirects;
+
+        let _connection = establish_connection();
+        return slug_redirects
+            .filter(schema::slug_redirects::types.eq(types))
+            .filter(schema::slug_redirects::is_category.eq(is_category))
+            .filter(schema::slug_redirects::old_slug.eq(old_slug))
+            .select(schema::slug_redirects::new_slug)
+            .first::<String>(&_connection)
+            .ok();
+    }
+    pub fn item_exists(types: i16, slug: &str) -> bool {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        return items
+            .filter(schema::items::types.eq(types))
+            .filter(schema::items::slug.eq(slug))
+            .select(schema::items::id)
+            .first::<i32>(&_connection)
+            .is_ok();
+    }
+    pub fn category_exists(types: i16, slug: &str) -> bool {
+        use crate::schema::categories::dsl::categories;
+
+        let _connection = establish_connection();
+        return categories
+            .filter(schema::categories::types.eq(types))
+            .filter(schema::categories::slug.eq(slug))
+            .select(schema::categories::id)
+            .first::<i32>(&_connection)
+            .is_ok();
+    }
+    // куда перенаправить /blog/{cat_slug}/{item_slug}/, если одна из
+    // частей ссылки устарела. None - перенаправлять некуда.
+    pub fn get_item_url(types: i16, cat_slug: &str, item_slug: &str) -> Option<String> {
+        let new_cat = match SlugRedirect::category_exists(types, cat_slug) {
+            true => cat_slug.to_string(),
+            false => SlugRedirect::get_new_slug(types, true, cat_slug)?,
+        };
+        let new_item = match SlugRedirect::item_exists(types, item_slug) {
+            true => item_slug.to_string(),
+            false => SlugRedirect::get_new_slug(types, false, item_slug)?,
+        };
+        if new_cat == cat_slug && new_item == item_slug {
+            return None;
+        }
+        return Some(get_item_url(types, &new_cat, &new_item));
+    }
+    pub fn get_category_url(types: i16, cat_slug: &str) -> Option<String> {
+        use crate::schema::categories::dsl::categories;
+        use crate::models::Categories;
+
+        let new_cat = SlugRedirect::get_new_slug(types, true, cat_slug)?;
+        let _connection = establish_connection();
+        let _cat_id = categories
+            .filter(schema::categories::types.eq(types))
+            .filter(schema::categories::slug.eq(&new_cat))
+            .select(schema::categories::id)
+            .first::<i32>(&_connection)
+            .ok()?;
+        // сразу на вложенный адрес, без лишнего перенаправления
+        return Some(Categories::get_category_url(_cat_id, types));
+    }
+    pub fn create(types: i16, is_category: bool, old_slug: &str, new_slug: &str) -> () {
+        use crate::schema::slug_redirects::dsl::slug_redirects;
+        use chrono::Duration;
+
+        if old_slug == new_slug || old_slug.is_empty() {
+            return;
+        }
+        let _connection = establish_connection();
+        // старые перенаправления на old_slug ведем сразу на новую ссылку,
+        // чтобы не было цепочек
+        diesel::update (
+            slug_redirects
+                .filter(schema::slug_redirects::types.eq(types))
+                .filter(schema::slug_redirects::is_category.eq(is_category))
+                .filter(schema::slug_redirects::new_slug.eq(old_slug))
+            )
+            .set(schema::slug_redirects::new_slug.eq(new_slug))
+            .execute(&_connection)
+            .expect("E.");
+        // новая ссылка снова занята - перенаправление с нее больше не нужно
+        diesel::delete (
+            slug_redirects
+                .filter(schema::slug_redirects::types.eq(types))
+                .filter(schema::slug_redirects::is_category.eq(is_category))
+                .filter(schema::slug_redirects::old_slug.eq_any(vec![old_slug, new_slug]))
+            )
+            .execute(&_connection)
+            .expect("E.");
+
+        let new_redirect = NewSlugRedirect {
+            types:       types,
+            is_category: is_category,
+            old_slug:    old_slug.to_string(),
+            new_slug:    new_slug.to_string(),
+            created:     chrono::Local::now().naive_utc() + Duration::hours(3),
+        };
+        diesel::insert_into(schema::slug_redirects::table)
+            .values(&new_redirect)
+            .execute(&_connection)
+            .expect("E.");
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="slug_redirects"]
+pub struct NewSlugRedirect {
+    pub types:       i16,
+    pub is_category: bool,
+    pub old_slug:    String,
+    pub new_slug:    String,
+    pub created:     chrono::NaiveDateTime,
+}
+
+// перенаправления, которые ведет админ: старые адреса прежнего сайта
+// и прочие ссылки. Проверяются перед отдачей страницы 404.
+#[derive(Debug, Serialize, Queryable, Identifiable)]
+pub struct Redirect {
+    pub id:       i32,
+    pub source:   String,
+    pub target:   String,
+    pub status:   i16,
+    pub hits:     i32,
+    pub last_hit: Option<chrono::NaiveDateTime>,
+    pub created:  chrono::NaiveDateTime,
+}
+
+impl Redirect {
+    // /old/page и /old/page/ считаем одним адресом
+    // адрес хранится раскодированным, без "/" в конце пути:
+    // "/%D0%BE-%D0%BD%D0%B0%D1%81/" -> "/о-нас", "/page.php/?id=5" -> "/page.php?id=5"
+    pub fn normalize_source(source: &str) -> String {
+        use crate::utils::percent_decode;
+
+        let _source = percent_decode(source.trim());
+        let (path, query) = match _source.find('?') {
+            Some(pos) => (&_source[..pos], Some(&_source[pos + 1..])),
+            None => (_source.as_str(), None),
+        };
+        let mut _path = path.to_string();
+        if !_path.starts_with('/') {
+            _path = "/".to_string() + &_path;
+        }
+        if _path.len() > 1 && _path.ends_with('/') {
+            _path.pop();
+        }
+        return match query {
+            Some(_query) if !_query.is_empty() => _path + &"?".to_string() + _query,
+            _ => _path,
+        };
+    }
+    // сначала ищем адрес вместе со строкой запроса (/page.php?id=5),
+    // потом один путь
+    pub fn get_for_path(path: &str, query: &str) -> Option<Redirect> {
+        use crate::schema::redirects::dsl::redirects;
+
+        let _connection = establish_connection();
+        if !query.is_empty() {
+            let _redirect = redirects
+                .filter(schema::redirects::source.eq(Redirect::normalize_source(&(path.to_string() + &"?".to_string() + query))))
+                .first::<Redirect>(&_connection);
+            if _redirect.is_ok() {
+                return _redirect.ok();
+            }
+        }
+        return redirects
+            .filter(schema::redirects::source.eq(Redirect::normalize_source(path)))
+            .first::<Redirect>(&_connection)
+            .ok();
+    }
+    pub fn plus_hit(&self) -> () {
+        use chrono::Duration;
+
+        let _connection = establish_connection();
+        diesel::update(self)
+            .set((
+                schema::redirects::hits.eq(self.hits + 1),
+                schema::redirects::last_hit.eq(chrono::Local::now().naive_utc() + Duration::hours(3)),
+            ))
+            .execute(&_connection)
+            .expect("E.");
+    }
+    pub fn is_permanent(&self) -> bool {
+        return self.status != 302;
+    }
+    pub fn get_redirects_list(page: i32, limit: i32) -> (Vec<Redirect>, i32) {
+        let mut next_page_number = 0;
+        let have_next: i32;
+        let object_list: Vec<Redirect>;
+
+        if page > 1 {
+            let step = (page - 1) * 20;
+            have_next = page * limit + 1;
+            object_list = Redirect::get_redirects(limit.into(), step.into());
+        }
+        else {
+            have_next = limit + 1;
+            object_list = Redirect::get_redirects(limit.into(), 0);
+        }
+        if Redirect::get_redirects(1, have_next.into()).len() > 0 {
+            next_page_number = page + 1;
+        }
+
+        return (object_list, next_page_number);
+    }
+    pub fn get_redirects(limit: i64, offset: i64) -> Vec<Redirect> {
+        use crate::schema::redirects::dsl::redirects;
+
+        let _connection = establish_connection();
+        return redirects
+            .order(schema::redirects::source.asc())
+            .limit(limit)
+            .offset(offset)
+            .load::<Redirect>(&_connection)
+            .expect("E.");
+    }
+    pub fn count_redirects() -> i64 {
+        use crate::schema::redirects::dsl::redirects;
+
+        let _connection = establish_connection();
+        return redirects
+            .count()
+            .get_result::<i64>(&_connection)
+            .expect("E.");
+    }
+    // создать или обновить перенаправление с этого адреса
+    pub fn save(source: &str, target: &str, status: i16) -> () {
+        use crate::schema::redirects::dsl::redirects;
+        use chrono::Duration;
+
+        let _source = Redirect::normalize_source(source);
+        let _target = target.trim().to_string();
+        if _source == "/" || _target.is_empty() || _source == Redirect::normalize_source(&_target) {
+            return;
+        }
+        let _status = if status == 302 { 302 } else { 301 };
+
+        let _connection = establish_connection();
+        let _redirect = redirects
+            .filter(schema::redirects::source.eq(&_source))
+            .first::<Redirect>(&_connection);
+        if _redirect.is_ok() {
+            diesel::update(&_redirect.expect("E."))
+                .set(EditRedirect {
+                    source: _source,
+                    target: _target,
+                    status: _status,
+                })
+                .execute(&_connection)
+                .expect("E.");
+        }
+        else {
+            let new_redirect = NewRedirect {
+                source:  _source,
+                target:  _target,
+                status:  _status,
+                hits:    0,
+                created: chrono::Local::now().naive_utc() + Duration::hours(3),
+            };
+            diesel::insert_into(schema::redirects::table)
+                .values(&new_redirect)
+                .execute(&_connection)
+                .expect("E.");
+        }
+    }
+}
+
+#[derive(Serialize, Insertable)]
+#[table_name="redirects"]
+pub struct NewRedirect {
+    pub source:  String,
+    pub target:  String,
+    pub status:  i16,
+    pub hits:    i32,
+    pub created: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Serialize, Deserialize, AsChangeset, Debug)]
+#[table_name="redirects"]
+pub struct EditRedirect {
+    pub source: String,
+    pub target: String,
+    pub status: i16,
+}
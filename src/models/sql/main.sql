@@ -23,7 +23,9 @@ CREATE TABLE orders (
     created     TIMESTAMP NOT NULL,
     user_id     INT NOT NULL,
     price       INT NOT NULL,
-    price_acc   INT
+    price_acc   INT,
+    status      SMALLINT NOT NULL DEFAULT 1, -- статус, см. order_status_history
+    promo_code  VARCHAR(50)                  -- промокод, см. discount_rules
 );
 
 CREATE TABLE order_files (
@@ -54,6 +56,26 @@ CREATE TABLE users (
     UNIQUE(email)
 );
 
+-- 1 новый, 2 рассмотрен, 3 отправлено предложение, 4 принят,
+-- 5 в работе, 6 сдан, 7 закрыт, 8 отменен
+CREATE TABLE order_status_history (
+    id       SERIAL PRIMARY KEY,
+    order_id INT NOT NULL,
+    status   SMALLINT NOT NULL,  -- в какой статус перешел заказ
+    user_id  INT,                -- кто перевел; пусто - сам заказчик
+    comment  VARCHAR(1000),      -- пояснение, его видит заказчик
+    created  TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_order_status_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id),
+
+    CONSTRAINT fk_order_status_user
+        FOREIGN KEY(user_id)
+            REFERENCES users(id)
+);
+CREATE INDEX order_status_history_order_id_idx ON order_status_history (order_id);
+
 -- chat -------
 ---------------
 ---------------
@@ -100,6 +122,44 @@ CREATE TABLE cookie_users (
     seconds    INT NOT NULL,
     created    TIMESTAMP NOT NULL     -- когда создан пользователь
 );
+CREATE TABLE order_changes (
+    id        SERIAL PRIMARY KEY,
+    order_id  INT NOT NULL,
+    c_user_id INT NOT NULL,           -- куки пользователь, владелец заказа
+    changes   VARCHAR(5000) NOT NULL, -- что изменено, по строке на поле
+    old_price INT NOT NULL,
+    new_price INT NOT NULL,
+    created   TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_order_change_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id),
+
+    CONSTRAINT fk_order_change_c_user
+        FOREIGN KEY(c_user_id)
+            REFERENCES cookie_users(id)
+);
+CREATE INDEX order_changes_order_id_idx ON order_changes (order_id);
+CREATE TABLE order_documents (
+    id        SERIAL PRIMARY KEY,
+    order_id  INT NOT NULL,
+    types     SMALLINT NOT NULL,     -- 1 коммерческое предложение, 2 счет
+    year      SMALLINT NOT NULL,     -- нумерация своя на каждый год
+    seq       INT NOT NULL,          -- порядковый номер в году
+    number    VARCHAR(50) NOT NULL,  -- номер для печати, КП-2026-0001
+    price     INT NOT NULL,          -- цена заказа на момент выдачи
+    price_acc INT,                   -- скидка на момент выдачи
+    lines     TEXT NOT NULL,         -- строки заказа на момент выдачи, json
+    created   TIMESTAMP NOT NULL,
+
+    UNIQUE(types, year, seq),
+
+    CONSTRAINT fk_order_document_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id)
+);
+CREATE INDEX order_documents_order_id_idx ON order_documents (order_id);
+
 CREATE TABLE cookie_stats (
     id         SERIAL PRIMARY KEY,
     user_id    INT NOT NULL,          -- связь с пользователем куки
@@ -162,15 +222,24 @@ CREATE TABLE categories (
     types       SMALLINT NOT NULL, -- категория блога, категория услуги ......
     slug        VARCHAR(100) NOT NULL,
     now_u       SMALLINT NOT NULL DEFAULT 0,
+    parent_id   INT,               -- родительская категория того же типа
+    deleted_at  TIMESTAMP,         -- когда перенесена в корзину
+
+    UNIQUE(slug, types),
 
-    UNIQUE(slug)
+    CONSTRAINT fk_category_parent
+        FOREIGN KEY(parent_id)
+            REFERENCES categories(id)
+            ON DELETE SET NULL
 );
+CREATE INDEX categories_parent_idx ON categories (parent_id);
+CREATE INDEX categories_deleted_at_idx ON categories (deleted_at);
 
 CREATE TABLE items (
     id          SERIAL PRIMARY KEY,
     title       VARCHAR(100) NOT NULL,
     description VARCHAR,
-    content     VARCHAR(30000),
+    content     TEXT,                -- html, собирается из content_md
     link        VARCHAR(500),
     image       VARCHAR(500),
     is_active   boolean NOT NULL,
@@ -185,23 +254,42 @@ CREATE TABLE items (
     types       SMALLINT NOT NULL, -- блог, услуга, товар ......
     slug        VARCHAR(100) NOT NULL,
     now_u       SMALLINT NOT NULL DEFAULT 0,
+    publish_at   TIMESTAMP, -- когда опубликовать
+    unpublish_at TIMESTAMP, -- когда скрыть
+    content_md  VARCHAR(30000),     -- markdown, из него собирается content
+    category_id INT,                -- основная категория, по ней канонический адрес
+    deleted_at  TIMESTAMP,          -- когда перенесен в корзину
 
-    UNIQUE(slug),
+    UNIQUE(slug, types),
 
     CONSTRAINT fk_store_creator
         FOREIGN KEY(user_id)
-            REFERENCES users(id)
+            REFERENCES users(id),
+
+    CONSTRAINT fk_item_main_category
+        FOREIGN KEY(category_id)
+            REFERENCES categories(id)
+            ON DELETE SET NULL
 );
 CREATE INDEX items_creator_idx ON items (user_id);
+CREATE INDEX items_category_idx ON items (category_id);
+CREATE INDEX items_deleted_at_idx ON items (deleted_at);
+CREATE INDEX items_publish_at_idx ON items (publish_at);
+CREATE INDEX items_unpublish_at_idx ON items (unpublish_at);
 
 
 CREATE TABLE item_comments (
     id        SERIAL PRIMARY KEY,
-    comment   VARCHAR(1000) NOT NULL,
+    comment   TEXT NOT NULL,                -- до 1000 видимых символов, хранится экранированным
     item_id   INT NOT NULL,
-    user_id   INT NOT NULL,
+    user_id   INT,                          -- зарегистрированный автор
     parent_id INT,
     created   TIMESTAMP NOT NULL,
+    c_user_id INT,                          -- куки пользователь (аноним)
+    username  VARCHAR(100) NOT NULL DEFAULT '', -- имя анонима
+    types     SMALLINT NOT NULL DEFAULT 1,  -- обычный / изменен / удален
+    status    SMALLINT NOT NULL DEFAULT 2,  -- на модерации / опубликован / отклонен
+    spam_score SMALLINT NOT NULL DEFAULT 0, -- оценка эвристики спама
 
     CONSTRAINT fk_item_comment
         FOREIGN KEY(item_id)
@@ -217,6 +305,131 @@ CREATE TABLE item_comments (
 );
 CREATE INDEX item_comments_id_idx ON item_comments (item_id);
 CREATE INDEX item_comments_user_id_idx ON item_comments (user_id);
+CREATE INDEX item_comments_parent_id_idx ON item_comments (parent_id);
+CREATE INDEX item_comments_status_idx ON item_comments (status);
+
+CREATE TABLE item_revisions (
+    id          SERIAL PRIMARY KEY,
+    item_id     INT NOT NULL,
+    user_id     INT NOT NULL,        -- автор правки
+    title       VARCHAR(100) NOT NULL,
+    description VARCHAR,
+    content     TEXT,
+    slug        VARCHAR(100) NOT NULL,
+    types       SMALLINT NOT NULL,   -- 1 правка объекта / 2 правка текста / 3 восстановление / 4 исходное состояние
+    created     TIMESTAMP NOT NULL,
+    content_md  VARCHAR(30000),
+
+    CONSTRAINT fk_item_revision
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_user_item_revision
+        FOREIGN KEY(user_id)
+            REFERENCES users(id)
+);
+CREATE INDEX item_revisions_item_id_idx ON item_revisions (item_id);
+
+CREATE TABLE item_previews (
+    id         SERIAL PRIMARY KEY,
+    item_id    INT NOT NULL,
+    user_id    INT NOT NULL,       -- кто выдал ссылку
+    expires    TIMESTAMP NOT NULL, -- до какого времени ссылка работает
+    is_revoked BOOLEAN NOT NULL DEFAULT false,
+    view       INT NOT NULL DEFAULT 0,
+    created    TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_item_preview
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_user_item_preview
+        FOREIGN KEY(user_id)
+            REFERENCES users(id)
+);
+CREATE INDEX item_previews_item_id_idx ON item_previews (item_id);
+
+CREATE TABLE related_items (
+    id         SERIAL PRIMARY KEY,
+    item_id    INT NOT NULL,       -- для какого объекта
+    related_id INT NOT NULL,       -- похожий объект
+    score      FLOAT NOT NULL,     -- чем больше, тем выше в списке
+    created    TIMESTAMP NOT NULL, -- когда посчитан
+
+    CONSTRAINT fk_related_item
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_related_item_related
+        FOREIGN KEY(related_id)
+            REFERENCES items(id)
+);
+CREATE INDEX related_items_item_id_idx ON related_items (item_id);
+CREATE INDEX related_items_related_id_idx ON related_items (related_id);
+
+CREATE TABLE wiki_links (
+    id        SERIAL PRIMARY KEY,
+    item_id   INT NOT NULL,          -- статья, в тексте которой ссылка
+    target    VARCHAR(200) NOT NULL, -- текст внутри [[...]] в нижнем регистре
+    target_id INT,                   -- найденная статья, пусто - такой статьи нет
+    created   TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_wiki_link_item
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_wiki_link_target
+        FOREIGN KEY(target_id)
+            REFERENCES items(id)
+            ON DELETE SET NULL
+);
+CREATE INDEX wiki_links_item_id_idx ON wiki_links (item_id);
+CREATE INDEX wiki_links_target_id_idx ON wiki_links (target_id);
+CREATE INDEX wiki_links_target_idx ON wiki_links (target);
+
+CREATE TABLE help_votes (
+    id         SERIAL PRIMARY KEY,
+    item_id    INT NOT NULL,       -- объект справки (types 6)
+    c_user_id  INT NOT NULL,       -- куки пользователь, один голос на статью
+    is_helpful BOOLEAN NOT NULL,   -- да / нет
+    comment    VARCHAR(1000),      -- необязательный отзыв
+    created    TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_help_vote_item
+        FOREIGN KEY(item_id)
+            REFERENCES items(id),
+
+    CONSTRAINT fk_help_vote_c_user
+        FOREIGN KEY(c_user_id)
+            REFERENCES cookie_users(id),
+
+    UNIQUE(item_id, c_user_id)
+);
+CREATE INDEX help_votes_item_id_idx ON help_votes (item_id);
+
+CREATE TABLE redirects (
+    id       SERIAL PRIMARY KEY,
+    source   VARCHAR(500) NOT NULL, -- старый путь, например /old/page.html
+    target   VARCHAR(500) NOT NULL, -- куда ведем: путь или полный адрес
+    status   SMALLINT NOT NULL,     -- 301 / 302
+    hits     INT NOT NULL DEFAULT 0,
+    last_hit TIMESTAMP,
+    created  TIMESTAMP NOT NULL,
+
+    UNIQUE(source)
+);
+
+CREATE TABLE slug_redirects (
+    id          SERIAL PRIMARY KEY,
+    types       SMALLINT NOT NULL,     -- блог, услуга, товар ......
+    is_category BOOLEAN NOT NULL,      -- ссылка категории или объекта
+    old_slug    VARCHAR(100) NOT NULL,
+    new_slug    VARCHAR(100) NOT NULL,
+    created     TIMESTAMP NOT NULL,
+
+    UNIQUE(types, is_category, old_slug)
+);
+CREATE INDEX slug_redirects_new_slug_idx ON slug_redirects (new_slug);
 
 CREATE TABLE category (
     id            SERIAL PRIMARY KEY,
@@ -327,6 +540,54 @@ CREATE TABLE tech_categories_items (
     is_active   SMALLINT NOT NULL -- тип: 1 - активно, 2 - неактивно
 );
 
+-- выбранные опции копируются в заказ целиком, так что
+-- правка или удаление опции в каталоге не меняет старые заказы
+CREATE TABLE order_lines (
+    id            SERIAL PRIMARY KEY,
+    order_id      INT NOT NULL,
+    serve_id      INT,                   -- опция каталога; пусто, если ее удалили
+    name          VARCHAR(100) NOT NULL, -- название опции на момент заказа
+    price         INT NOT NULL,
+    man_hours     SMALLINT NOT NULL,
+    tech_cat_id   INT NOT NULL,          -- тех. категория опции
+    tech_cat_name VARCHAR(100) NOT NULL,
+    position      SMALLINT NOT NULL,
+
+    CONSTRAINT fk_order_line_order
+        FOREIGN KEY(order_id)
+            REFERENCES orders(id),
+
+    CONSTRAINT fk_order_line_serve
+        FOREIGN KEY(serve_id)
+            REFERENCES serve(id)
+            ON DELETE SET NULL
+);
+CREATE INDEX order_lines_order_id_idx ON order_lines (order_id);
+
+-- из нестыкуемых правил срабатывает самое выгодное,
+-- стыкуемые добавляются к нему. Скидка не больше цены.
+CREATE TABLE discount_rules (
+    id           SERIAL PRIMARY KEY,
+    name         VARCHAR(100) NOT NULL,
+    min_price    INT NOT NULL DEFAULT 0,  -- порог, включительно
+    percent      SMALLINT,                -- скидка в процентах
+    amount       INT,                     -- или фиксированной суммой
+    tech_cat_id  INT,                     -- только опции этой тех. категории
+    types        SMALLINT,                -- только объекты этого типа (2 услуга, 3 товар, 5 работа)
+    promo_code   VARCHAR(50),             -- работает только с этим промокодом
+    starts_at    TIMESTAMP,
+    ends_at      TIMESTAMP,
+    is_stackable BOOLEAN NOT NULL DEFAULT false,
+    is_active    BOOLEAN NOT NULL DEFAULT true,
+    created      TIMESTAMP NOT NULL,
+
+    CONSTRAINT fk_discount_rule_tech_cat
+        FOREIGN KEY(tech_cat_id)
+            REFERENCES tech_categories(id)
+            ON DELETE CASCADE
+);
+CREATE INDEX discount_rules_promo_code_idx ON discount_rules (promo_code);
+
 CREATE TABLE stat_pages (
     id      SERIAL PRIMARY KEY,
     types   SMALLINT NOT NULL,  -- главная страница, инфо ......
@@ -10,6 +10,14 @@ use crate::views::{
     tag_progs,
     serve_progs,
     help_progs,
+    comment_progs,
+    revision_progs,
+    preview_progs,
+    redirect_progs,
+    discount_progs,
+    feed_progs,
+    sitemap_progs,
+    trash_progs,
     search_progs,
     pages,
     progs,
@@ -20,6 +28,9 @@ pub fn routes(cfg: &mut web::ServiceConfig) {
     cfg
     .configure(pages::pages_routes)
     .configure(progs::progs_routes)
+    // ленты раньше категорий: /blogs/{slug}/rss/ иначе
+    // попадет во вложенный адрес категории
+    .configure(feed_progs::feed_routes)
     .configure(blog_progs::blog_routes)
     .configure(service_progs::service_routes)
     .configure(store_progs::store_routes)
@@ -31,5 +42,12 @@ pub fn routes(cfg: &mut web::ServiceConfig) {
     .configure(auth::auth_routes)
     .configure(help_progs::help_routes)
     .configure(order_progs::order_routes)
+    .configure(comment_progs::comment_routes)
+    .configure(revision_progs::revision_routes)
+    .configure(preview_progs::preview_routes)
+    .configure(redirect_progs::redirect_routes)
+    .configure(discount_progs::discount_routes)
+    .configure(sitemap_progs::sitemap_routes)
+    .configure(trash_progs::trash_routes)
     ;
 }
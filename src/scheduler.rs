@@ -0,0 +1,59 @@
+use actix::prelude::{Actor, Context, AsyncContext};
+use actix_web::web;
+use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::models::{Item, Categories};
+use crate::vars::{scheduler_interval, trash_days};
+
+
+// фоновые задачи внутри сервера: раз в SCHEDULER_INTERVAL секунд
+// публикует и скрывает объекты по расписанию и чистит корзину.
+// Запросы к базе блокирующие и падают на ошибках (.expect), поэтому
+// задачи идут в пуле web::block: ошибка пишется в лог, а не роняет
+// главный арбитр. Пока прошлый проход не закончен, новый не начинается.
+pub struct Scheduler;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+impl Scheduler {
+    pub fn run_tasks() -> () {
+        let now = chrono::Local::now().naive_utc() + chrono::Duration::hours(3);
+
+        for _item in Item::get_items_for_publish(now).iter() {
+            info!("scheduler: publish item {}", _item.id);
+            _item.publish();
+        }
+        for _item in Item::get_items_for_hide(now).iter() {
+            info!("scheduler: hide item {}", _item.id);
+            _item.hide();
+        }
+
+        let purge_before = now - chrono::Duration::days(trash_days());
+        for _item in Item::get_items_for_purge(purge_before).iter() {
+            info!("scheduler: purge item {}", _item.id);
+            _item.purge();
+        }
+        for _category in Categories::get_categories_for_purge(purge_before).iter() {
+            info!("scheduler: purge category {}", _category.id);
+            _category.purge();
+        }
+    }
+}
+
+impl Actor for Scheduler {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(Duration::from_secs(scheduler_interval()), |_, _| {
+            if RUNNING.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            actix::spawn(async {
+                if let Err(e) = web::block(Scheduler::run_tasks).await {
+                    error!("scheduler: tasks failed: {}", e);
+                }
+                RUNNING.store(false, Ordering::SeqCst);
+            });
+        });
+    }
+}
@@ -12,6 +12,8 @@ table! {
         types -> Int2,
         slug -> Varchar,
         now_u -> Int2,
+        parent_id -> Nullable<Int4>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -63,6 +65,24 @@ table! {
     }
 }
 
+table! {
+    discount_rules (id) {
+        id -> Int4,
+        name -> Varchar,
+        min_price -> Int4,
+        percent -> Nullable<Int2>,
+        amount -> Nullable<Int4>,
+        tech_cat_id -> Nullable<Int4>,
+        types -> Nullable<Int2>,
+        promo_code -> Nullable<Varchar>,
+        starts_at -> Nullable<Timestamp>,
+        ends_at -> Nullable<Timestamp>,
+        is_stackable -> Bool,
+        is_active -> Bool,
+        created -> Timestamp,
+    }
+}
+
 table! {
     feedbacks (id) {
         id -> Int4,
@@ -87,14 +107,30 @@ table! {
     }
 }
 
+table! {
+    help_votes (id) {
+        id -> Int4,
+        item_id -> Int4,
+        c_user_id -> Int4,
+        is_helpful -> Bool,
+        comment -> Nullable<Varchar>,
+        created -> Timestamp,
+    }
+}
+
 table! {
     item_comments (id) {
         id -> Int4,
-        comment -> Varchar,
+        comment -> Text,
         item_id -> Int4,
-        user_id -> Int4,
+        user_id -> Nullable<Int4>,
         parent_id -> Nullable<Int4>,
         created -> Timestamp,
+        c_user_id -> Nullable<Int4>,
+        username -> Varchar,
+        types -> Int2,
+        status -> Int2,
+        spam_score -> Int2,
     }
 }
 
@@ -103,7 +139,7 @@ table! {
         id -> Int4,
         title -> Varchar,
         description -> Nullable<Varchar>,
-        content -> Nullable<Varchar>,
+        content -> Nullable<Text>,
         link -> Nullable<Varchar>,
         image -> Nullable<Varchar>,
         is_active -> Bool,
@@ -118,6 +154,38 @@ table! {
         types -> Int2,
         slug -> Varchar,
         now_u -> Int2,
+        publish_at -> Nullable<Timestamp>,
+        unpublish_at -> Nullable<Timestamp>,
+        content_md -> Nullable<Varchar>,
+        category_id -> Nullable<Int4>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    item_previews (id) {
+        id -> Int4,
+        item_id -> Int4,
+        user_id -> Int4,
+        expires -> Timestamp,
+        is_revoked -> Bool,
+        view -> Int4,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    item_revisions (id) {
+        id -> Int4,
+        item_id -> Int4,
+        user_id -> Int4,
+        title -> Varchar,
+        description -> Nullable<Varchar>,
+        content -> Nullable<Text>,
+        slug -> Varchar,
+        types -> Int2,
+        created -> Timestamp,
+        content_md -> Nullable<Varchar>,
     }
 }
 
@@ -133,6 +201,33 @@ table! {
     }
 }
 
+table! {
+    order_changes (id) {
+        id -> Int4,
+        order_id -> Int4,
+        c_user_id -> Int4,
+        changes -> Varchar,
+        old_price -> Int4,
+        new_price -> Int4,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    order_documents (id) {
+        id -> Int4,
+        order_id -> Int4,
+        types -> Int2,
+        year -> Int2,
+        seq -> Int4,
+        number -> Varchar,
+        price -> Int4,
+        price_acc -> Nullable<Int4>,
+        lines -> Text,
+        created -> Timestamp,
+    }
+}
+
 table! {
     order_files (id) {
         id -> Int4,
@@ -141,6 +236,20 @@ table! {
     }
 }
 
+table! {
+    order_lines (id) {
+        id -> Int4,
+        order_id -> Int4,
+        serve_id -> Nullable<Int4>,
+        name -> Varchar,
+        price -> Int4,
+        man_hours -> Int2,
+        tech_cat_id -> Int4,
+        tech_cat_name -> Varchar,
+        position -> Int2,
+    }
+}
+
 table! {
     orders (id) {
         id -> Int4,
@@ -154,6 +263,41 @@ table! {
         user_id -> Int4,
         price -> Int4,
         price_acc -> Nullable<Int4>,
+        status -> Int2,
+        promo_code -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    order_status_history (id) {
+        id -> Int4,
+        order_id -> Int4,
+        status -> Int2,
+        user_id -> Nullable<Int4>,
+        comment -> Nullable<Varchar>,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    redirects (id) {
+        id -> Int4,
+        source -> Varchar,
+        target -> Varchar,
+        status -> Int2,
+        hits -> Int4,
+        last_hit -> Nullable<Timestamp>,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    related_items (id) {
+        id -> Int4,
+        item_id -> Int4,
+        related_id -> Int4,
+        score -> Float8,
+        created -> Timestamp,
     }
 }
 
@@ -201,6 +345,17 @@ table! {
     }
 }
 
+table! {
+    slug_redirects (id) {
+        id -> Int4,
+        types -> Int2,
+        is_category -> Bool,
+        old_slug -> Varchar,
+        new_slug -> Varchar,
+        created -> Timestamp,
+    }
+}
+
 table! {
     stat_pages (id) {
         id -> Int4,
@@ -273,19 +428,46 @@ table! {
     }
 }
 
+table! {
+    wiki_links (id) {
+        id -> Int4,
+        item_id -> Int4,
+        target -> Varchar,
+        target_id -> Nullable<Int4>,
+        created -> Timestamp,
+    }
+}
+
 joinable!(category -> categories (categories_id));
 joinable!(category -> items (item_id));
 joinable!(chats -> users (user_id));
 joinable!(cookie_stats -> cookie_users (user_id));
+joinable!(discount_rules -> tech_categories (tech_cat_id));
+joinable!(help_votes -> cookie_users (c_user_id));
+joinable!(help_votes -> items (item_id));
 joinable!(item_comments -> items (item_id));
 joinable!(item_comments -> users (user_id));
+joinable!(item_previews -> items (item_id));
+joinable!(item_previews -> users (user_id));
+joinable!(item_revisions -> items (item_id));
+joinable!(item_revisions -> users (user_id));
+joinable!(items -> categories (category_id));
 joinable!(items -> users (user_id));
 joinable!(messages -> users (user_id));
+joinable!(order_changes -> cookie_users (c_user_id));
+joinable!(order_changes -> orders (order_id));
+joinable!(order_documents -> orders (order_id));
 joinable!(order_files -> orders (order_id));
+joinable!(order_lines -> orders (order_id));
+joinable!(order_lines -> serve (serve_id));
+joinable!(order_status_history -> orders (order_id));
+joinable!(order_status_history -> users (user_id));
+joinable!(related_items -> items (item_id));
 joinable!(serve -> serve_categories (serve_categories));
 joinable!(serve -> users (user_id));
 joinable!(serve_categories -> tech_categories (tech_categories));
 joinable!(tags -> users (user_id));
+joinable!(wiki_links -> items (item_id));
 
 allow_tables_to_appear_in_same_query!(
     categories,
@@ -293,20 +475,32 @@ allow_tables_to_appear_in_same_query!(
     chats,
     cookie_stats,
     cookie_users,
+    discount_rules,
     feedbacks,
     files,
+    help_votes,
     item_comments,
+    item_previews,
+    item_revisions,
     items,
     messages,
+    order_changes,
+    order_documents,
     order_files,
+    order_lines,
+    order_status_history,
     orders,
+    redirects,
+    related_items,
     serve,
     serve_categories,
     serve_items,
+    slug_redirects,
     stat_pages,
     tags,
     tags_items,
     tech_categories,
     tech_categories_items,
     users,
+    wiki_links,
 );
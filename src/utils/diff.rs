@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+
+///////////
+// types:
+// 1. без изменений
+// 2. удалено
+// 3. добавлено
+// 4. изменено
+#[derive(Debug, Serialize)]
+pub struct DiffLine {
+    pub left:  String,
+    pub right: String,
+    pub types: i16,
+}
+
+// сравнение одного поля двух ревизий (заголовок, описание, текст...)
+#[derive(Debug, Serialize)]
+pub struct FieldDiff {
+    pub name:       String,
+    pub lines:      Vec<DiffLine>,
+    pub is_changed: bool,
+}
+
+// html текст объекта часто приходит одной строкой, поэтому
+// переносим строки после закрывающих блочных тегов, иначе
+// любая правка подсвечивала бы весь текст.
+pub fn split_for_diff(text: &str) -> Vec<String> {
+    let mut _text = text.replace("\r\n", "\n");
+    for tag in ["</p>", "</div>", "</li>", "</ul>", "</ol>", "</h2>", "</h3>", "</h4>", "</pre>", "</blockquote>", "</tr>", "<br>"].iter() {
+        _text = _text.replace(tag, &(tag.to_string() + "\n"));
+    }
+    return _text
+        .split("\n")
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+}
+
+// построчное сравнение через наибольшую общую подпоследовательность.
+// подряд идущие удаленные и добавленные строки ставим друг напротив друга,
+// чтобы в двух колонках было видно, что на что заменили.
+pub fn get_diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a = split_for_diff(old);
+    let b = split_for_diff(new);
+    let (n, m) = (a.len(), b.len());
+
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            }
+            else {
+                std::cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut removed: Vec<String> = Vec::new();
+    let mut added: Vec<String> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            flush_changes(&mut lines, &mut removed, &mut added);
+            lines.push(DiffLine {
+                left:  a[i].clone(),
+                right: b[j].clone(),
+                types: 1,
+            });
+            i += 1;
+            j += 1;
+        }
+        else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(b[j].clone());
+            j += 1;
+        }
+        else {
+            removed.push(a[i].clone());
+            i += 1;
+        }
+    }
+    flush_changes(&mut lines, &mut removed, &mut added);
+    return lines;
+}
+
+fn flush_changes(lines: &mut Vec<DiffLine>, removed: &mut Vec<String>, added: &mut Vec<String>) -> () {
+    let count = std::cmp::max(removed.len(), added.len());
+    for k in 0..count {
+        let left = removed.get(k).cloned();
+        let right = added.get(k).cloned();
+        let types = match (&left, &right) {
+            (Some(_), Some(_)) => 4,
+            (Some(_), None) => 2,
+            _ => 3,
+        };
+        lines.push(DiffLine {
+            left:  left.unwrap_or_default(),
+            right: right.unwrap_or_default(),
+            types: types,
+        });
+    }
+    removed.clear();
+    added.clear();
+}
+
+pub fn get_field_diff(name: &str, old: &str, new: &str) -> FieldDiff {
+    let lines = get_diff_lines(old, new);
+    let is_changed = lines.iter().any(|l| l.types != 1);
+    return FieldDiff {
+        name:       name.to_string(),
+        lines:      lines,
+        is_changed: is_changed,
+    };
+}
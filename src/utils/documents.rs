@@ -0,0 +1,293 @@
+use crate::models::{Order, OrderDocument, OrderLine};
+use crate::utils::unescape_html;
+use std::env::var;
+
+
+// реквизиты компании для КП и счетов берутся из .env, например:
+// COMPANY_NAME=ООО "Вебсервисы"
+// COMPANY_INN=7700000000
+// COMPANY_KPP=770001001
+// COMPANY_OGRN=1000000000000
+// COMPANY_ADDRESS=г. Москва, ул. Примерная, д. 1
+// COMPANY_PHONE=+7 900 000-00-00
+// COMPANY_EMAIL=info@вебсервисы.рф
+// COMPANY_BANK=АО "Банк"
+// COMPANY_BIK=044500000
+// COMPANY_ACCOUNT=40702810000000000000
+// COMPANY_CORR_ACCOUNT=30101810000000000000
+// COMPANY_DIRECTOR=Иванов И. И.
+// Пустые поля в документ не попадают.
+pub struct CompanyRequisites {
+    pub name:         String,
+    pub inn:          String,
+    pub kpp:          String,
+    pub ogrn:         String,
+    pub address:      String,
+    pub phone:        String,
+    pub email:        String,
+    pub bank:         String,
+    pub bik:          String,
+    pub account:      String,
+    pub corr_account: String,
+    pub director:     String,
+}
+
+impl CompanyRequisites {
+    pub fn load() -> CompanyRequisites {
+        use dotenv::dotenv;
+
+        dotenv().ok();
+        let get = |name: &str| var(name).unwrap_or_default().trim().to_string();
+        CompanyRequisites {
+            name:         var("COMPANY_NAME").unwrap_or_else(|_| "вебсервисы.рф".to_string()),
+            inn:          get("COMPANY_INN"),
+            kpp:          get("COMPANY_KPP"),
+            ogrn:         get("COMPANY_OGRN"),
+            address:      get("COMPANY_ADDRESS"),
+            phone:        get("COMPANY_PHONE"),
+            email:        get("COMPANY_EMAIL"),
+            bank:         get("COMPANY_BANK"),
+            bik:          get("COMPANY_BIK"),
+            account:      get("COMPANY_ACCOUNT"),
+            corr_account: get("COMPANY_CORR_ACCOUNT"),
+            director:     get("COMPANY_DIRECTOR"),
+        }
+    }
+    // строки шапки документа под названием компании
+    pub fn get_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut codes: Vec<String> = Vec::new();
+        if !self.inn.is_empty() {
+            codes.push("ИНН ".to_string() + &self.inn);
+        }
+        if !self.kpp.is_empty() {
+            codes.push("КПП ".to_string() + &self.kpp);
+        }
+        if !self.ogrn.is_empty() {
+            codes.push("ОГРН ".to_string() + &self.ogrn);
+        }
+        if codes.len() > 0 {
+            lines.push(codes.join(", "));
+        }
+        if !self.address.is_empty() {
+            lines.push(self.address.clone());
+        }
+        let contacts: Vec<String> = [&self.phone, &self.email]
+            .iter()
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_string())
+            .collect();
+        if contacts.len() > 0 {
+            lines.push(contacts.join(", "));
+        }
+        return lines;
+    }
+    // банковские реквизиты, нужны только в счете
+    pub fn get_bank_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for (label, value) in [
+            ("Банк", &self.bank),
+            ("БИК", &self.bik),
+            ("Р/с", &self.account),
+            ("К/с", &self.corr_account),
+        ].iter() {
+            if !value.is_empty() {
+                lines.push(label.to_string() + &": ".to_string() + value.as_str());
+            }
+        }
+        return lines;
+    }
+}
+
+// опции заказа одной тех. категории
+pub struct DocumentGroup {
+    pub tech_cat_id: i32,
+    pub name:        String,
+    pub lines:       Vec<OrderLine>,
+    pub man_hours:   i32,
+    pub price:       i32,
+}
+
+// строки заказа по тех. категориям в порядке появления
+pub fn get_document_groups(lines: Vec<OrderLine>) -> Vec<DocumentGroup> {
+    let mut groups: Vec<DocumentGroup> = Vec::new();
+    for line in lines.into_iter() {
+        let pos = match groups.iter().position(|g| g.tech_cat_id == line.tech_cat_id) {
+            Some(_pos) => _pos,
+            None => {
+                groups.push(DocumentGroup {
+                    tech_cat_id: line.tech_cat_id,
+                    name:        line.tech_cat_name.clone(),
+                    lines:       Vec::new(),
+                    man_hours:   0,
+                    price:       0,
+                });
+                groups.len() - 1
+            },
+        };
+        let group = &mut groups[pos];
+        group.man_hours += line.man_hours as i32;
+        group.price += line.price;
+        group.lines.push(line);
+    }
+    return groups;
+}
+
+// 1234567 -> "1 234 567"
+pub fn get_price_string(price: i32) -> String {
+    let digits = price.abs().to_string();
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(' ');
+        }
+        result.push(c);
+    }
+    if price < 0 {
+        return "-".to_string() + &result;
+    }
+    return result;
+}
+
+// длинные названия опций переносятся по словам
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + word.chars().count() + 1 > width {
+            lines.push(current);
+            current = String::new();
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    return lines;
+}
+
+const PAGE_WIDTH: f64 = 210.0;
+const PAGE_HEIGHT: f64 = 297.0;
+const MARGIN: f64 = 15.0;
+
+// лист A4, на котором текст пишется сверху вниз,
+// новый лист добавляется, когда место кончилось
+struct PdfWriter {
+    doc:   printpdf::PdfDocumentReference,
+    layer: printpdf::PdfLayerReference,
+    font:  printpdf::IndirectFontRef,
+    y:     f64,
+}
+
+impl PdfWriter {
+    fn text(&self, x: f64, size: f64, text: &str) -> () {
+        use printpdf::Mm;
+
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), &self.font);
+    }
+    fn next_line(&mut self, height: f64) -> () {
+        use printpdf::Mm;
+
+        self.y -= height;
+        if self.y < MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Слой 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+}
+
+// PDF документа заказа. None, если не удалось открыть шрифт
+// из DOCUMENT_FONT - тогда остается печать HTML версии.
+pub fn render_document_pdf (
+    order:      &Order,
+    document:   &OrderDocument,
+    groups:     &Vec<DocumentGroup>,
+    requisites: &CompanyRequisites,
+) -> Option<Vec<u8>> {
+    use printpdf::{PdfDocument, Mm};
+    use crate::vars::document_font;
+
+    let title = document.get_type_name() + &" № ".to_string() + &document.number;
+    let (doc, page, layer) = PdfDocument::new(&title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Слой 1");
+    let font_file = std::fs::File::open(document_font()).ok()?;
+    let font = doc.add_external_font(font_file).ok()?;
+    let layer = doc.get_page(page).get_layer(layer);
+    let mut pdf = PdfWriter {
+        doc:   doc,
+        layer: layer,
+        font:  font,
+        y:     PAGE_HEIGHT - MARGIN,
+    };
+
+    pdf.text(MARGIN, 14.0, &requisites.name);
+    pdf.next_line(6.0);
+    for line in requisites.get_lines().iter() {
+        pdf.text(MARGIN, 8.0, line);
+        pdf.next_line(4.0);
+    }
+    pdf.next_line(8.0);
+    pdf.text(MARGIN, 13.0, &(title.clone() + &" от ".to_string() + &document.created.format("%d.%m.%Y").to_string()));
+    pdf.next_line(8.0);
+    // поля заказа хранятся экранированными для html, в PDF нужны символы
+    pdf.text(MARGIN, 10.0, &unescape_html(&("Заказчик: ".to_string() + &order.username + &", ".to_string() + &order.email)));
+    pdf.next_line(5.0);
+    for line in wrap_text(&unescape_html(&("Заказ: ".to_string() + &order.title)), 90).iter() {
+        pdf.text(MARGIN, 10.0, line);
+        pdf.next_line(5.0);
+    }
+    pdf.next_line(5.0);
+
+    pdf.text(MARGIN, 9.0, "Опция");
+    pdf.text(140.0, 9.0, "Часы");
+    pdf.text(160.0, 9.0, "Цена, руб.");
+    pdf.next_line(6.0);
+    for group in groups.iter() {
+        pdf.text(MARGIN, 11.0, &group.name);
+        pdf.next_line(5.0);
+        for line in group.lines.iter() {
+            let name_lines = wrap_text(&line.name, 70);
+            pdf.text(140.0, 9.0, &line.man_hours.to_string());
+            pdf.text(160.0, 9.0, &get_price_string(line.price));
+            for name in name_lines.iter() {
+                pdf.text(MARGIN + 5.0, 9.0, name);
+                pdf.next_line(4.5);
+            }
+        }
+        pdf.text(MARGIN + 5.0, 9.0, "Итого по разделу");
+        pdf.text(140.0, 9.0, &group.man_hours.to_string());
+        pdf.text(160.0, 9.0, &get_price_string(group.price));
+        pdf.next_line(7.0);
+    }
+
+    let man_hours: i32 = groups.iter().map(|g| g.man_hours).sum();
+    pdf.next_line(3.0);
+    pdf.text(MARGIN, 10.0, &("Трудоемкость: ".to_string() + &man_hours.to_string() + &" ч.".to_string()));
+    pdf.next_line(5.0);
+    pdf.text(MARGIN, 10.0, &("Сумма: ".to_string() + &get_price_string(document.price) + &" руб.".to_string()));
+    pdf.next_line(5.0);
+    if let Some(_acc) = document.price_acc.filter(|a| *a > 0) {
+        pdf.text(MARGIN, 10.0, &("Скидка: ".to_string() + &get_price_string(_acc) + &" руб.".to_string()));
+        pdf.next_line(5.0);
+    }
+    pdf.text(MARGIN, 12.0, &("Итого к оплате: ".to_string() + &get_price_string(document.get_total_price()) + &" руб.".to_string()));
+    pdf.next_line(10.0);
+
+    if document.types == 2 {
+        pdf.text(MARGIN, 10.0, &("Получатель: ".to_string() + &requisites.name));
+        pdf.next_line(5.0);
+        for line in requisites.get_bank_lines().iter() {
+            pdf.text(MARGIN, 9.0, line);
+            pdf.next_line(4.5);
+        }
+        pdf.text(MARGIN, 9.0, &("Назначение платежа: оплата по счету № ".to_string() + &document.number));
+        pdf.next_line(10.0);
+    }
+    if !requisites.director.is_empty() {
+        pdf.text(MARGIN, 10.0, &("Руководитель ____________ ".to_string() + &requisites.director));
+    }
+    return pdf.doc.save_to_bytes().ok();
+}
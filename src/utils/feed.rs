@@ -0,0 +1,201 @@
+use actix_web::{HttpRequest, HttpResponse, http::header};
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+
+pub fn escape_xml(text: &str) -> String {
+    return text
+        .replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&apos;");
+}
+
+// время в базе хранится по Москве
+fn get_rfc2822(date: &chrono::NaiveDateTime) -> String {
+    return date.format("%a, %d %b %Y %H:%M:%S +0300").to_string();
+}
+fn get_rfc3339(date: &chrono::NaiveDateTime) -> String {
+    return date.format("%Y-%m-%dT%H:%M:%S+03:00").to_string();
+}
+
+pub struct FeedEntry {
+    pub title:   String,
+    pub url:     String,
+    pub summary: String,
+    pub content: Option<String>,  // полный текст, если лента с ?full=1
+    pub image:   Option<String>,
+    pub created: chrono::NaiveDateTime,
+}
+
+pub struct Feed {
+    pub title:       String,
+    pub description: String,
+    pub url:         String,  // страница, которую описывает лента
+    pub feed_url:    String,  // адрес самой ленты
+    pub entries:     Vec<FeedEntry>,
+}
+
+impl Feed {
+    fn get_updated(&self) -> chrono::NaiveDateTime {
+        return self.entries
+            .iter()
+            .map(|e| e.created)
+            .max()
+            .unwrap_or(chrono::Local::now().naive_utc() + chrono::Duration::hours(3));
+    }
+
+    pub fn render_rss(&self, site_url: &str) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n<channel>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        xml.push_str(&format!("<link>{}{}</link>\n", site_url, escape_xml(&self.url)));
+        xml.push_str(&format!("<description>{}</description>\n", escape_xml(&self.description)));
+        xml.push_str("<language>ru</language>\n");
+        xml.push_str(&format!("<lastBuildDate>{}</lastBuildDate>\n", get_rfc2822(&self.get_updated())));
+        xml.push_str(&format!(
+            "<atom:link href=\"{}{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+            site_url,
+            escape_xml(&self.feed_url),
+        ));
+        for entry in self.entries.iter() {
+            let link = site_url.to_string() + &entry.url;
+            xml.push_str("<item>\n");
+            xml.push_str(&format!("<title>{}</title>\n", escape_xml(&entry.title)));
+            xml.push_str(&format!("<link>{}</link>\n", escape_xml(&link)));
+            xml.push_str(&format!("<guid isPermaLink=\"true\">{}</guid>\n", escape_xml(&link)));
+            xml.push_str(&format!("<pubDate>{}</pubDate>\n", get_rfc2822(&entry.created)));
+            xml.push_str(&format!("<description>{}</description>\n", escape_xml(&entry.summary)));
+            if entry.content.is_some() {
+                xml.push_str(&format!("<content:encoded>{}</content:encoded>\n", escape_xml(entry.content.as_ref().unwrap())));
+            }
+            if entry.image.is_some() {
+                let (length, mime) = get_enclosure_info(entry.image.as_ref().unwrap());
+                xml.push_str(&format!(
+                    "<enclosure url=\"{}\" length=\"{}\" type=\"{}\"/>\n",
+                    escape_xml(&get_absolute_url(site_url, entry.image.as_ref().unwrap())),
+                    length,
+                    mime,
+                ));
+            }
+            xml.push_str("</item>\n");
+        }
+        xml.push_str("</channel>\n</rss>\n");
+        return xml;
+    }
+
+    pub fn render_atom(&self, site_url: &str) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"ru\">\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        xml.push_str(&format!("<subtitle>{}</subtitle>\n", escape_xml(&self.description)));
+        xml.push_str(&format!("<id>{}{}</id>\n", site_url, escape_xml(&self.feed_url)));
+        xml.push_str(&format!("<link href=\"{}{}\"/>\n", site_url, escape_xml(&self.url)));
+        xml.push_str(&format!(
+            "<link rel=\"self\" type=\"application/atom+xml\" href=\"{}{}\"/>\n",
+            site_url,
+            escape_xml(&self.feed_url),
+        ));
+        xml.push_str(&format!("<updated>{}</updated>\n", get_rfc3339(&self.get_updated())));
+        for entry in self.entries.iter() {
+            let link = site_url.to_string() + &entry.url;
+            xml.push_str("<entry>\n");
+            xml.push_str(&format!("<title>{}</title>\n", escape_xml(&entry.title)));
+            xml.push_str(&format!("<link href=\"{}\"/>\n", escape_xml(&link)));
+            xml.push_str(&format!("<id>{}</id>\n", escape_xml(&link)));
+            xml.push_str(&format!("<published>{}</published>\n", get_rfc3339(&entry.created)));
+            xml.push_str(&format!("<updated>{}</updated>\n", get_rfc3339(&entry.created)));
+            xml.push_str(&format!("<summary type=\"html\">{}</summary>\n", escape_xml(&entry.summary)));
+            if entry.content.is_some() {
+                xml.push_str(&format!("<content type=\"html\">{}</content>\n", escape_xml(entry.content.as_ref().unwrap())));
+            }
+            if entry.image.is_some() {
+                let (length, mime) = get_enclosure_info(entry.image.as_ref().unwrap());
+                xml.push_str(&format!(
+                    "<link rel=\"enclosure\" href=\"{}\" length=\"{}\" type=\"{}\"/>\n",
+                    escape_xml(&get_absolute_url(site_url, entry.image.as_ref().unwrap())),
+                    length,
+                    mime,
+                ));
+            }
+            xml.push_str("</entry>\n");
+        }
+        xml.push_str("</feed>\n");
+        return xml;
+    }
+}
+
+fn get_absolute_url(site_url: &str, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return path.to_string();
+    }
+    if path.starts_with('/') {
+        return site_url.to_string() + path;
+    }
+    return site_url.to_string() + &"/".to_string() + path;
+}
+
+// размер файла берем с диска, если картинка лежит у нас
+fn get_enclosure_info(image: &str) -> (u64, String) {
+    let path = image.trim_start_matches('/');
+    let length = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let lower = image.to_lowercase();
+    let mime = if lower.ends_with(".png") {
+        "image/png"
+    }
+    else if lower.ends_with(".gif") {
+        "image/gif"
+    }
+    else if lower.ends_with(".webp") {
+        "image/webp"
+    }
+    else if lower.ends_with(".svg") {
+        "image/svg+xml"
+    }
+    else {
+        "image/jpeg"
+    };
+    return (length, mime.to_string());
+}
+
+pub fn get_etag(key: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    return format!("\"{:x}\"", hasher.finish());
+}
+
+pub fn is_not_modified(req: &HttpRequest, etag: &str) -> bool {
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    return if_none_match.split(',').any(|t| t.trim() == etag || t.trim() == "*");
+}
+
+pub fn get_not_modified_response(etag: String) -> HttpResponse {
+    return HttpResponse::NotModified()
+        .append_header((header::ETAG, etag))
+        .finish();
+}
+
+pub fn get_xml_response_with_etag(body: String, content_type: &str, etag: String) -> HttpResponse {
+    return HttpResponse::Ok()
+        .content_type(content_type)
+        .append_header((header::ETAG, etag))
+        .append_header((header::CACHE_CONTROL, "public, max-age=600"))
+        .body(body);
+}
+
+// ответ с ETag: если документ не менялся, клиент получит 304 без тела.
+// Ленты считают ETag заранее, по дешевому ключу, см. feed_progs
+pub fn get_xml_response(req: &HttpRequest, body: String, content_type: &str) -> HttpResponse {
+    let etag = get_etag(&body);
+    if is_not_modified(req, &etag) {
+        return get_not_modified_response(etag);
+    }
+    return get_xml_response_with_etag(body, content_type, etag);
+}
@@ -54,12 +54,14 @@ pub struct CategoriesForm {
     pub level:       i16,
     pub types:       i16,
     pub slug:        String,
+    pub parent_id:   i32,   // 0 - категория верхнего уровня
 }
 
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct ContentForm {
-    pub content: Option<String>,
+    pub content:    Option<String>,
+    pub content_md: Option<String>, // если заполнен, content собирается из него
 }
 
 pub async fn category_form(payload: &mut Multipart, owner_id: i32) -> CategoriesForm {
@@ -71,6 +73,7 @@ pub async fn category_form(payload: &mut Multipart, owner_id: i32) -> Categories
         level:       0,
         types:       0,
         slug:        "".to_string(),
+        parent_id:   0,
     };
 
     while let Some(item) = payload.next().await {
@@ -113,6 +116,14 @@ pub async fn category_form(payload: &mut Multipart, owner_id: i32) -> Categories
                 }
             }
         }
+        else if name == "parent_id" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    form.parent_id = s.parse().unwrap_or(0);
+                }
+            }
+        }
         else if name == "types" {
             while let Some(chunk) = field.next().await {
                 let data = chunk.expect("split_payload err chunk");
@@ -145,7 +156,43 @@ pub async fn category_form(payload: &mut Multipart, owner_id: i32) -> Categories
 
 pub async fn content_form(payload: &mut Multipart) -> ContentForm {
     let mut form: ContentForm = ContentForm {
-        content: None,
+        content:    None,
+        content_md: None,
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        // длинный текст приходит несколькими кусками - склеиваем
+        let mut bytes: Vec<u8> = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let data = chunk.expect("split_payload err chunk");
+            bytes.extend_from_slice(&data);
+        }
+        if let Ok(s) = str::from_utf8(&bytes) {
+            let data_string = s.to_string();
+            if field.name() == "content" {
+                form.content = Some(data_string);
+            }
+            else if field.name() == "content_md" && !data_string.trim().is_empty() {
+                form.content_md = Some(data_string);
+            }
+        }
+    }
+    form
+}
+
+
+// дата и время из поля datetime-local, например 2026-10-18T10:00
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ScheduleForm {
+    pub publish_at:   Option<chrono::NaiveDateTime>,
+    pub unpublish_at: Option<chrono::NaiveDateTime>,
+}
+pub async fn schedule_form(payload: &mut Multipart) -> ScheduleForm {
+    let mut form: ScheduleForm = ScheduleForm {
+        publish_at:   None,
+        unpublish_at: None,
     };
 
     while let Some(item) = payload.next().await {
@@ -154,9 +201,12 @@ pub async fn content_form(payload: &mut Multipart) -> ContentForm {
         while let Some(chunk) = field.next().await {
             let data = chunk.expect("split_payload err chunk");
             if let Ok(s) = str::from_utf8(&data) {
-                let data_string = s.to_string();
-                if field.name() == "content" {
-                    form.content = Some(data_string)
+                let _date = chrono::NaiveDateTime::parse_from_str(s.trim(), "%Y-%m-%dT%H:%M").ok();
+                if field.name() == "publish_at" {
+                    form.publish_at = _date;
+                }
+                else if field.name() == "unpublish_at" {
+                    form.unpublish_at = _date;
                 }
             }
         }
@@ -164,7 +214,6 @@ pub async fn content_form(payload: &mut Multipart) -> ContentForm {
     form
 }
 
-
 #[derive(Deserialize, Serialize, Debug)]
 pub struct ItemForms {
     pub title:         String,
@@ -172,6 +221,7 @@ pub struct ItemForms {
     pub link:          Option<String>,
     pub main_image:    Option<String>,
     pub category_list: Vec<i32>,
+    pub main_category: i32,       // основная категория из category_list
     pub tags_list:     Vec<i32>,
     pub serve_list:    Vec<i32>,
     pub close_tech_cats_list: Vec<i32>,
@@ -188,6 +238,7 @@ pub async fn item_form(payload: &mut Multipart, owner_id: i32) -> ItemForms {
         link:          None,
         main_image:    None,
         category_list: Vec::new(),
+        main_category: 0,
         tags_list:     Vec::new(),
         serve_list:    Vec::new(),
         close_tech_cats_list: Vec::new(),
@@ -251,6 +302,14 @@ pub async fn item_form(payload: &mut Multipart, owner_id: i32) -> ItemForms {
                 }
             }
         }
+        else if name == "main_category" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    form.main_category = s.parse().unwrap_or(0);
+                }
+            }
+        }
         else if name == "position" {
             while let Some(chunk) = field.next().await {
                 let data = chunk.expect("split_payload err chunk");
@@ -331,16 +390,274 @@ pub async fn feedback_form(payload: &mut Multipart) -> FeedbackForm {
 }
 
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CommentForm {
+    pub comment:   String,
+    pub username:  String,
+    pub parent_id: Option<i32>,
+}
+pub async fn comment_form(payload: &mut Multipart) -> CommentForm {
+    let mut form: CommentForm = CommentForm {
+        comment:   "".to_string(),
+        username:  "".to_string(),
+        parent_id: None,
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        if field.name() == "parent_id" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    let _int: i32 = s.parse().unwrap_or(0);
+                    if _int > 0 {
+                        form.parent_id = Some(_int);
+                    }
+                }
+            }
+        }
+        else {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    let data_string = s.to_string();
+                    if field.name() == "comment" {
+                        form.comment.push_str(&data_string);
+                    } else if field.name() == "username" {
+                        form.username = data_string;
+                    }
+                }
+            }
+        }
+    }
+    form
+}
+
+// is_helpful: "1" - помогла, "0" - нет
+#[derive(Deserialize, Serialize, Debug)]
+pub struct HelpVoteForm {
+    pub is_helpful: bool,
+    pub comment:    String,
+}
+pub async fn help_vote_form(payload: &mut Multipart) -> HelpVoteForm {
+    let mut form: HelpVoteForm = HelpVoteForm {
+        is_helpful: false,
+        comment:    "".to_string(),
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        while let Some(chunk) = field.next().await {
+            let data = chunk.expect("split_payload err chunk");
+            if let Ok(s) = str::from_utf8(&data) {
+                let data_string = s.to_string();
+                if field.name() == "is_helpful" {
+                    form.is_helpful = data_string.trim() == "1";
+                } else if field.name() == "comment" {
+                    form.comment.push_str(&data_string);
+                }
+            }
+        }
+    }
+    form
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrderStatusForm {
+    pub status:  i16,
+    pub comment: String,
+}
+pub async fn order_status_form(payload: &mut Multipart) -> OrderStatusForm {
+    let mut form: OrderStatusForm = OrderStatusForm {
+        status:  0,
+        comment: "".to_string(),
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        while let Some(chunk) = field.next().await {
+            let data = chunk.expect("split_payload err chunk");
+            if let Ok(s) = str::from_utf8(&data) {
+                let data_string = s.to_string();
+                if field.name() == "status" {
+                    form.status = data_string.trim().parse().unwrap_or(0);
+                } else if field.name() == "comment" {
+                    form.comment.push_str(&data_string);
+                }
+            }
+        }
+    }
+    form
+}
+
+// правило скидки. Пустые числовые поля - None, даты
+// из datetime-local, флажки приходят как "1"
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DiscountRuleForm {
+    pub name:         String,
+    pub min_price:    i32,
+    pub percent:      Option<i16>,
+    pub amount:       Option<i32>,
+    pub tech_cat_id:  Option<i32>,
+    pub types:        Option<i16>,
+    pub promo_code:   Option<String>,
+    pub starts_at:    Option<chrono::NaiveDateTime>,
+    pub ends_at:      Option<chrono::NaiveDateTime>,
+    pub is_stackable: bool,
+    pub is_active:    bool,
+}
+pub async fn discount_rule_form(payload: &mut Multipart) -> DiscountRuleForm {
+    let mut form: DiscountRuleForm = DiscountRuleForm {
+        name:         "".to_string(),
+        min_price:    0,
+        percent:      None,
+        amount:       None,
+        tech_cat_id:  None,
+        types:        None,
+        promo_code:   None,
+        starts_at:    None,
+        ends_at:      None,
+        is_stackable: false,
+        is_active:    false,
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        while let Some(chunk) = field.next().await {
+            let data = chunk.expect("split_payload err chunk");
+            if let Ok(s) = str::from_utf8(&data) {
+                let data_string = s.trim().to_string();
+                match field.name() {
+                    "name" => form.name = data_string,
+                    "min_price" => form.min_price = data_string.parse().unwrap_or(0),
+                    "percent" => form.percent = data_string.parse().ok(),
+                    "amount" => form.amount = data_string.parse().ok(),
+                    "tech_cat_id" => form.tech_cat_id = data_string.parse().ok().filter(|i| *i > 0),
+                    "types" => form.types = data_string.parse().ok().filter(|i| *i > 0),
+                    "promo_code" => form.promo_code = Some(data_string).filter(|c| !c.is_empty()),
+                    "starts_at" => form.starts_at = chrono::NaiveDateTime::parse_from_str(&data_string, "%Y-%m-%dT%H:%M").ok(),
+                    "ends_at" => form.ends_at = chrono::NaiveDateTime::parse_from_str(&data_string, "%Y-%m-%dT%H:%M").ok(),
+                    "is_stackable" => form.is_stackable = data_string == "1",
+                    "is_active" => form.is_active = data_string == "1",
+                    _ => (),
+                }
+            }
+        }
+    }
+    form
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RedirectForm {
+    pub source: String,
+    pub target: String,
+    pub status: i16,
+}
+pub async fn redirect_form(payload: &mut Multipart) -> RedirectForm {
+    let mut form: RedirectForm = RedirectForm {
+        source: "".to_string(),
+        target: "".to_string(),
+        status: 301,
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        while let Some(chunk) = field.next().await {
+            let data = chunk.expect("split_payload err chunk");
+            if let Ok(s) = str::from_utf8(&data) {
+                let data_string = s.to_string();
+                if field.name() == "source" {
+                    form.source = data_string;
+                }
+                else if field.name() == "target" {
+                    form.target = data_string;
+                }
+                else if field.name() == "status" {
+                    let _int: i16 = data_string.parse().unwrap_or(301);
+                    form.status = _int;
+                }
+            }
+        }
+    }
+    form
+}
+
+// список перенаправлений одним текстом, по строке на адрес:
+// /old/page.html /new/page/ 301
+pub async fn redirects_import_form(payload: &mut Multipart) -> Vec<RedirectForm> {
+    let mut bytes: Vec<u8> = Vec::new();
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+        if field.name() == "redirects" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                bytes.extend_from_slice(&data);
+            }
+        }
+    }
+
+    let mut list = Vec::new();
+    if let Ok(s) = str::from_utf8(&bytes) {
+        for line in s.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 2 {
+                continue;
+            }
+            list.push(RedirectForm {
+                source: parts[0].to_string(),
+                target: parts[1].to_string(),
+                status: parts.get(2).and_then(|p| p.parse().ok()).unwrap_or(301),
+            });
+        }
+    }
+    list
+}
+
+// форма для массовых действий над объектами (модерация и т.д.)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct IdListForm {
+    pub id_list: Vec<i32>,
+}
+pub async fn id_list_form(payload: &mut Multipart) -> IdListForm {
+    let mut form: IdListForm = IdListForm {
+        id_list: Vec::new(),
+    };
+
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item.expect("split_payload err");
+
+        if field.name() == "id_list[]" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                // нечисловые значения просто пропускаем
+                if let Ok(_int) = str::from_utf8(&data).unwrap_or("").trim().parse::<i32>() {
+                    form.id_list.push(_int);
+                }
+            }
+        }
+    }
+    form
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OrderForms {
-    pub title:       String,
-    pub types:       i16,
-    pub object_id:   i32,
-    pub username:    String,
-    pub description: Option<String>,
-    pub email:       String,
-    pub files:       Vec<String>,
-    pub serve_list:  Vec<i32>,
+    pub title:        String,
+    pub types:        i16,
+    pub object_id:    i32,
+    pub username:     String,
+    pub description:  Option<String>,
+    pub email:        String,
+    pub files:        Vec<String>,
+    pub serve_list:   Vec<i32>,
+    pub serve_change: bool,     // при правке: список опций прислан и его надо применить
+    pub delete_files: Vec<i32>, // id файлов заказа, которые убирают при правке
+    pub promo_code:   Option<String>,
+    pub promo_change: bool,     // при правке: поле промокода прислано и его надо применить
 }
 
 // форма для заказов
@@ -348,20 +665,24 @@ pub async fn order_form(payload: &mut Multipart, owner_id: i32) -> OrderForms {
     let mut files: Vec<UploadedFiles> = Vec::new();
 
     let mut form: OrderForms = OrderForms {
-        title:       "".to_string(),
-        types:       0,
-        object_id:   0,
-        username:    "".to_string(),
-        description: None,
-        email:       "".to_string(),
-        files:       Vec::new(),
-        serve_list:  Vec::new(),
+        title:        "".to_string(),
+        types:        0,
+        object_id:    0,
+        username:     "".to_string(),
+        description:  None,
+        email:        "".to_string(),
+        files:        Vec::new(),
+        serve_list:   Vec::new(),
+        serve_change: false,
+        delete_files: Vec::new(),
+        promo_code:   None,
+        promo_change: false,
     };
 
     while let Some(item) = payload.next().await {
         let mut field: Field = item.expect("split_payload err");
         let name = field.name();
-        let string_list = ["title", "email", "description", "username"];
+        let string_list = ["title", "email", "description", "username", "promo_code"];
 
         if string_list.contains(&name) {
             while let Some(chunk) = field.next().await {
@@ -376,6 +697,8 @@ pub async fn order_form(payload: &mut Multipart, owner_id: i32) -> OrderForms {
                         form.email = data_string;
                     } else if field.name() == "username" {
                         form.username = data_string;
+                    } else if field.name() == "promo_code" {
+                        form.promo_code = Some(data_string.trim().to_string()).filter(|c| !c.is_empty());
                     }
                 }
             }
@@ -405,8 +728,37 @@ pub async fn order_form(payload: &mut Multipart, owner_id: i32) -> OrderForms {
                     let data_string = s.to_string();
                     let v: Vec<&str> = data_string.split(",").collect();
                     for i in v.iter() {
-                        let _int: i32 = i.parse().unwrap();
-                        form.serve_list.push(_int);
+                        if let Ok(_int) = i.trim().parse::<i32>() {
+                            form.serve_list.push(_int);
+                        }
+                    }
+                }
+            }
+        }
+        else if name == "serve_change" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    form.serve_change = s.trim() == "1";
+                }
+            }
+        }
+        else if name == "promo_change" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    form.promo_change = s.trim() == "1";
+                }
+            }
+        }
+        else if name == "delete_files" {
+            while let Some(chunk) = field.next().await {
+                let data = chunk.expect("split_payload err chunk");
+                if let Ok(s) = str::from_utf8(&data) {
+                    for i in s.split(",") {
+                        if let Ok(_int) = i.trim().parse::<i32>() {
+                            form.delete_files.push(_int);
+                        }
                     }
                 }
             }
@@ -11,6 +11,7 @@ pub fn get_categories_for_types_obj(types: i16) -> Vec<Categories> {
     let _connection = establish_connection();
     return categories
         .filter(schema::categories::types.eq(types))
+        .filter(schema::categories::deleted_at.is_null())
         .load::<Categories>(&_connection)
         .expect("E");
 }
@@ -0,0 +1,222 @@
+use pulldown_cmark::{
+    html,
+    CodeBlockKind,
+    Event,
+    HeadingLevel,
+    Options,
+    Parser,
+    Tag,
+};
+use syntect::{
+    html::{ClassedHTMLGenerator, ClassStyle},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+use std::env::var;
+use std::sync::OnceLock;
+
+
+// настройки сборки html из markdown берутся из .env, например:
+// MARKDOWN_TABLES=true
+// MARKDOWN_FOOTNOTES=true
+// MARKDOWN_STRIKETHROUGH=true
+// MARKDOWN_HEADING_ANCHORS=true
+// MARKDOWN_HIGHLIGHT=true
+// подсветка кода идет css классами (стили лежат в static),
+// так что тему можно менять без пересборки текстов.
+pub struct MarkdownOptions {
+    pub tables:          bool,
+    pub footnotes:       bool,
+    pub strikethrough:   bool,
+    pub heading_anchors: bool,
+    pub highlight:       bool,
+}
+
+fn get_bool_var(name: &str) -> bool {
+    return var(name).map(|v| v != "false" && v != "0").unwrap_or(true);
+}
+
+impl MarkdownOptions {
+    pub fn load() -> MarkdownOptions {
+        use dotenv::dotenv;
+
+        dotenv().ok();
+        MarkdownOptions {
+            tables:          get_bool_var("MARKDOWN_TABLES"),
+            footnotes:       get_bool_var("MARKDOWN_FOOTNOTES"),
+            strikethrough:   get_bool_var("MARKDOWN_STRIKETHROUGH"),
+            heading_anchors: get_bool_var("MARKDOWN_HEADING_ANCHORS"),
+            highlight:       get_bool_var("MARKDOWN_HIGHLIGHT"),
+        }
+    }
+    fn get_cmark_options(&self) -> Options {
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+        if self.tables {
+            options.insert(Options::ENABLE_TABLES);
+        }
+        if self.footnotes {
+            options.insert(Options::ENABLE_FOOTNOTES);
+        }
+        if self.strikethrough {
+            options.insert(Options::ENABLE_STRIKETHROUGH);
+        }
+        return options;
+    }
+}
+
+fn get_syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    return SYNTAX_SET.get_or_init(|| SyntaxSet::load_defaults_newlines());
+}
+
+// якорь заголовка: "Установка и настройка" -> "установка-и-настройка"
+pub fn get_anchor(text: &str) -> String {
+    let mut anchor = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            anchor.push(c);
+        }
+        else if (c.is_whitespace() || c == '-' || c == '_') && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+    let anchor = anchor.trim_matches('-').to_string();
+    if anchor.is_empty() {
+        return "section".to_string();
+    }
+    return anchor;
+}
+
+// одинаковые заголовки получают -2, -3...
+pub fn get_unique_anchor(text: &str, used: &mut Vec<String>) -> String {
+    let base = get_anchor(text);
+    let mut anchor = base.clone();
+    let mut n = 2;
+    while used.contains(&anchor) {
+        anchor = format!("{}-{}", base, n);
+        n += 1;
+    }
+    used.push(anchor.clone());
+    return anchor;
+}
+
+fn get_level_number(level: HeadingLevel) -> u8 {
+    return match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    };
+}
+
+fn escape_html(text: &str) -> String {
+    return text
+        .replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;");
+}
+
+// подсветка блока кода. Если язык не указан или незнаком - None,
+// тогда блок уйдет в html как обычный <pre><code>.
+fn highlight_code(lang: &str, code: &str) -> Option<String> {
+    let lang: String = lang
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '+' || *c == '#')
+        .collect();
+    if lang.is_empty() {
+        return None;
+    }
+    let syntax_set = get_syntax_set();
+    let syntax = syntax_set.find_syntax_by_token(&lang)?;
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set, ClassStyle::Spaced);
+    for line in LinesWithEndings::from(code) {
+        if generator.parse_html_for_line_which_includes_newline(line).is_err() {
+            return None;
+        }
+    }
+    return Some(format!(
+        "<pre class=\"code\"><code class=\"language-{}\">{}</code></pre>\n",
+        escape_html(&lang),
+        generator.finalize(),
+    ));
+}
+
+pub fn render_markdown(source: &str) -> String {
+    return render_markdown_with(source, &MarkdownOptions::load());
+}
+
+pub fn render_markdown_with(source: &str, options: &MarkdownOptions) -> String {
+    let parser = Parser::new_ext(source, options.get_cmark_options());
+
+    let mut events: Vec<Event> = Vec::new();
+    let mut used_anchors: Vec<String> = Vec::new();
+    // заголовок и блок кода собираем целиком, а потом
+    // отдаем уже готовым html
+    let mut heading: Option<(HeadingLevel, Option<String>, Vec<Event>)> = None;
+    let mut code: Option<(String, String)> = None;
+
+    for event in parser {
+        if code.is_some() {
+            match event {
+                Event::Text(text) => {
+                    code.as_mut().unwrap().1.push_str(&text);
+                },
+                Event::End(Tag::CodeBlock(kind)) => {
+                    let (lang, text) = code.take().unwrap();
+                    match highlight_code(&lang, &text) {
+                        Some(_html) => events.push(Event::Html(_html.into())),
+                        None => {
+                            events.push(Event::Start(Tag::CodeBlock(kind.clone())));
+                            events.push(Event::Text(text.into()));
+                            events.push(Event::End(Tag::CodeBlock(kind)));
+                        },
+                    }
+                },
+                _ => {},
+            }
+            continue;
+        }
+        if heading.is_some() {
+            match event {
+                Event::End(Tag::Heading(..)) => {
+                    let (level, id, inner) = heading.take().unwrap();
+                    let text: String = inner.iter().filter_map(|e| match e {
+                        Event::Text(t) | Event::Code(t) => Some(t.to_string()),
+                        _ => None,
+                    }).collect();
+                    let anchor = match id {
+                        Some(_id) => get_unique_anchor(&_id, &mut used_anchors),
+                        None => get_unique_anchor(&text, &mut used_anchors),
+                    };
+                    let n = get_level_number(level);
+                    events.push(Event::Html(format!("<h{} id=\"{}\">", n, escape_html(&anchor)).into()));
+                    events.extend(inner);
+                    events.push(Event::Html(format!(
+                        "<a class=\"anchor\" href=\"#{}\">#</a></h{}>\n",
+                        escape_html(&anchor),
+                        n,
+                    ).into()));
+                },
+                _ => heading.as_mut().unwrap().2.push(event),
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading(level, id, _)) if options.heading_anchors => {
+                heading = Some((level, id.map(|i| i.to_string()), Vec::new()));
+            },
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) if options.highlight => {
+                code = Some((lang.to_string(), String::new()));
+            },
+            _ => events.push(event),
+        }
+    }
+
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events.into_iter());
+    return html_output;
+}
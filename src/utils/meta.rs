@@ -0,0 +1,190 @@
+use crate::models::Item;
+use crate::utils::{escape_xml, sanitize_plain, unescape_html};
+use crate::vars::site_url;
+use serde_json::{json, Value};
+
+
+static SITE_NAME: &str = "вебсервисы.рф";
+
+// метаданные страницы для первой загрузки: OpenGraph, Twitter,
+// каноническая ссылка и JSON-LD по типу содержимого.
+pub struct PageMeta {
+    pub title:       String,
+    pub description: String,
+    pub uri:         String,
+    pub canonical:   String,          // у объектов - адрес через основную категорию
+    pub image:       String,
+    pub og_type:     String,          // website, article, product
+    pub published:   Option<chrono::NaiveDateTime>,
+    pub schema:      Option<Value>,   // JSON-LD
+}
+
+fn get_absolute(path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return path.to_string();
+    }
+    return site_url() + path;
+}
+
+impl PageMeta {
+    pub fn new(title: String, description: String, uri: String, image: String) -> PageMeta {
+        PageMeta {
+            title:       title,
+            description: description,
+            canonical:   uri.clone(),
+            uri:         uri,
+            image:       image,
+            og_type:     "website".to_string(),
+            published:   None,
+            schema:      None,
+        }
+    }
+
+    // для объектов описание берем из самого объекта,
+    // если оно заполнено
+    pub fn for_item(item: &Item, title: String, description: String, uri: String) -> PageMeta {
+        let _description = match item.description {
+            // в описании бывает разметка, в meta нужен чистый текст
+            Some(ref d) if !d.trim().is_empty() => unescape_html(&sanitize_plain(d)),
+            _ => description,
+        };
+        let mut meta = PageMeta::new(title, _description, uri, item.get_image());
+        meta.canonical = item.get_url();
+        meta.published = Some(item.created);
+        match item.types {
+            1 | 4 | 5 => meta.set_article(item),
+            2 => meta.set_service(item),
+            3 => meta.set_product(item),
+            _ => (),
+        };
+        return meta;
+    }
+
+    // статья блога и wiki (работы размечаем так же)
+    fn set_article(&mut self, item: &Item) {
+        self.og_type = "article".to_string();
+        self.schema = Some(json!({
+            "@context": "https://schema.org",
+            "@type": "Article",
+            "headline": item.title,
+            "description": self.description,
+            "image": get_absolute(&self.image),
+            "datePublished": item.created.format("%Y-%m-%dT%H:%M:%S+03:00").to_string(),
+            "mainEntityOfPage": get_absolute(&self.canonical),
+            "publisher": {
+                "@type": "Organization",
+                "name": SITE_NAME,
+                "url": site_url(),
+            },
+        }));
+    }
+
+    // товар: цена со скидкой, если она есть
+    fn set_product(&mut self, item: &Item) {
+        self.og_type = "product".to_string();
+        let mut offer = json!({
+            "@type": "Offer",
+            "price": item.price,
+            "priceCurrency": "RUB",
+            "availability": "https://schema.org/InStock",
+            "url": get_absolute(&self.canonical),
+        });
+        if item.price_acc.is_some() && item.price_acc.unwrap() > 0 {
+            offer["price"] = json!(item.get_price_with_discount());
+            offer["priceSpecification"] = json!({
+                "@type": "PriceSpecification",
+                "price": item.price,
+                "priceCurrency": "RUB",
+            });
+        }
+        self.schema = Some(json!({
+            "@context": "https://schema.org",
+            "@type": "Product",
+            "name": item.title,
+            "description": self.description,
+            "image": get_absolute(&self.image),
+            "offers": offer,
+        }));
+    }
+
+    fn set_service(&mut self, item: &Item) {
+        let mut schema = json!({
+            "@context": "https://schema.org",
+            "@type": "Service",
+            "name": item.title,
+            "description": self.description,
+            "image": get_absolute(&self.image),
+            "url": get_absolute(&self.canonical),
+            "provider": {
+                "@type": "Organization",
+                "name": SITE_NAME,
+                "url": site_url(),
+            },
+        });
+        if item.price > 0 {
+            schema["offers"] = json!({
+                "@type": "Offer",
+                "price": item.get_price_with_discount(),
+                "priceCurrency": "RUB",
+            });
+        }
+        self.schema = Some(schema);
+    }
+
+    // страница категории помощи: пары вопрос - ответ
+    pub fn set_faq(&mut self, questions: Vec<(String, String)>) {
+        let entities: Vec<Value> = questions
+            .into_iter()
+            .map(|(question, answer)| json!({
+                "@type": "Question",
+                "name": question,
+                "acceptedAnswer": {
+                    "@type": "Answer",
+                    "text": answer,
+                },
+            }))
+            .collect();
+        if entities.is_empty() {
+            return;
+        }
+        self.schema = Some(json!({
+            "@context": "https://schema.org",
+            "@type": "FAQPage",
+            "mainEntity": entities,
+        }));
+    }
+
+    // готовый блок для <head>. Выводится в шаблоне как есть.
+    pub fn render(&self) -> String {
+        let url = escape_xml(&get_absolute(&self.canonical));
+        let title = escape_xml(&self.title);
+        let description = escape_xml(&self.description);
+        let image = escape_xml(&get_absolute(&self.image));
+
+        let mut html = String::new();
+        html.push_str(&format!("<link rel=\"canonical\" href=\"{}\">\n", url));
+        html.push_str(&format!("<meta property=\"og:site_name\" content=\"{}\">\n", SITE_NAME));
+        html.push_str(&format!("<meta property=\"og:type\" content=\"{}\">\n", self.og_type));
+        html.push_str(&format!("<meta property=\"og:title\" content=\"{}\">\n", title));
+        html.push_str(&format!("<meta property=\"og:description\" content=\"{}\">\n", description));
+        html.push_str(&format!("<meta property=\"og:url\" content=\"{}\">\n", url));
+        html.push_str(&format!("<meta property=\"og:image\" content=\"{}\">\n", image));
+        html.push_str("<meta property=\"og:locale\" content=\"ru_RU\">\n");
+        if self.published.is_some() && self.og_type == "article" {
+            html.push_str(&format!(
+                "<meta property=\"article:published_time\" content=\"{}\">\n",
+                self.published.unwrap().format("%Y-%m-%dT%H:%M:%S+03:00"),
+            ));
+        }
+        html.push_str("<meta name=\"twitter:card\" content=\"summary_large_image\">\n");
+        html.push_str(&format!("<meta name=\"twitter:title\" content=\"{}\">\n", title));
+        html.push_str(&format!("<meta name=\"twitter:description\" content=\"{}\">\n", description));
+        html.push_str(&format!("<meta name=\"twitter:image\" content=\"{}\">\n", image));
+        if self.schema.is_some() {
+            // "</" внутри json закрыл бы тег script
+            let json = self.schema.as_ref().unwrap().to_string().replace("</", "<\\/");
+            html.push_str(&format!("<script type=\"application/ld+json\">{}</script>\n", json));
+        }
+        return html;
+    }
+}
@@ -1,11 +1,35 @@
 mod forms;
 mod auth;
 mod stat;
+mod spam;
+mod diff;
+mod preview;
+mod markdown;
+mod sanitize;
+mod slug;
+mod feed;
+mod sitemap;
+mod meta;
+mod wiki;
+mod toc;
+mod documents;
 
 pub use self::{
     forms::*,
     auth::*,
     stat::*,
+    spam::*,
+    diff::*,
+    preview::*,
+    markdown::*,
+    sanitize::*,
+    slug::*,
+    feed::*,
+    sitemap::*,
+    meta::*,
+    wiki::*,
+    toc::*,
+    documents::*,
 };
 use actix_web::{
     HttpRequest,
@@ -48,23 +72,6 @@ pub struct IndexResponse {
     pub messages: Vec<String>,
 }
 
-pub fn get_price_acc_values(price: &i32) -> Option<i32> {
-    if price > &3_000_000 {
-        let acc = (price * 10) / 100; // 10% скидка
-        return Some(acc);
-    }
-    else if price > &2_000_000 && price < &3_000_000 {
-        let acc = (price * 7) / 100; // 10% скидка
-        return Some(acc);
-    }
-    else if price > &1_000_000 && price < &2_000_000 {
-        let acc = (price * 5) / 100; // 5% скидка
-        return Some(acc);
-    }
-    else {
-        return None;
-    }
-}
 //lazy_static! {
     pub fn establish_connection() -> PgConnection {
         use dotenv::dotenv;
@@ -257,6 +264,23 @@ pub async fn get_first_load_page (
     image:          String,
     template_types: i16
 ) -> actix_web::Result<HttpResponse> {
+    get_first_load_page_with_meta (
+        session,
+        is_desctop,
+        PageMeta::new(title, description, uri, image),
+        template_types,
+    ).await
+}
+
+// то же, но с полной разметкой страницы (OpenGraph, JSON-LD...)
+pub async fn get_first_load_page_with_meta (
+    session:        &Session,
+    is_desctop:     bool,
+    meta:           PageMeta,
+    template_types: i16
+) -> actix_web::Result<HttpResponse> {
+    let meta_tags = meta.render();
+    let (title, description, uri, image) = (meta.title, meta.description, meta.uri, meta.image);
     if is_signed_in(&session) {
         let _request_user = get_request_user_data(&session);
         if is_desctop {
@@ -268,7 +292,8 @@ pub async fn get_first_load_page (
                 description:    String,
                 image:          String,
                 uri:            String,
-                template_types: i16, 
+                template_types: i16,
+                meta_tags:      String,
             }
             let body = Template {
                 request_user:   _request_user,
@@ -277,6 +302,7 @@ pub async fn get_first_load_page (
                 image:          image,
                 uri:            uri,
                 template_types: template_types,
+                meta_tags:      meta_tags,
             }
             .render_once()
             .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
@@ -292,6 +318,7 @@ pub async fn get_first_load_page (
                 image:          String,
                 uri:            String,
                 template_types: i16,
+                meta_tags:      String,
             }
             let body = Template {
                 request_user:   _request_user,
@@ -300,6 +327,7 @@ pub async fn get_first_load_page (
                 image:          image,
                 uri:            uri,
                 template_types: template_types,
+                meta_tags:      meta_tags,
             }
             .render_once()
             .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
@@ -316,6 +344,7 @@ pub async fn get_first_load_page (
                 image:          String,
                 uri:            String,
                 template_types: i16,
+                meta_tags:      String,
             }
             let body = Template {
                 title:          title,
@@ -323,6 +352,7 @@ pub async fn get_first_load_page (
                 image:          image,
                 uri:            uri,
                 template_types: template_types,
+                meta_tags:      meta_tags,
             }
             .render_once()
             .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
@@ -337,6 +367,7 @@ pub async fn get_first_load_page (
                 image:          String,
                 uri:            String,
                 template_types: i16,
+                meta_tags:      String,
             }
             let body = Template {
                 title:          title,
@@ -344,6 +375,7 @@ pub async fn get_first_load_page (
                 image:          image,
                 uri:            uri,
                 template_types: template_types,
+                meta_tags:      meta_tags,
             }
             .render_once()
             .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
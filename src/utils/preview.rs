@@ -0,0 +1,44 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::vars::preview_secret_key;
+
+type HmacSha256 = Hmac<Sha256>;
+
+
+// подпись ссылки на черновик: id ссылки, id объекта и срок действия.
+// подделать срок или подставить другой объект без ключа нельзя.
+// None - SECRET_KEY не задан, подписывать нечем.
+fn get_preview_mac(id: i32, item_id: i32, expires: i64) -> Option<HmacSha256> {
+    let key = preview_secret_key()?;
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes())
+        .expect("HMAC принимает ключ любой длины");
+    mac.update(format!("{}:{}:{}", id, item_id, expires).as_bytes());
+    return Some(mac);
+}
+
+pub fn is_preview_enabled() -> bool {
+    return preview_secret_key().is_some();
+}
+
+pub fn sign_preview(id: i32, item_id: i32, expires: i64) -> Option<String> {
+    let bytes = get_preview_mac(id, item_id, expires)?.finalize().into_bytes();
+    return Some(bytes.iter().map(|b| format!("{:02x}", b)).collect());
+}
+
+pub fn check_preview_signature(id: i32, item_id: i32, expires: i64, signature: &str) -> bool {
+    if signature.len() != 64 || !signature.is_ascii() {
+        return false;
+    }
+    let mut bytes = Vec::new();
+    for i in (0..signature.len()).step_by(2) {
+        match u8::from_str_radix(&signature[i..i + 2], 16) {
+            Ok(b) => bytes.push(b),
+            Err(_) => return false,
+        }
+    }
+    // verify_slice сравнивает за постоянное время
+    return match get_preview_mac(id, item_id, expires) {
+        Some(_mac) => _mac.verify_slice(&bytes).is_ok(),
+        None => false,
+    };
+}
@@ -0,0 +1,236 @@
+use ammonia::Builder;
+use crate::schema;
+use crate::utils::establish_connection;
+use crate::diesel::{ExpressionMethods, RunQueryDsl, QueryDsl};
+
+
+// политики очистки html при записи в базу:
+// rich  - текст объектов от админов: разметка, таблицы, картинки, код,
+//         inline стили, видео во фреймах и ссылки в новой вкладке;
+// basic - короткие описания: только строчная разметка и ссылки;
+// plain - всё, что приходит из публичных форм: теги вырезаются целиком,
+//         а текст хранится экранированным (&lt; &amp;) - шаблоны выводят
+//         его как есть. Обычные символы нужны только там, где текст
+//         уходит не в html (meta, PDF) - там и вызывается unescape_html.
+// Скрипты, обработчики событий и javascript: ссылки не проходят нигде.
+pub fn sanitize_rich(text: &str) -> String {
+    let mut builder = Builder::default();
+    builder
+        .add_generic_attributes(&["class", "style"])
+        .add_tags(&["figure", "figcaption", "section", "iframe", "video", "source"])
+        .add_tag_attributes("iframe", &["src", "width", "height", "frameborder", "allow", "allowfullscreen"])
+        .add_tag_attributes("video", &["src", "width", "height", "controls", "poster"])
+        .add_tag_attributes("source", &["src", "type"])
+        .add_tag_attributes("a", &["target"])
+        .add_tag_attributes("h1", &["id"])
+        .add_tag_attributes("h2", &["id"])
+        .add_tag_attributes("h3", &["id"])
+        .add_tag_attributes("h4", &["id"])
+        .add_tag_attributes("h5", &["id"])
+        .add_tag_attributes("h6", &["id"])
+        .add_tag_attributes("sup", &["id"])
+        .add_tag_attributes("li", &["id"])
+        .add_tag_attributes("div", &["id"])
+        .add_tag_attributes("img", &["loading"]);
+    return builder.clean(text).to_string();
+}
+
+pub fn sanitize_basic(text: &str) -> String {
+    let mut builder = Builder::empty();
+    builder
+        .clean_content_tags(["script", "style"].iter().cloned().collect())
+        .add_tags(&["a", "b", "strong", "i", "em", "u", "s", "br", "p", "span", "ul", "ol", "li", "code"])
+        .add_tag_attributes("a", &["href", "title"])
+        .add_url_schemes(&["http", "https", "mailto", "tel"])
+        .link_rel(Some("noopener noreferrer"));
+    return builder.clean(text).to_string();
+}
+
+pub fn sanitize_plain(text: &str) -> String {
+    let mut builder = Builder::empty();
+    builder.clean_content_tags(["script", "style"].iter().cloned().collect());
+    return builder.clean(text).to_string();
+}
+
+pub fn sanitize_rich_opt(text: &Option<String>) -> Option<String> {
+    return text.as_ref().map(|t| sanitize_rich(t));
+}
+pub fn sanitize_basic_opt(text: &Option<String>) -> Option<String> {
+    return text.as_ref().map(|t| sanitize_basic(t));
+}
+pub fn sanitize_plain_opt(text: &Option<String>) -> Option<String> {
+    return text.as_ref().map(|t| sanitize_plain(t));
+}
+
+// текст, вынутый из html, обратно в обычные символы
+pub fn unescape_html(text: &str) -> String {
+    return text
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+}
+
+// разовая команда для строк, сохраненных до появления очистки:
+// ./target/release/<бинарник> resanitize
+// Обновляются только строки, текст которых реально изменился.
+// С --dry-run ничего не пишет, только перечисляет такие строки.
+pub fn resanitize_all(dry_run: bool) -> () {
+    use crate::schema::{
+        items::dsl::items,
+        orders::dsl::orders,
+        feedbacks::dsl::feedbacks,
+        messages::dsl::messages,
+        item_comments::dsl::item_comments,
+    };
+
+    let _connection = establish_connection();
+    let mut count = 0;
+
+    let _items = items
+        .select((schema::items::id, schema::items::content, schema::items::description))
+        .load::<(i32, Option<String>, Option<String>)>(&_connection)
+        .expect("E.");
+    for (id, content, description) in _items.into_iter() {
+        let (_content, _description) = (sanitize_rich_opt(&content), sanitize_basic_opt(&description));
+        if _content != content || _description != description {
+            if dry_run {
+                info!("resanitize: items {} would change", id);
+            }
+            else {
+                diesel::update(items.filter(schema::items::id.eq(id)))
+                    .set((
+                        schema::items::content.eq(_content),
+                        schema::items::description.eq(_description),
+                    ))
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+            count += 1;
+        }
+    }
+    info!("resanitize: items {}", count);
+
+    count = 0;
+    let _orders = orders
+        .select((
+            schema::orders::id,
+            schema::orders::title,
+            schema::orders::username,
+            schema::orders::email,
+            schema::orders::description,
+        ))
+        .load::<(i32, String, String, String, Option<String>)>(&_connection)
+        .expect("E.");
+    for (id, title, username, email, description) in _orders.into_iter() {
+        let _title = sanitize_plain(&title);
+        let _username = sanitize_plain(&username);
+        let _email = sanitize_plain(&email);
+        let _description = sanitize_plain_opt(&description);
+        if _title != title || _username != username || _email != email || _description != description {
+            if dry_run {
+                info!("resanitize: orders {} would change", id);
+            }
+            else {
+                diesel::update(orders.filter(schema::orders::id.eq(id)))
+                    .set((
+                        schema::orders::title.eq(_title),
+                        schema::orders::username.eq(_username),
+                        schema::orders::email.eq(_email),
+                        schema::orders::description.eq(_description),
+                    ))
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+            count += 1;
+        }
+    }
+    info!("resanitize: orders {}", count);
+
+    count = 0;
+    let _feedbacks = feedbacks
+        .select((
+            schema::feedbacks::id,
+            schema::feedbacks::username,
+            schema::feedbacks::email,
+            schema::feedbacks::message,
+        ))
+        .load::<(i32, String, String, String)>(&_connection)
+        .expect("E.");
+    for (id, username, email, message) in _feedbacks.into_iter() {
+        let _username = sanitize_plain(&username);
+        let _email = sanitize_plain(&email);
+        let _message = sanitize_plain(&message);
+        if _username != username || _email != email || _message != message {
+            if dry_run {
+                info!("resanitize: feedbacks {} would change", id);
+            }
+            else {
+                diesel::update(feedbacks.filter(schema::feedbacks::id.eq(id)))
+                    .set((
+                        schema::feedbacks::username.eq(_username),
+                        schema::feedbacks::email.eq(_email),
+                        schema::feedbacks::message.eq(_message),
+                    ))
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+            count += 1;
+        }
+    }
+    info!("resanitize: feedbacks {}", count);
+
+    count = 0;
+    let _messages = messages
+        .select((schema::messages::id, schema::messages::content))
+        .load::<(i32, Option<String>)>(&_connection)
+        .expect("E.");
+    for (id, content) in _messages.into_iter() {
+        let _content = sanitize_plain_opt(&content);
+        if _content != content {
+            if dry_run {
+                info!("resanitize: messages {} would change", id);
+            }
+            else {
+                diesel::update(messages.filter(schema::messages::id.eq(id)))
+                    .set(schema::messages::content.eq(_content))
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+            count += 1;
+        }
+    }
+    info!("resanitize: messages {}", count);
+
+    count = 0;
+    let _comments = item_comments
+        .select((
+            schema::item_comments::id,
+            schema::item_comments::comment,
+            schema::item_comments::username,
+        ))
+        .load::<(i32, String, String)>(&_connection)
+        .expect("E.");
+    for (id, comment, username) in _comments.into_iter() {
+        let _comment = sanitize_plain(&comment);
+        let _username = sanitize_plain(&username);
+        if _comment != comment || _username != username {
+            if dry_run {
+                info!("resanitize: item_comments {} would change", id);
+            }
+            else {
+                diesel::update(item_comments.filter(schema::item_comments::id.eq(id)))
+                    .set((
+                        schema::item_comments::comment.eq(_comment),
+                        schema::item_comments::username.eq(_username),
+                    ))
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+            count += 1;
+        }
+    }
+    info!("resanitize: item_comments {}", count);
+}
@@ -0,0 +1,299 @@
+use crate::schema;
+use crate::utils::{establish_connection, escape_xml, get_ascii_url};
+use crate::models::{get_item_url, get_category_prefix};
+use crate::diesel::{ExpressionMethods, RunQueryDsl, QueryDsl};
+use std::collections::HashMap;
+
+
+// больше адресов в одном файле поисковики не принимают
+pub static SITEMAP_LIMIT: usize = 50000;
+
+// статичные страницы сайта и их приоритет
+static STATIC_PAGES: [(&str, f32); 8] = [
+    ("/", 1.0),
+    ("/info/", 0.5),
+    ("/blog_categories/", 0.6),
+    ("/service_categories/", 0.8),
+    ("/store_categories/", 0.8),
+    ("/wiki_categories/", 0.6),
+    ("/work_categories/", 0.6),
+    ("/tags/", 0.3),
+];
+
+// служебные адреса, которые не нужны в поиске.
+// robots.txt сравнивает начало пути, поэтому "/edit_" закрывает
+// и /edit_item/, и /edit_category/, и остальные правки.
+static ROBOTS_DISALLOW: [&str; 40] = [
+    "/create_",
+    "/edit_",
+    "/delete_",
+    "/publish_item/",
+    "/hide_item/",
+    "/schedule_item/",
+    "/orders/",
+    "/order/",
+    "/user_orders/",
+    "/cookie_users_list/",
+    "/feedback_list/",
+    "/serve_list/",
+    "/history/",
+    "/object_history/",
+    "/comments_moderation/",
+    "/approve_comments/",
+    "/reject_comments/",
+    "/item_revisions/",
+    "/item_revisions_diff/",
+    "/restore_revision/",
+    "/item_previews/",
+    "/preview/",
+    "/revoke_preview/",
+    "/redirects/",
+    "/import_redirects/",
+    "/discount_rules/",
+    "/trash/",
+    "/restore_",
+    "/purge_",
+    "/clone_item/",
+    "/help_vote",
+    "/change_order_status/",
+    "/order_document",
+    "/load_",
+    "/unical_object_form/",
+    "/login/",
+    "/signup/",
+    "/logout/",
+    "/search",
+    "/test/",
+];
+
+pub struct SitemapUrl {
+    pub loc:      String,
+    pub lastmod:  Option<chrono::NaiveDateTime>,
+    pub priority: f32,
+}
+
+pub fn get_item_priority(types: i16) -> f32 {
+    return match types {
+        2 => 0.9,   // услуги
+        3 => 0.9,   // товары
+        1 => 0.7,   // блог
+        5 => 0.7,   // работы
+        4 => 0.6,   // wiki
+        _ => 0.5,
+    };
+}
+pub fn get_category_priority(types: i16) -> f32 {
+    return match types {
+        2 | 3 => 0.8,
+        _ => 0.6,
+    };
+}
+
+// все открытые адреса сайта: статичные страницы, объекты,
+// категории и теги. Помощь (6) показывается только списком
+// в категории, поэтому у ее объектов своих страниц нет.
+pub fn get_sitemap_urls() -> Vec<SitemapUrl> {
+    use crate::schema::{
+        items::dsl::items,
+        item_revisions::dsl::item_revisions,
+        category::dsl::category,
+        categories::dsl::categories,
+        tags::dsl::tags,
+        tags_items::dsl::tags_items,
+    };
+
+    let _connection = establish_connection();
+    let mut urls = Vec::new();
+
+    for (path, priority) in STATIC_PAGES.iter() {
+        urls.push(SitemapUrl {
+            loc:      path.to_string(),
+            lastmod:  None,
+            priority: *priority,
+        });
+    }
+
+    let _items = items
+        .filter(schema::items::is_active.eq(true))
+        .filter(schema::items::types.lt(6))
+        .order(schema::items::created.desc())
+        .select((
+            schema::items::id,
+            schema::items::slug,
+            schema::items::types,
+            schema::items::created,
+            schema::items::category_id,
+        ))
+        .load::<(i32, String, i16, chrono::NaiveDateTime, Option<i32>)>(&_connection)
+        .expect("E.");
+    let item_ids: Vec<i32> = _items.iter().map(|i| i.0).collect();
+
+    // дата последней правки объекта, если правки были
+    let mut lastmods: HashMap<i32, chrono::NaiveDateTime> = HashMap::new();
+    for (id, _, _, created, _) in _items.iter() {
+        lastmods.insert(*id, *created);
+    }
+    let _revisions = item_revisions
+        .filter(schema::item_revisions::item_id.eq_any(&item_ids))
+        .select((schema::item_revisions::item_id, schema::item_revisions::created))
+        .load::<(i32, chrono::NaiveDateTime)>(&_connection)
+        .expect("E.");
+    for (item_id, created) in _revisions.into_iter() {
+        let lastmod = lastmods.entry(item_id).or_insert(created);
+        if created > *lastmod {
+            *lastmod = created;
+        }
+    }
+
+    let _categories = categories
+        .filter(schema::categories::types.lt(7))
+        .filter(schema::categories::deleted_at.is_null())
+        .order(schema::categories::position.asc())
+        .select((
+            schema::categories::id,
+            schema::categories::slug,
+            schema::categories::types,
+            schema::categories::parent_id,
+        ))
+        .load::<(i32, String, i16, Option<i32>)>(&_connection)
+        .expect("E.");
+    let mut cat_slugs: HashMap<i32, String> = HashMap::new();
+    let mut cat_parents: HashMap<i32, Option<i32>> = HashMap::new();
+    for (id, slug, _, parent_id) in _categories.iter() {
+        cat_slugs.insert(*id, slug.clone());
+        cat_parents.insert(*id, *parent_id);
+    }
+
+    // основная категория объекта (или первая привязанная) дает
+    // его адрес, а самый свежий объект категории - дату ее изменения
+    let _links = category
+        .filter(schema::category::item_id.eq_any(&item_ids))
+        .order(schema::category::id.asc())
+        .select((schema::category::categories_id, schema::category::item_id))
+        .load::<(i32, i32)>(&_connection)
+        .expect("E.");
+    let mut item_cats: HashMap<i32, i32> = HashMap::new();
+    let mut cat_lastmods: HashMap<i32, chrono::NaiveDateTime> = HashMap::new();
+    for (cat_id, item_id) in _links.iter() {
+        item_cats.entry(*item_id).or_insert(*cat_id);
+        if let Some(item_lastmod) = lastmods.get(item_id) {
+            let lastmod = cat_lastmods.entry(*cat_id).or_insert(*item_lastmod);
+            if *item_lastmod > *lastmod {
+                *lastmod = *item_lastmod;
+            }
+        }
+    }
+
+    for (id, slug, types, _, main_id) in _items.iter() {
+        let cat_id = main_id.as_ref().or(item_cats.get(id));
+        let cat_slug = match cat_id.and_then(|c| cat_slugs.get(c)) {
+            Some(cat_slug) => cat_slug,
+            None => continue,
+        };
+        urls.push(SitemapUrl {
+            loc:      get_item_url(*types, cat_slug, slug),
+            lastmod:  lastmods.get(id).cloned(),
+            priority: get_item_priority(*types),
+        });
+    }
+
+    for (id, slug, types, parent_id) in _categories.iter() {
+        // вложенный путь: родители через "/"
+        let mut path = slug.clone();
+        let mut current = *parent_id;
+        let mut depth = 0;
+        while current.is_some() && depth < 20 {
+            let parent = current.unwrap();
+            match cat_slugs.get(&parent) {
+                Some(parent_slug) => path = parent_slug.clone() + &"/".to_string() + &path,
+                None => break,
+            };
+            current = *cat_parents.get(&parent).unwrap_or(&None);
+            depth += 1;
+        }
+        urls.push(SitemapUrl {
+            loc:      get_category_prefix(*types) + &path + &"/".to_string(),
+            lastmod:  cat_lastmods.get(id).cloned(),
+            priority: get_category_priority(*types),
+        });
+    }
+
+    let _tags = tags
+        .filter(schema::tags::count.gt(0))
+        .order(schema::tags::position.asc())
+        .select((schema::tags::id, schema::tags::name))
+        .load::<(i32, String)>(&_connection)
+        .expect("E.");
+    let _tag_links = tags_items
+        .filter(schema::tags_items::item_id.eq_any(&item_ids))
+        .select((schema::tags_items::tag_id, schema::tags_items::created))
+        .load::<(i32, chrono::NaiveDateTime)>(&_connection)
+        .expect("E.");
+    let mut tag_lastmods: HashMap<i32, chrono::NaiveDateTime> = HashMap::new();
+    for (tag_id, created) in _tag_links.into_iter() {
+        let lastmod = tag_lastmods.entry(tag_id).or_insert(created);
+        if created > *lastmod {
+            *lastmod = created;
+        }
+    }
+    for (id, name) in _tags.iter() {
+        urls.push(SitemapUrl {
+            loc:      "/tag/".to_string() + name + &"/".to_string(),
+            lastmod:  tag_lastmods.get(id).cloned(),
+            priority: 0.4,
+        });
+    }
+    return urls;
+}
+
+pub fn get_sitemap_pages_count(count: usize) -> usize {
+    return (count + SITEMAP_LIMIT - 1) / SITEMAP_LIMIT;
+}
+
+// в <loc> адрес должен быть ASCII: хост в punycode, путь
+// (имена тегов, slug на кириллице) в %-кодировке
+pub fn render_urlset(site_url: &str, urls: &[SitemapUrl]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for url in urls.iter() {
+        xml.push_str("<url>");
+        xml.push_str(&format!("<loc>{}</loc>", escape_xml(&get_ascii_url(&(site_url.to_string() + &url.loc)))));
+        if url.lastmod.is_some() {
+            xml.push_str(&format!("<lastmod>{}</lastmod>", url.lastmod.unwrap().format("%Y-%m-%d")));
+        }
+        xml.push_str(&format!("<priority>{:.1}</priority>", url.priority));
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    return xml;
+}
+
+// индекс ссылается на /sitemap-1.xml, /sitemap-2.xml ...
+pub fn render_sitemap_index(site_url: &str, urls: &[SitemapUrl]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for (i, chunk) in urls.chunks(SITEMAP_LIMIT).enumerate() {
+        xml.push_str("<sitemap>");
+        xml.push_str(&format!("<loc>{}/sitemap-{}.xml</loc>", escape_xml(&get_ascii_url(site_url)), i + 1));
+        let lastmod = chunk.iter().filter_map(|u| u.lastmod).max();
+        if lastmod.is_some() {
+            xml.push_str(&format!("<lastmod>{}</lastmod>", lastmod.unwrap().format("%Y-%m-%d")));
+        }
+        xml.push_str("</sitemap>\n");
+    }
+    xml.push_str("</sitemapindex>\n");
+    return xml;
+}
+
+pub fn render_robots(site_url: &str) -> String {
+    let mut text = String::new();
+    text.push_str("User-agent: *\n");
+    for path in ROBOTS_DISALLOW.iter() {
+        text.push_str(&format!("Disallow: {}\n", path));
+    }
+    text.push_str("Allow: /\n\n");
+    text.push_str(&format!("Sitemap: {}/sitemap.xml\n", get_ascii_url(site_url)));
+    return text;
+}
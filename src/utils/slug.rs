@@ -0,0 +1,218 @@
+use crate::schema;
+use crate::utils::establish_connection;
+use crate::diesel::{ExpressionMethods, RunQueryDsl, QueryDsl};
+
+
+fn transliterate_char(c: char) -> &'static str {
+    return match c {
+        'а' => "a",  'б' => "b",  'в' => "v",  'г' => "g",  'д' => "d",
+        'е' => "e",  'ё' => "yo", 'ж' => "zh", 'з' => "z",  'и' => "i",
+        'й' => "y",  'к' => "k",  'л' => "l",  'м' => "m",  'н' => "n",
+        'о' => "o",  'п' => "p",  'р' => "r",  'с' => "s",  'т' => "t",
+        'у' => "u",  'ф' => "f",  'х' => "h",  'ц' => "ts", 'ч' => "ch",
+        'ш' => "sh", 'щ' => "sch", 'ъ' => "",  'ы' => "y",  'ь' => "",
+        'э' => "e",  'ю' => "yu", 'я' => "ya",
+        _ => "-",
+    };
+}
+
+// "Сайт под ключ: цены 2024" -> "sayt-pod-klyuch-tseny-2024"
+pub fn make_slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        }
+        else {
+            let part = transliterate_char(c);
+            if part == "-" {
+                if !slug.ends_with('-') {
+                    slug.push('-');
+                }
+            }
+            else {
+                slug.push_str(part);
+            }
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(90).collect();
+    return slug.trim_end_matches('-').to_string();
+}
+
+// "/blog/%D0%B1%D0%BB%D0%BE%D0%B3/" -> "/blog/блог/".
+// Битые последовательности остаются как есть.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("00");
+            result.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+            i += 3;
+        }
+        else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    return String::from_utf8(result).unwrap_or_else(|_| text.to_string());
+}
+
+// обратное к percent_decode для заголовка Location: кодируются
+// только не-ASCII байты и символы, которых не бывает в адресе
+pub fn percent_encode_url(url: &str) -> String {
+    let mut result = String::with_capacity(url.len());
+    for b in url.bytes() {
+        if b > 0x20 && b < 0x7f && !b"\"<>`{}|\\^".contains(&b) {
+            result.push(b as char);
+        }
+        else {
+            result.push_str(&format!("%{:02X}", b));
+        }
+    }
+    return result;
+}
+
+// адрес для карты сайта: хост с кириллицей в punycode
+// (https://вебсервисы.рф -> https://xn--90abbkbt8cge4h.xn--p1ai),
+// остальное - как в percent_encode_url
+pub fn get_ascii_url(url: &str) -> String {
+    let (scheme, rest) = match url.find("://") {
+        Some(_pos) => (&url[.._pos + 3], &url[_pos + 3..]),
+        None => ("", url),
+    };
+    let host_end = rest.find(|c| c == '/' || c == ':' || c == '?').unwrap_or(rest.len());
+    let host = rest[..host_end]
+        .split('.')
+        .map(|label| {
+            if label.is_ascii() {
+                label.to_string()
+            }
+            else {
+                "xn--".to_string() + &punycode_encode(&label.to_lowercase())
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".");
+    return scheme.to_string() + &host + &percent_encode_url(&rest[host_end..]);
+}
+
+// punycode одной метки домена по RFC 3492
+fn punycode_encode(label: &str) -> String {
+    const BASE: u32 = 36;
+    const TMIN: u32 = 1;
+    const TMAX: u32 = 26;
+
+    fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+        let mut delta = if first { delta / 700 } else { delta / 2 };
+        delta += delta / points;
+        let mut k = 0;
+        while delta > ((BASE - TMIN) * TMAX) / 2 {
+            delta /= BASE - TMIN;
+            k += BASE;
+        }
+        return k + (BASE - TMIN + 1) * delta / (delta + 38);
+    }
+    fn digit(d: u32) -> char {
+        return if d < 26 { (b'a' + d as u8) as char } else { (b'0' + (d - 26) as u8) as char };
+    }
+
+    let chars: Vec<u32> = label.chars().map(|c| c as u32).collect();
+    let mut output: String = label.chars().filter(|c| c.is_ascii()).collect();
+    let basic = output.len() as u32;
+    let mut handled = basic;
+    if basic > 0 {
+        output.push('-');
+    }
+    let (mut n, mut delta, mut bias) = (128u32, 0u32, 72u32);
+    while (handled as usize) < chars.len() {
+        let m = *chars.iter().filter(|c| **c >= n).min().unwrap();
+        delta += (m - n) * (handled + 1);
+        n = m;
+        for c in chars.iter() {
+            if *c < n {
+                delta += 1;
+            }
+            if *c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias { TMIN } else if k >= bias + TMAX { TMAX } else { k - bias };
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    return output;
+}
+
+// slug из формы, а если его нет - из названия. Если такой
+// slug уже есть у другого объекта того же типа, добавляем -2, -3...
+pub fn get_unique_item_slug(slug: &str, title: &str, types: i16, exclude_id: i32) -> String {
+    use crate::schema::items::dsl::items;
+
+    let mut base = make_slug(slug);
+    if base.is_empty() {
+        base = make_slug(title);
+    }
+    if base.is_empty() {
+        base = "item".to_string();
+    }
+
+    let _connection = establish_connection();
+    let mut _slug = base.clone();
+    let mut n = 2;
+    while items
+        .filter(schema::items::slug.eq(&_slug))
+        .filter(schema::items::types.eq(types))
+        .filter(schema::items::id.ne(exclude_id))
+        .select(schema::items::id)
+        .first::<i32>(&_connection)
+        .is_ok() {
+        _slug = format!("{}-{}", base, n);
+        n += 1;
+    }
+    return _slug;
+}
+
+pub fn get_unique_category_slug(slug: &str, name: &str, types: i16, exclude_id: i32) -> String {
+    use crate::schema::categories::dsl::categories;
+
+    let mut base = make_slug(slug);
+    if base.is_empty() {
+        base = make_slug(name);
+    }
+    if base.is_empty() {
+        base = "category".to_string();
+    }
+
+    let _connection = establish_connection();
+    let mut _slug = base.clone();
+    let mut n = 2;
+    while categories
+        .filter(schema::categories::slug.eq(&_slug))
+        .filter(schema::categories::types.eq(types))
+        .filter(schema::categories::id.ne(exclude_id))
+        .select(schema::categories::id)
+        .first::<i32>(&_connection)
+        .is_ok() {
+        _slug = format!("{}-{}", base, n);
+        n += 1;
+    }
+    return _slug;
+}
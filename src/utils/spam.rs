@@ -0,0 +1,112 @@
+use crate::schema;
+use crate::utils::{establish_connection, sanitize_plain};
+use crate::diesel::{ExpressionMethods, RunQueryDsl, QueryDsl};
+use std::env::var;
+
+
+// настройки эвристики спама берутся из .env, например:
+// SPAM_LINK_WEIGHT=3
+// SPAM_STOP_WORD_WEIGHT=5
+// SPAM_REPEAT_WEIGHT=4
+// SPAM_REPEAT_MINUTES=10
+// SPAM_REJECT_SCORE=15
+// SPAM_STOP_WORDS=казино,ставки,viagra,crypto
+pub struct SpamRules {
+    pub link_weight:      i16,   // за каждую ссылку
+    pub stop_word_weight: i16,   // за каждое стоп-слово
+    pub repeat_weight:    i16,   // за каждый недавний комментарий того же куки пользователя
+    pub repeat_minutes:   i64,   // окно, в котором считаются повторы
+    pub reject_score:     i16,   // с такой оценкой комментарий сразу отклоняется
+    pub stop_words:       Vec<String>,
+}
+
+impl SpamRules {
+    pub fn load() -> SpamRules {
+        use dotenv::dotenv;
+
+        dotenv().ok();
+        let stop_words = var("SPAM_STOP_WORDS")
+            .unwrap_or_else(|_| "казино,ставки,букмекер,заработок,кредит,viagra,casino,crypto".to_string())
+            .split(",")
+            .map(|w| w.trim().to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        SpamRules {
+            link_weight:      var("SPAM_LINK_WEIGHT").ok().and_then(|v| v.parse().ok()).unwrap_or(3),
+            stop_word_weight: var("SPAM_STOP_WORD_WEIGHT").ok().and_then(|v| v.parse().ok()).unwrap_or(5),
+            repeat_weight:    var("SPAM_REPEAT_WEIGHT").ok().and_then(|v| v.parse().ok()).unwrap_or(4),
+            repeat_minutes:   var("SPAM_REPEAT_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(10),
+            reject_score:     var("SPAM_REJECT_SCORE").ok().and_then(|v| v.parse().ok()).unwrap_or(15),
+            stop_words:       stop_words,
+        }
+    }
+
+    pub fn count_links(&self, text: &str) -> i16 {
+        let lower = text.to_lowercase();
+        let mut count = 0;
+        for pattern in ["http://", "https://", "www.", "<a "].iter() {
+            count += lower.matches(pattern).count();
+        }
+        return count as i16;
+    }
+
+    pub fn count_stop_words(&self, text: &str) -> i16 {
+        let lower = text.to_lowercase();
+        let mut count = 0;
+        for word in self.stop_words.iter() {
+            count += lower.matches(word.as_str()).count();
+        }
+        return count as i16;
+    }
+
+    // сколько комментариев автор оставил за последние repeat_minutes
+    // минут. Автор - пользователь (user_id), а если он не вошел -
+    // куки пользователь. Одинаковый текст считаем вдвойне.
+    pub fn count_repeats(&self, user_id: Option<i32>, c_user_id: i32, text: &str) -> i16 {
+        use chrono::Duration;
+        use crate::schema::item_comments::dsl::item_comments;
+
+        let _connection = establish_connection();
+        let since = chrono::Local::now().naive_utc() + Duration::hours(3) - Duration::minutes(self.repeat_minutes);
+        let _query = item_comments
+            .filter(schema::item_comments::created.gt(since))
+            .select(schema::item_comments::comment);
+        let _recent = match user_id {
+            Some(_id) => _query
+                .filter(schema::item_comments::user_id.eq(_id))
+                .load::<String>(&_connection)
+                .expect("E."),
+            None if c_user_id != 0 => _query
+                .filter(schema::item_comments::c_user_id.eq(c_user_id))
+                .load::<String>(&_connection)
+                .expect("E."),
+            None => return 0,
+        };
+
+        // в базе текст лежит уже очищенным
+        let _text = sanitize_plain(text);
+        let mut count = 0;
+        for comment in _recent.iter() {
+            count += 1;
+            if comment.trim() == _text.trim() {
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    pub fn get_score(&self, text: &str, user_id: Option<i32>, c_user_id: i32) -> i16 {
+        let score = self.count_links(text) as i32 * self.link_weight as i32
+            + self.count_stop_words(text) as i32 * self.stop_word_weight as i32
+            + self.count_repeats(user_id, c_user_id, text) as i32 * self.repeat_weight as i32;
+        if score > i16::MAX as i32 {
+            return i16::MAX;
+        }
+        return score as i16;
+    }
+
+    pub fn is_rejected(&self, score: i16) -> bool {
+        return score >= self.reject_score;
+    }
+}
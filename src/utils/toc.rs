@@ -0,0 +1,124 @@
+use serde::Serialize;
+use crate::utils::{
+    get_unique_anchor,
+    sanitize_plain,
+    unescape_html,
+};
+
+
+// средняя скорость чтения, слов в минуту
+const WORDS_PER_MINUTE: usize = 180;
+
+// пункт оглавления статьи
+#[derive(Serialize, Clone)]
+pub struct TocItem {
+    pub level:  i16,    // 2, 3 или 4
+    pub anchor: String, // id заголовка в тексте
+    pub title:  String,
+}
+
+// позиция следующего открывающего <h2>, <h3> или <h4>
+fn find_heading(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    while let Some(pos) = text[start..].find("<h") {
+        let i = start + pos;
+        if i + 3 < bytes.len()
+            && (b'2'..=b'4').contains(&bytes[i + 2])
+            && (bytes[i + 3] == b' ' || bytes[i + 3] == b'>') {
+            return Some(i);
+        }
+        start = i + 2;
+    }
+    return None;
+}
+
+fn get_id_attr(open_tag: &str) -> Option<String> {
+    let start = open_tag.find(" id=\"")? + 5;
+    let end = open_tag[start..].find('"')?;
+    let id = &open_tag[start..start + end];
+    if id.is_empty() {
+        return None;
+    }
+    return Some(id.to_string());
+}
+
+// текст заголовка без тегов и без знака "#" ссылки-якоря,
+// которую добавляет markdown
+fn get_heading_text(inner: &str) -> String {
+    return unescape_html(&sanitize_plain(inner))
+        .trim()
+        .trim_end_matches('#')
+        .trim()
+        .to_string();
+}
+
+// заголовкам h2-h4 без id проставляются якоря из их текста,
+// так что ссылка на раздел не меняется от показа к показу.
+// Возвращает оглавление в порядке заголовков.
+pub fn add_heading_anchors(content: &mut Option<String>) -> Vec<TocItem> {
+    let text = match content.as_ref() {
+        Some(_text) => _text,
+        None => return Vec::new(),
+    };
+    let mut result = String::with_capacity(text.len() + 64);
+    let mut toc: Vec<TocItem> = Vec::new();
+    let mut used: Vec<String> = Vec::new();
+    let mut rest = text.as_str();
+
+    while let Some(pos) = find_heading(rest) {
+        let level = (rest.as_bytes()[pos + 2] - b'0') as i16;
+        let close_tag = format!("</h{}>", level);
+        let (open_end, close) = match (rest[pos..].find('>'), rest[pos..].find(&close_tag)) {
+            (Some(_open), Some(_close)) if _open < _close => (pos + _open + 1, pos + _close),
+            _ => {
+                result.push_str(&rest[..pos + 3]);
+                rest = &rest[pos + 3..];
+                continue;
+            },
+        };
+        let open_tag = &rest[pos..open_end];
+        let inner = &rest[open_end..close];
+        let title = get_heading_text(inner);
+
+        result.push_str(&rest[..pos]);
+        let anchor = match get_id_attr(open_tag) {
+            Some(_id) => {
+                used.push(_id.clone());
+                result.push_str(open_tag);
+                _id
+            },
+            None => {
+                let _anchor = get_unique_anchor(&title, &mut used);
+                result.push_str(&format!("<h{} id=\"{}\"{}", level, _anchor, &open_tag[3..]));
+                _anchor
+            },
+        };
+        result.push_str(inner);
+        result.push_str(&close_tag);
+        if !title.is_empty() {
+            toc.push(TocItem {
+                level:  level,
+                anchor: anchor,
+                title:  title,
+            });
+        }
+        rest = &rest[close + close_tag.len()..];
+    }
+    result.push_str(rest);
+    *content = Some(result);
+    return toc;
+}
+
+// примерное время чтения в минутах, не меньше одной
+pub fn get_reading_time(content: &Option<String>) -> i16 {
+    let words = match content {
+        Some(_text) => sanitize_plain(_text).split_whitespace().count(),
+        None => 0,
+    };
+    let minutes = (words + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE;
+    if minutes < 1 {
+        return 1;
+    }
+    return minutes.min(i16::MAX as usize) as i16;
+}
@@ -0,0 +1,83 @@
+use crate::models::Item;
+use crate::utils::unescape_html;
+
+
+// внутренние ссылки вики: [[Название статьи]], [[slug]]
+// или [[slug|текст ссылки]]. В тексте хранятся как есть,
+// в ссылки превращаются при показе статьи.
+
+// текст цели в том виде, в каком он лежит в wiki_links
+pub fn normalize_wiki_target(target: &str) -> String {
+    return unescape_html(target).trim().to_lowercase();
+}
+
+// обходит все [[...]] вне <code> и <pre>, заменяя их
+// результатом f(цель, текст ссылки)
+fn replace_wiki_links<F: FnMut(&str, &str) -> String>(text: &str, mut f: F) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut code_depth = 0;
+
+    while rest.len() > 0 {
+        if rest.starts_with("<code") || rest.starts_with("<pre") {
+            code_depth += 1;
+        }
+        else if (rest.starts_with("</code>") || rest.starts_with("</pre>")) && code_depth > 0 {
+            code_depth -= 1;
+        }
+        else if code_depth == 0 && rest.starts_with("[[") {
+            if let Some(end) = rest[2..].find("]]") {
+                let inner = &rest[2..2 + end];
+                if !inner.is_empty() && !inner.contains('<') && !inner.contains('\n') && !inner.contains("[[") {
+                    let (target, label) = match inner.find('|') {
+                        Some(pos) => (&inner[..pos], &inner[pos + 1..]),
+                        None => (inner, inner),
+                    };
+                    if !target.trim().is_empty() {
+                        result.push_str(&f(target.trim(), label.trim()));
+                        rest = &rest[2 + end + 2..];
+                        continue;
+                    }
+                }
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    return result;
+}
+
+// все цели ссылок статьи, без повторов
+pub fn parse_wiki_links(text: &str) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    replace_wiki_links(text, |target, _label| {
+        let target = normalize_wiki_target(target);
+        if !target.is_empty() && !targets.contains(&target) {
+            targets.push(target);
+        }
+        String::new()
+    });
+    return targets;
+}
+
+// [[...]] -> ссылка на статью. Если статьи нет (или она скрыта),
+// текст подсвечивается, чтобы было видно, что писать дальше.
+pub fn render_wiki_links(text: &str) -> String {
+    let mut found: Vec<(String, Option<String>)> = Vec::new();
+    return replace_wiki_links(text, |target, label| {
+        let target = normalize_wiki_target(target);
+        let url = match found.iter().find(|f| f.0 == target) {
+            Some(_f) => _f.1.clone(),
+            None => {
+                let url = Item::find_wiki_article(&target).map(|i| i.get_url());
+                found.push((target, url.clone()));
+                url
+            },
+        };
+        match url {
+            Some(_url) => "<a class=\"wiki_link\" href=\"".to_string() + &_url + &"\">".to_string() + label + &"</a>".to_string(),
+            None => "<span class=\"wiki_link_missing\" title=\"Статья не найдена\">".to_string() + label + &"</span>".to_string(),
+        }
+    });
+}
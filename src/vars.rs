@@ -5,3 +5,34 @@ pub fn secret_key() -> String {
   dotenv().ok();
   var("SECRET_KEY").unwrap_or_else(|_| "0123".repeat(8))
 }
+
+// ключ подписи ссылок на черновики. Ключ по умолчанию известен всем,
+// поэтому без своего SECRET_KEY ссылки не выдаются и не принимаются
+pub fn preview_secret_key() -> Option<String> {
+  dotenv().ok();
+  var("SECRET_KEY").ok().filter(|k| !k.trim().is_empty() && *k != "0123".repeat(8))
+}
+
+// как часто планировщик проверяет отложенные задачи, в секундах
+pub fn scheduler_interval() -> u64 {
+  dotenv().ok();
+  var("SCHEDULER_INTERVAL").ok().and_then(|v| v.parse().ok()).unwrap_or(60)
+}
+
+// адрес сайта для канонических ссылок и разметки
+pub fn site_url() -> String {
+  dotenv().ok();
+  var("SITE_URL").unwrap_or_else(|_| "https://вебсервисы.рф".to_string()).trim_end_matches('/').to_string()
+}
+
+// сколько дней объекты и категории лежат в корзине до удаления
+pub fn trash_days() -> i64 {
+  dotenv().ok();
+  var("TRASH_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(30)
+}
+
+// шрифт с кириллицей для PDF документов заказа
+pub fn document_font() -> String {
+  dotenv().ok();
+  var("DOCUMENT_FONT").unwrap_or_else(|_| "static/fonts/DejaVuSans.ttf".to_string())
+}
@@ -12,6 +12,8 @@ use crate::utils::{
     is_signed_in,
     get_request_user_data,
     get_first_load_page,
+    get_first_load_page_with_meta,
+    PageMeta,
     get_template,
 };
 use actix_session::Session;
@@ -28,14 +30,17 @@ use crate::models::{
     Item,
     User,
     CatDetail,
+    RelatedItem,
+    SlugRedirect,
 };
+use crate::views::{redirect_or_not_found, get_category_redirect};
 use sailfish::TemplateOnce;
 
 
 pub fn blog_routes(config: &mut web::ServiceConfig) {
     config.route("/blog_categories/", web::get().to(blog_categories_page));
     config.service(web::resource("/blog/{cat_slug}/{blog_slug}/").route(web::get().to(get_blog_page)));
-    config.service(web::resource("/blogs/{slug}/").route(web::get().to(blog_category_page)));
+    config.service(web::resource("/blogs/{slug:.+}/").route(web::get().to(blog_category_page)));
 }
 
 
@@ -49,24 +54,46 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
     let _cat_id: String = param.0.clone();
     let template_types = get_template(&req);
 
-    let _item = items
+    let _item_res = items
         .filter(schema::items::slug.eq(&_item_id))
-        .first::<Item>(&_connection)
-        .expect("E");
+        .filter(schema::items::types.eq(1))
+        .filter(schema::items::deleted_at.is_null())
+        .first::<Item>(&_connection);
+    // старая ссылка на объект или категорию - перенаправляем на новую
+    if _item_res.is_err() || !SlugRedirect::category_exists(1, &_cat_id) {
+        return redirect_or_not_found(req, session, SlugRedirect::get_item_url(1, &_cat_id, &_item_id)).await;
+    }
+    let _item = _item_res.expect("E");
+    // объект открыт через чужую категорию - ведем на канонический адрес
+    if _item.get_main_category_id().is_some() && !_item.has_category_slug(&_cat_id) {
+        return redirect_or_not_found(req, session, Some(_item.get_url())).await;
+    }
     if is_ajax == 0 {
-        get_first_load_page (
+        get_first_load_page_with_meta (
             &session,
             is_desctop,
-            _item.title.clone() + &" | Статья ".to_string(),
-            _item.title.clone() + &" | Статья: вебсервисы.рф".to_string(),
-            "/blog/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
-            _item.get_image(),
-            template_types
+            PageMeta::for_item(
+                &_item,
+                _item.title.clone() + &" | Статья ".to_string(),
+                _item.title.clone() + &" | Статья: вебсервисы.рф".to_string(),
+                "/blog/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
+            ),
+            template_types,
         ).await
     }
     else {
         use schema::categories::dsl::categories;
         use crate::models::FeaturedItem;
+        use crate::utils::{
+            add_heading_anchors,
+            get_reading_time,
+            TocItem,
+        };
+
+        // оглавление по h2-h4, якоря проставляются при показе
+        let mut _item = _item;
+        let toc = add_heading_anchors(&mut _item.content);
+        let reading_time = get_reading_time(&_item.content);
 
         let _category = categories
             .filter(schema::categories::slug.eq(&_cat_id))
@@ -88,6 +115,7 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
         };
 
         let (prev, next) = _category.get_featured_items(_item.types, _item.id);
+        let related = _item.get_related_items(6);
 
         if is_signed_in(&session) {
             let _request_user = get_request_user_data(&session);
@@ -115,6 +143,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -126,6 +157,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -144,6 +178,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -155,6 +192,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -186,6 +226,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -196,6 +239,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -213,6 +259,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -223,6 +272,9 @@ pub async fn get_blog_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -239,13 +291,16 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
     use crate::utils::get_device_and_ajax;
     use crate::models::Blog;
 
-    let _cat_id: String = _id.clone();
+    // вложенный адрес "родитель/категория" - ищем по последней части
+    let _path: String = _id.clone();
+    let _cat_id: String = _path.rsplit('/').next().unwrap_or("").to_string();
     let _connection = establish_connection();
     let template_types = get_template(&req);
 
     let _category = categories
         .filter(schema::categories::slug.eq(&_cat_id))
         .filter(schema::categories::types.eq(1))
+        .filter(schema::categories::deleted_at.is_null())
         .select((
             schema::categories::name,
             schema::categories::slug,
@@ -257,8 +312,20 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
             schema::categories::seconds,
             schema::categories::now_u,
         ))
-        .first::<CatDetail>(&_connection)
-        .expect("E");
+        .first::<CatDetail>(&_connection);
+    if _category.is_err() {
+        return redirect_or_not_found(req, session, SlugRedirect::get_category_url(1, &_cat_id)).await;
+    }
+    let mut _category = _category.expect("E");
+
+    // плоский или устаревший путь - ведем на полный вложенный адрес
+    let _url = Categories::get_category_url(_category.id, 1);
+    if let Some(_response) = get_category_redirect(&req, &_url) {
+        return Ok(_response);
+    }
+    // в счетчике учитываются и объекты подкатегорий
+    _category.count = Categories::get_total_count(_category.id, 1);
+    let _breadcrumbs = Categories::get_breadcrumbs(_category.id);
     let cat_image: String;
     if _category.image.is_some() {
         cat_image = _category.image.as_deref().unwrap().to_string();
@@ -274,7 +341,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
             is_desctop,
             _category.name.clone() + &" | Категория блога ".to_string(),
             _category.name.clone() + &" | Категория блога - вебсервисы.рф".to_string(),
-            "/blogs/".to_string() + &_category.slug.clone() + &"/".to_string(),
+            _url.clone(),
             cat_image,
             template_types,
         ).await
@@ -316,6 +383,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     User,
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Blog>,
                     next_page_number: i32,
@@ -326,6 +394,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     _request_user,
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -342,6 +411,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Blog>,
                     next_page_number: i32,
@@ -351,6 +421,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -375,6 +446,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Blog>,
                     next_page_number: i32,
@@ -384,6 +456,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -400,6 +473,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Blog>,
                     next_page_number: i32,
@@ -409,6 +483,7 @@ pub async fn blog_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -0,0 +1,493 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+    error::InternalError,
+    http::StatusCode,
+};
+use std::borrow::BorrowMut;
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    is_signed_in,
+    get_request_user_data,
+    get_cookie_user_id,
+    get_or_create_cookie_user_id,
+    get_template,
+    sanitize_plain,
+    unescape_html,
+    SpamRules,
+};
+use crate::schema;
+use crate::models::{
+    Item,
+    ItemComment,
+    NewItemComment,
+    EditItemComment,
+    User,
+};
+use actix_session::Session;
+use actix_multipart::Multipart;
+use sailfish::TemplateOnce;
+use actix_web::dev::ConnectionInfo;
+
+
+pub fn comment_routes(config: &mut web::ServiceConfig) {
+    config.route("/load_comments/{id}/", web::get().to(load_comments_page));
+    config.route("/create_comment/{id}/", web::post().to(create_comment));
+    config.route("/edit_comment/{id}/", web::post().to(edit_comment));
+    config.route("/delete_comment/{id}/", web::get().to(delete_comment));
+
+    config.route("/comments_moderation/", web::get().to(comments_moderation_page));
+    config.route("/approve_comments/", web::post().to(approve_comments));
+    config.route("/reject_comments/", web::post().to(reject_comments));
+    config.route("/delete_comments/", web::post().to(delete_comments));
+}
+
+// комментарии доступны для блога, услуг, товаров, wiki и работ
+fn is_commentable(item: &Item) -> bool {
+    return item.types > 0 && item.types < 6;
+}
+
+pub async fn load_comments_page(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use crate::utils::{get_device_and_ajax, get_page};
+    use crate::schema::items::dsl::items;
+
+    let (is_desctop, _is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    let _connection = establish_connection();
+    let _item_id: i32 = *_id;
+    let _item = items
+        .filter(schema::items::id.eq(&_item_id))
+        .first::<Item>(&_connection)
+        .expect("E");
+
+    let c_user_id = get_cookie_user_id(&req).await;
+    let (object_list, next_page_number) = ItemComment::get_comments_list(_item.id, get_page(&req), 20);
+
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if !is_commentable(&_item) || (!_item.is_active && !_request_user.is_superuser()) {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/comments/comments.stpl")]
+            struct Template {
+                request_user:     User,
+                item:             Item,
+                object_list:      Vec<ItemComment>,
+                next_page_number: i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                item:             _item,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/comments/comments.stpl")]
+            struct Template {
+                request_user:     User,
+                item:             Item,
+                object_list:      Vec<ItemComment>,
+                next_page_number: i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                item:             _item,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+    else {
+        if !is_commentable(&_item) || !_item.is_active {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/comments/anon_comments.stpl")]
+            struct Template {
+                c_user_id:        i32,
+                item:             Item,
+                object_list:      Vec<ItemComment>,
+                next_page_number: i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                c_user_id:        c_user_id,
+                item:             _item,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/comments/anon_comments.stpl")]
+            struct Template {
+                c_user_id:        i32,
+                item:             Item,
+                object_list:      Vec<ItemComment>,
+                next_page_number: i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                c_user_id:        c_user_id,
+                item:             _item,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+pub async fn create_comment(conn: ConnectionInfo, session: Session, req: HttpRequest, mut payload: Multipart, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use crate::utils::comment_form;
+    use crate::schema::{
+        items::dsl::items,
+        item_comments::dsl::item_comments,
+    };
+
+    let _connection = establish_connection();
+    let _item_id: i32 = *_id;
+    let _item = items
+        .filter(schema::items::id.eq(&_item_id))
+        .first::<Item>(&_connection)
+        .expect("E");
+
+    let form = comment_form(payload.borrow_mut()).await;
+    // длина считается по тексту без тегов, таким, каким его увидят,
+    // а не по экранированному. Спам оценивается по исходному тексту:
+    // ссылки в нем еще есть
+    let _comment = sanitize_plain(form.comment.trim());
+    if !is_commentable(&_item) || _comment.is_empty() || unescape_html(&_comment).chars().count() > 1000 {
+        return Ok(HttpResponse::BadRequest().body(""));
+    }
+
+    // ответить можно только на комментарий этого же объекта
+    if form.parent_id.is_some() {
+        let parent_exists = item_comments
+            .filter(schema::item_comments::id.eq(form.parent_id.unwrap()))
+            .filter(schema::item_comments::item_id.eq(_item.id))
+            .select(schema::item_comments::id)
+            .first::<i32>(&_connection)
+            .is_ok();
+        if !parent_exists {
+            return Ok(HttpResponse::BadRequest().body(""));
+        }
+    }
+
+    let new_comment: NewItemComment;
+    let mut c_user_id = 0;
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if !_item.is_active && !_request_user.is_superuser() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        // комментарии суперпользователя публикуются сразу,
+        // остальные уходят в очередь модерации
+        let status: i16;
+        let spam_score: i16;
+        if _request_user.is_superuser() {
+            status = 2;
+            spam_score = 0;
+        }
+        else {
+            let rules = SpamRules::load();
+            spam_score = rules.get_score(&form.comment, Some(_request_user.id), 0);
+            status = if rules.is_rejected(spam_score) { 3 } else { 1 };
+        }
+        new_comment = NewItemComment::new (
+            _comment,
+            _item.id,
+            Some(_request_user.id),
+            None,
+            _request_user.username.clone(),
+            form.parent_id,
+            status,
+            spam_score,
+        );
+    }
+    else {
+        if !_item.is_active {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        c_user_id = get_or_create_cookie_user_id(conn, &req).await;
+        let _username: String = sanitize_plain(form.username.trim()).chars().take(100).collect();
+        let rules = SpamRules::load();
+        let spam_score = rules.get_score(&form.comment, None, c_user_id);
+        let status = if rules.is_rejected(spam_score) { 3 } else { 1 };
+        new_comment = NewItemComment::new (
+            _comment,
+            _item.id,
+            None,
+            Some(c_user_id),
+            _username,
+            form.parent_id,
+            status,
+            spam_score,
+        );
+    }
+
+    let _new_comment = diesel::insert_into(schema::item_comments::table)
+        .values(&new_comment)
+        .get_result::<ItemComment>(&_connection)
+        .expect("E.");
+
+    let template_types = get_template(&req);
+    #[derive(TemplateOnce)]
+    #[template(path = "desctop/comments/comment.stpl")]
+    struct Template {
+        object:         ItemComment,
+        c_user_id:      i32,
+        template_types: i16,
+    }
+    let body = Template {
+        object:         _new_comment,
+        c_user_id:      c_user_id,
+        template_types: template_types,
+    }
+    .render_once()
+    .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+}
+
+pub async fn edit_comment(session: Session, req: HttpRequest, mut payload: Multipart, _id: web::Path<i32>) -> impl actix_web::Responder {
+    use crate::utils::comment_form;
+    use crate::schema::item_comments::dsl::item_comments;
+
+    let _connection = establish_connection();
+    let _comment_id: i32 = *_id;
+    let _comment = item_comments
+        .filter(schema::item_comments::id.eq(&_comment_id))
+        .first::<ItemComment>(&_connection)
+        .expect("E");
+
+    let is_owner: bool;
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        is_owner = _request_user.is_superuser() || _comment.is_owner(_request_user.id, 0);
+    }
+    else {
+        let c_user_id = get_cookie_user_id(&req).await;
+        is_owner = _comment.is_owner(0, c_user_id);
+    }
+
+    if is_owner && !_comment.is_deleted() {
+        let form = comment_form(payload.borrow_mut()).await;
+        let _text = sanitize_plain(form.comment.trim());
+        if !_text.is_empty() && unescape_html(&_text).chars().count() <= 1000 {
+            let _edit_comment = EditItemComment {
+                comment: _text,
+                types:   2,
+            };
+            diesel::update(&_comment)
+                .set(_edit_comment)
+                .execute(&_connection)
+                .expect("E");
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn delete_comment(session: Session, req: HttpRequest, _id: web::Path<i32>) -> impl actix_web::Responder {
+    use crate::schema::item_comments::dsl::item_comments;
+
+    let _connection = establish_connection();
+    let _comment_id: i32 = *_id;
+    let _comment = item_comments
+        .filter(schema::item_comments::id.eq(&_comment_id))
+        .first::<ItemComment>(&_connection)
+        .expect("E");
+
+    let is_owner: bool;
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        is_owner = _request_user.is_superuser() || _comment.is_owner(_request_user.id, 0);
+    }
+    else {
+        let c_user_id = get_cookie_user_id(&req).await;
+        is_owner = _comment.is_owner(0, c_user_id);
+    }
+
+    if is_owner {
+        _comment.remove();
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn comments_moderation_page(session: Session, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    use crate::utils::{get_device_and_ajax, get_page, get_first_load_page};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Params {
+        pub status: Option<i16>,
+    }
+
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Модерация комментариев".to_string(),
+            "вебсервисы.рф: Модерация комментариев".to_string(),
+            "/comments_moderation/".to_string(),
+            "/static/images/dark/store.jpg".to_string(),
+            template_types,
+        ).await
+    }
+    else if !is_signed_in(&session) {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+    else {
+        let _request_user = get_request_user_data(&session);
+        if !_request_user.is_superuser() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+
+        let mut status: i16 = 1;
+        let params_some = web::Query::<Params>::from_query(&req.query_string());
+        if params_some.is_ok() {
+            let params = params_some.unwrap();
+            if params.status.is_some() {
+                let _status = params.status.unwrap();
+                if _status > 0 && _status < 4 {
+                    status = _status;
+                }
+            }
+        }
+        let (object_list, next_page_number) = ItemComment::get_moderation_list(status, get_page(&req), 20);
+        let pending_count = ItemComment::count_for_status(1);
+
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/comments/moderation.stpl")]
+            struct Template {
+                request_user:     User,
+                status:           i16,
+                pending_count:    i64,
+                object_list:      Vec<ItemComment>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                status:           status,
+                pending_count:    pending_count,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/comments/moderation.stpl")]
+            struct Template {
+                status:           i16,
+                pending_count:    i64,
+                object_list:      Vec<ItemComment>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                status:           status,
+                pending_count:    pending_count,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+fn get_comments_for_ids(ids: Vec<i32>) -> Vec<ItemComment> {
+    use crate::schema::item_comments::dsl::item_comments;
+
+    let _connection = establish_connection();
+    return item_comments
+        .filter(schema::item_comments::id.eq_any(ids))
+        .load::<ItemComment>(&_connection)
+        .expect("E");
+}
+
+pub async fn approve_comments(session: Session, mut payload: Multipart) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::id_list_form;
+
+            let form = id_list_form(payload.borrow_mut()).await;
+            for _comment in get_comments_for_ids(form.id_list).iter() {
+                _comment.set_status(2);
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn reject_comments(session: Session, mut payload: Multipart) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::id_list_form;
+
+            let form = id_list_form(payload.borrow_mut()).await;
+            for _comment in get_comments_for_ids(form.id_list).iter() {
+                _comment.set_status(3);
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn delete_comments(session: Session, mut payload: Multipart) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::id_list_form;
+
+            let form = id_list_form(payload.borrow_mut()).await;
+            for _comment in get_comments_for_ids(form.id_list).iter() {
+                _comment.remove();
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
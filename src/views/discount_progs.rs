@@ -0,0 +1,218 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+    error::InternalError,
+    http::StatusCode,
+};
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    is_signed_in,
+    get_request_user_data,
+    get_device_and_ajax,
+    get_first_load_page,
+    get_page,
+    get_template,
+    DiscountRuleForm,
+};
+use crate::schema;
+use crate::models::{
+    DiscountRule,
+    EditDiscountRule,
+    TechCategories,
+    User,
+};
+use actix_session::Session;
+use actix_multipart::Multipart;
+use std::borrow::BorrowMut;
+use sailfish::TemplateOnce;
+
+
+pub fn discount_routes(config: &mut web::ServiceConfig) {
+    config.route("/discount_rules/", web::get().to(discount_rules_page));
+    config.route("/create_discount_rule/", web::post().to(create_discount_rule));
+    config.route("/edit_discount_rule/{id}/", web::post().to(edit_discount_rule));
+    config.route("/delete_discount_rule/{id}/", web::get().to(delete_discount_rule));
+}
+
+pub async fn discount_rules_page(session: Session, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Правила скидок".to_string(),
+            "вебсервисы.рф: Правила скидок".to_string(),
+            "/discount_rules/".to_string(),
+            "/static/images/dark/store.jpg".to_string(),
+            template_types,
+        ).await
+    }
+    else if !is_signed_in(&session) {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+    else {
+        use crate::schema::tech_categories::dsl::tech_categories;
+
+        let _request_user = get_request_user_data(&session);
+        if !_request_user.is_superuser() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        let _connection = establish_connection();
+        let (object_list, next_page_number) = DiscountRule::get_rules_list(get_page(&req), 20);
+        let _tech_categories = tech_categories
+            .order(schema::tech_categories::level.asc())
+            .load::<TechCategories>(&_connection)
+            .expect("E");
+
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/pages/discount_rules.stpl")]
+            struct Template {
+                request_user:     User,
+                object_list:      Vec<DiscountRule>,
+                tech_categories:  Vec<TechCategories>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                object_list:      object_list,
+                tech_categories:  _tech_categories,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/pages/discount_rules.stpl")]
+            struct Template {
+                object_list:      Vec<DiscountRule>,
+                tech_categories:  Vec<TechCategories>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                object_list:      object_list,
+                tech_categories:  _tech_categories,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+// правило без названия или без размера скидки не сохраняем.
+// Процент режется до 0..100, промокод пишется в верхнем регистре.
+fn get_rule_from_form(form: DiscountRuleForm) -> Option<EditDiscountRule> {
+    use crate::utils::sanitize_plain;
+
+    let _name = sanitize_plain(&form.name);
+    if _name.is_empty() || (form.percent.is_none() && form.amount.is_none()) {
+        return None;
+    }
+    return Some(EditDiscountRule {
+        name:         _name,
+        min_price:    form.min_price.max(0),
+        percent:      form.percent.map(|p| p.max(0).min(100)),
+        amount:       if form.percent.is_some() { None } else { form.amount.map(|a| a.max(0)) },
+        tech_cat_id:  form.tech_cat_id,
+        types:        form.types,
+        promo_code:   form.promo_code.map(|c| DiscountRule::normalize_code(&c)),
+        starts_at:    form.starts_at,
+        ends_at:      form.ends_at,
+        is_stackable: form.is_stackable,
+        is_active:    form.is_active,
+    });
+}
+
+pub async fn create_discount_rule(session: Session, mut payload: Multipart) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::discount_rule_form;
+            use crate::models::NewDiscountRule;
+            use chrono::Duration;
+
+            let form = discount_rule_form(payload.borrow_mut()).await;
+            if let Some(_rule) = get_rule_from_form(form) {
+                let _connection = establish_connection();
+                let _new = NewDiscountRule {
+                    name:         _rule.name,
+                    min_price:    _rule.min_price,
+                    percent:      _rule.percent,
+                    amount:       _rule.amount,
+                    tech_cat_id:  _rule.tech_cat_id,
+                    types:        _rule.types,
+                    promo_code:   _rule.promo_code,
+                    starts_at:    _rule.starts_at,
+                    ends_at:      _rule.ends_at,
+                    is_stackable: _rule.is_stackable,
+                    is_active:    _rule.is_active,
+                    created:      chrono::Local::now().naive_utc() + Duration::hours(3),
+                };
+                diesel::insert_into(schema::discount_rules::table)
+                    .values(&_new)
+                    .execute(&_connection)
+                    .expect("E.");
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn edit_discount_rule(session: Session, mut payload: Multipart, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::schema::discount_rules::dsl::discount_rules;
+            use crate::utils::discount_rule_form;
+
+            let _connection = establish_connection();
+            let _rule = discount_rules
+                .filter(schema::discount_rules::id.eq(*_id))
+                .first::<DiscountRule>(&_connection)
+                .expect("E");
+
+            let form = discount_rule_form(payload.borrow_mut()).await;
+            if let Some(_edit) = get_rule_from_form(form) {
+                diesel::update(&_rule)
+                    .set(_edit)
+                    .execute(&_connection)
+                    .expect("E");
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn delete_discount_rule(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::schema::discount_rules::dsl::discount_rules;
+
+            let _connection = establish_connection();
+            diesel::delete(discount_rules.filter(schema::discount_rules::id.eq(*_id)))
+                .execute(&_connection)
+                .expect("E");
+        }
+    }
+    HttpResponse::Ok()
+}
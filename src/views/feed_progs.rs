@@ -0,0 +1,289 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+};
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    get_etag,
+    is_not_modified,
+    get_not_modified_response,
+    get_xml_response_with_etag,
+    Feed,
+    FeedEntry,
+};
+use crate::schema;
+use crate::vars::site_url;
+use crate::models::{
+    Item,
+    Categories,
+    Tag,
+};
+use serde::Deserialize;
+
+
+pub fn feed_routes(config: &mut web::ServiceConfig) {
+    config.route("/feeds/blog/rss/", web::get().to(blog_rss_feed));
+    config.route("/feeds/blog/atom/", web::get().to(blog_atom_feed));
+    config.route("/feeds/wiki/rss/", web::get().to(wiki_rss_feed));
+    config.route("/feeds/wiki/atom/", web::get().to(wiki_atom_feed));
+    // вложенные категории: /blogs/родитель/категория/rss/
+    config.route("/blogs/{slug:.+}/rss/", web::get().to(blog_category_rss_feed));
+    config.route("/blogs/{slug:.+}/atom/", web::get().to(blog_category_atom_feed));
+    config.route("/wikis/{slug:.+}/rss/", web::get().to(wiki_category_rss_feed));
+    config.route("/wikis/{slug:.+}/atom/", web::get().to(wiki_category_atom_feed));
+    config.route("/tag/{slug}/rss/", web::get().to(tag_rss_feed));
+    config.route("/tag/{slug}/atom/", web::get().to(tag_atom_feed));
+}
+
+// сколько записей отдаем в ленту
+static FEED_LIMIT: i64 = 50;
+
+// ?full=1 - в ленту идет полный текст, иначе только описание
+fn is_full_feed(req: &HttpRequest) -> bool {
+    #[derive(Deserialize)]
+    struct Params {
+        pub full: Option<i16>,
+    }
+    let params_some = web::Query::<Params>::from_query(&req.query_string());
+    if params_some.is_ok() {
+        let params = params_some.unwrap();
+        if params.full.is_some() && params.full.unwrap() == 1 {
+            return true;
+        }
+    }
+    return false;
+}
+
+// ссылки в ленте всегда канонические, через основную категорию
+fn get_feed_entries(_items: Vec<Item>, full: bool) -> Vec<FeedEntry> {
+    let mut entries = Vec::new();
+    for _item in _items.into_iter() {
+        let url = _item.get_url();
+        let summary = match _item.description {
+            Some(ref description) => description.clone(),
+            None => _item.title.clone(),
+        };
+        entries.push(FeedEntry {
+            title:   _item.title,
+            url:     url,
+            summary: summary,
+            content: if full { _item.content } else { None },
+            image:   _item.image,
+            created: _item.created,
+        });
+    }
+    return entries;
+}
+
+// из чего собирается лента: объекты каких типов и, для
+// категории или тега, только какие id
+struct FeedSource {
+    title:       String,
+    description: String,
+    url:         String,
+    types:       Vec<i16>,
+    ids:         Option<Vec<i32>>,
+}
+
+impl FeedSource {
+    // дешевый ключ для ETag: время последней записи, число записей
+    // и время последней правки (каждая правка пишет ревизию).
+    // Считается до загрузки объектов и сборки xml.
+    fn get_key(&self, req: &HttpRequest) -> String {
+        use crate::schema::{
+            items::dsl::items,
+            item_revisions::dsl::item_revisions,
+        };
+        use diesel::dsl::{max, count_star};
+
+        let _connection = establish_connection();
+        let mut _query = items
+            .filter(schema::items::types.eq_any(self.types.clone()))
+            .filter(schema::items::is_active.eq(true))
+            .into_boxed();
+        let mut _ids_query = items
+            .filter(schema::items::types.eq_any(self.types.clone()))
+            .filter(schema::items::is_active.eq(true))
+            .select(schema::items::id)
+            .into_boxed();
+        if let Some(ref _ids) = self.ids {
+            _query = _query.filter(schema::items::id.eq_any(_ids.clone()));
+            _ids_query = _ids_query.filter(schema::items::id.eq_any(_ids.clone()));
+        }
+        let (last, count) = _query
+            .select((max(schema::items::created), count_star()))
+            .first::<(Option<chrono::NaiveDateTime>, i64)>(&_connection)
+            .expect("E.");
+        let edited = item_revisions
+            .filter(schema::item_revisions::item_id.eq_any(_ids_query))
+            .select(max(schema::item_revisions::created))
+            .first::<Option<chrono::NaiveDateTime>>(&_connection)
+            .expect("E.");
+        return format!("{}|{}|{:?}|{}|{:?}", req.path(), is_full_feed(req), last, count, edited);
+    }
+    fn get_items(&self) -> Vec<Item> {
+        use crate::schema::items::dsl::items;
+
+        let _connection = establish_connection();
+        let mut _query = items
+            .filter(schema::items::types.eq_any(self.types.clone()))
+            .filter(schema::items::is_active.eq(true))
+            .into_boxed();
+        if let Some(ref _ids) = self.ids {
+            _query = _query.filter(schema::items::id.eq_any(_ids.clone()));
+        }
+        return _query
+            .order(schema::items::created.desc())
+            .limit(FEED_LIMIT)
+            .load::<Item>(&_connection)
+            .expect("E.");
+    }
+    fn get_feed(self, req: &HttpRequest) -> Feed {
+        let entries = get_feed_entries(self.get_items(), is_full_feed(req));
+        return Feed {
+            title:       self.title,
+            description: self.description,
+            url:         self.url,
+            feed_url:    req.path().to_string(),
+            entries:     entries,
+        };
+    }
+}
+
+fn get_type_source(types: i16) -> FeedSource {
+    let (title, url) = match types {
+        1 => ("Блог", "/blog_categories/"),
+        _ => ("Wiki", "/wiki_categories/"),
+    };
+    return FeedSource {
+        title:       "вебсервисы.рф: ".to_string() + title,
+        description: "вебсервисы.рф: ".to_string() + title + &": новые статьи".to_string(),
+        url:         url.to_string(),
+        types:       vec![types],
+        ids:         None,
+    };
+}
+
+// вложенный путь "родитель/категория" - ищем по последней части,
+// ссылка ленты ведет на полный адрес категории
+fn get_category_source(types: i16, path: &str) -> Option<FeedSource> {
+    use crate::schema::{
+        category::dsl::category,
+        categories::dsl::categories,
+    };
+
+    let slug = path.trim_matches('/').rsplit('/').next().unwrap_or("");
+    let _connection = establish_connection();
+    let _category = categories
+        .filter(schema::categories::slug.eq(slug))
+        .filter(schema::categories::types.eq(types))
+        .filter(schema::categories::deleted_at.is_null())
+        .first::<Categories>(&_connection)
+        .ok()?;
+    let ids = category
+        .filter(schema::category::categories_id.eq(_category.id))
+        .filter(schema::category::types.eq(types))
+        .select(schema::category::item_id)
+        .load::<i32>(&_connection)
+        .expect("E");
+
+    let description = match _category.description {
+        Some(ref description) => description.clone(),
+        None => "вебсервисы.рф: ".to_string() + &_category.name,
+    };
+    return Some(FeedSource {
+        title:       "вебсервисы.рф: ".to_string() + &_category.name,
+        description: description,
+        url:         Categories::get_category_url(_category.id, types),
+        types:       vec![types],
+        ids:         Some(ids),
+    });
+}
+
+// в ленту тега попадают и статьи блога, и статьи wiki
+fn get_tag_source(slug: &str) -> Option<FeedSource> {
+    use crate::schema::{
+        tags::dsl::tags,
+        tags_items::dsl::tags_items,
+    };
+
+    let _connection = establish_connection();
+    let _tag = tags
+        .filter(schema::tags::name.eq(slug))
+        .first::<Tag>(&_connection)
+        .ok()?;
+    let ids = tags_items
+        .filter(schema::tags_items::tag_id.eq(_tag.id))
+        .filter(schema::tags_items::types.eq_any(vec![1, 4]))
+        .select(schema::tags_items::item_id)
+        .load::<i32>(&_connection)
+        .expect("E");
+
+    return Some(FeedSource {
+        title:       "вебсервисы.рф: тег ".to_string() + &_tag.name,
+        description: "вебсервисы.рф: статьи с тегом ".to_string() + &_tag.name,
+        url:         "/tag/".to_string() + &_tag.name + &"/".to_string(),
+        types:       vec![1, 4],
+        ids:         Some(ids),
+    });
+}
+
+// если у читалки свежая версия, лента даже не собирается
+fn feed_response(req: &HttpRequest, source: Option<FeedSource>, is_atom: bool) -> HttpResponse {
+    let source = match source {
+        Some(_source) => _source,
+        None => return HttpResponse::NotFound().body("Feed not found."),
+    };
+    let etag = get_etag(&source.get_key(req));
+    if is_not_modified(req, &etag) {
+        return get_not_modified_response(etag);
+    }
+    let feed = source.get_feed(req);
+    if is_atom {
+        return get_xml_response_with_etag(feed.render_atom(&site_url()), "application/atom+xml; charset=utf-8", etag);
+    }
+    return get_xml_response_with_etag(feed.render_rss(&site_url()), "application/rss+xml; charset=utf-8", etag);
+}
+fn rss_response(req: &HttpRequest, source: Option<FeedSource>) -> HttpResponse {
+    feed_response(req, source, false)
+}
+fn atom_response(req: &HttpRequest, source: Option<FeedSource>) -> HttpResponse {
+    feed_response(req, source, true)
+}
+
+pub async fn blog_rss_feed(req: HttpRequest) -> HttpResponse {
+    rss_response(&req, Some(get_type_source(1)))
+}
+pub async fn blog_atom_feed(req: HttpRequest) -> HttpResponse {
+    atom_response(&req, Some(get_type_source(1)))
+}
+pub async fn wiki_rss_feed(req: HttpRequest) -> HttpResponse {
+    rss_response(&req, Some(get_type_source(4)))
+}
+pub async fn wiki_atom_feed(req: HttpRequest) -> HttpResponse {
+    atom_response(&req, Some(get_type_source(4)))
+}
+pub async fn blog_category_rss_feed(req: HttpRequest, _slug: web::Path<String>) -> HttpResponse {
+    rss_response(&req, get_category_source(1, &_slug))
+}
+pub async fn blog_category_atom_feed(req: HttpRequest, _slug: web::Path<String>) -> HttpResponse {
+    atom_response(&req, get_category_source(1, &_slug))
+}
+pub async fn wiki_category_rss_feed(req: HttpRequest, _slug: web::Path<String>) -> HttpResponse {
+    rss_response(&req, get_category_source(4, &_slug))
+}
+pub async fn wiki_category_atom_feed(req: HttpRequest, _slug: web::Path<String>) -> HttpResponse {
+    atom_response(&req, get_category_source(4, &_slug))
+}
+pub async fn tag_rss_feed(req: HttpRequest, _slug: web::Path<String>) -> HttpResponse {
+    rss_response(&req, get_tag_source(&_slug))
+}
+pub async fn tag_atom_feed(req: HttpRequest, _slug: web::Path<String>) -> HttpResponse {
+    atom_response(&req, get_tag_source(&_slug))
+}
@@ -11,7 +11,8 @@ use crate::utils::{
     establish_connection,
     is_signed_in,
     get_request_user_data,
-    get_first_load_page,
+    get_first_load_page_with_meta,
+    PageMeta,
     get_template,
 };
 use actix_session::Session;
@@ -23,19 +24,29 @@ use crate::diesel::{
 };
 use crate::models::{
     Categories,
+    Item,
+    HelpVote,
+    HelpVoteStat,
     User,
     Cat,
     SmallTag,
     CatDetail,
+    SlugRedirect,
 };
 use sailfish::TemplateOnce;
-use crate::views::not_found;
+use crate::views::{not_found, redirect_or_not_found, get_category_redirect};
+use actix_multipart::Multipart;
+use actix_web::dev::ConnectionInfo;
+use std::borrow::BorrowMut;
 
 
 pub fn help_routes(config: &mut web::ServiceConfig) {
+    config.route("/help_vote/{id}/", web::post().to(help_vote));
+    config.route("/help_votes/", web::get().to(help_votes_page));
+    config.route("/help_votes/{id}/", web::get().to(help_item_votes_page));
     config.service(
         web::scope("/helps")
-            .service(web::resource("{slug}/").route(web::get().to(help_category_page)))
+            .service(web::resource("{slug:.+}/").route(web::get().to(help_category_page)))
     ); 
 }
 
@@ -44,13 +55,16 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
     use crate::schema::categories::dsl::categories;
     use crate::utils::get_device_and_ajax;
 
-    let _cat_id: String = _id.clone();
+    // вложенный адрес "родитель/категория" - ищем по последней части
+    let _path: String = _id.clone();
+    let _cat_id: String = _path.rsplit('/').next().unwrap_or("").to_string();
     let _connection = establish_connection();
     let template_types = get_template(&req);
 
     let _category = categories
         .filter(schema::categories::slug.eq(&_cat_id))
         .filter(schema::categories::types.eq(6))
+        .filter(schema::categories::deleted_at.is_null())
         .select((
             schema::categories::name,
             schema::categories::slug,
@@ -62,8 +76,20 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
             schema::categories::seconds,
             schema::categories::now_u,
         ))
-        .first::<CatDetail>(&_connection)
-        .expect("E");
+        .first::<CatDetail>(&_connection);
+    if _category.is_err() {
+        return redirect_or_not_found(req, session, SlugRedirect::get_category_url(6, &_cat_id)).await;
+    }
+    let mut _category = _category.expect("E");
+
+    // плоский или устаревший путь - ведем на полный вложенный адрес
+    let _url = Categories::get_category_url(_category.id, 6);
+    if let Some(_response) = get_category_redirect(&req, &_url) {
+        return Ok(_response);
+    }
+    // в счетчике учитываются и объекты подкатегорий
+    _category.count = Categories::get_total_count(_category.id, 6);
+    let _breadcrumbs = Categories::get_breadcrumbs(_category.id);
 
     let cat_image: String;
     if _category.image.is_some() {
@@ -75,13 +101,42 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
 
     let (is_desctop, is_ajax) = get_device_and_ajax(&req);
     if is_ajax == 0 {
-        get_first_load_page (
-            &session,
-            is_desctop,
+        use crate::schema::{
+            items::dsl::items,
+            category::dsl::category,
+        };
+
+        // вопросы категории идут в разметку FAQPage
+        let ids = category
+            .filter(schema::category::categories_id.eq(_category.id))
+            .filter(schema::category::types.eq(6))
+            .select(schema::category::item_id)
+            .load::<i32>(&_connection)
+            .expect("E");
+        let _questions = items
+            .filter(schema::items::id.eq_any(ids))
+            .filter(schema::items::is_active.eq(true))
+            .order(schema::items::position.asc())
+            .select((schema::items::title, schema::items::content))
+            .load::<(String, Option<String>)>(&_connection)
+            .expect("E");
+
+        let mut meta = PageMeta::new (
             _category.name.clone() + &" | Категория помощи ".to_string(),
             _category.name.clone() + &" | Категория помощи - вебсервисы.рф".to_string(),
-            "/help/".to_string() + &_category.slug.clone() + &"/".to_string(),
+            _url.clone(),
             cat_image,
+        );
+        meta.set_faq(_questions
+            .into_iter()
+            .filter(|q| q.1.is_some())
+            .map(|q| (q.0, q.1.unwrap()))
+            .collect()
+        );
+        get_first_load_page_with_meta (
+            &session,
+            is_desctop,
+            meta,
             template_types,
         ).await
     }
@@ -120,6 +175,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     User,
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Help>,
                     next_page_number: i32,
@@ -130,6 +186,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     _request_user,
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -147,6 +204,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     User,
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Help>,
                     next_page_number: i32,
@@ -157,6 +215,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     _request_user,
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -181,6 +240,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Help>,
                     next_page_number: i32,
@@ -190,6 +250,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -206,6 +267,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Help>,
                     next_page_number: i32,
@@ -215,6 +277,7 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -228,3 +291,210 @@ pub async fn help_category_page(session: Session, req: HttpRequest, _id: web::Pa
         }
     }
 }
+
+// голос "помогла ли статья". В ответе id куки пользователя:
+// если cookie еще не было, фронт сохраняет его, чтобы
+// повторный голос заменил первый, а не добавился к нему.
+pub async fn help_vote(conn: ConnectionInfo, req: HttpRequest, mut payload: Multipart, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use crate::schema::items::dsl::items;
+    use crate::utils::{
+        help_vote_form,
+        get_or_create_cookie_user_id,
+        sanitize_plain,
+    };
+
+    let _connection = establish_connection();
+    let _item = items
+        .filter(schema::items::id.eq(*_id))
+        .filter(schema::items::types.eq(6))
+        .filter(schema::items::is_active.eq(true))
+        .filter(schema::items::deleted_at.is_null())
+        .first::<Item>(&_connection);
+    if _item.is_err() {
+        return Ok(HttpResponse::BadRequest().body(""));
+    }
+    let _item = _item.expect("E");
+
+    let form = help_vote_form(payload.borrow_mut()).await;
+    let _comment: String = sanitize_plain(form.comment.trim()).chars().take(1000).collect();
+    let c_user_id = get_or_create_cookie_user_id(conn, &req).await;
+    if c_user_id == 0 {
+        return Ok(HttpResponse::BadRequest().body(""));
+    }
+    HelpVote::vote (
+        _item.id,
+        c_user_id,
+        form.is_helpful,
+        if _comment.is_empty() { None } else { Some(_comment) },
+    );
+    Ok(HttpResponse::Ok().body(c_user_id.to_string()))
+}
+
+// отчет для админов: какие ответы чаще всего не помогают
+pub async fn help_votes_page(session: Session, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    use crate::utils::{get_device_and_ajax, get_page, get_first_load_page};
+
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Полезность справки".to_string(),
+            "вебсервисы.рф: Полезность справки".to_string(),
+            "/help_votes/".to_string(),
+            "/static/images/dark/store.jpg".to_string(),
+            template_types,
+        ).await
+    }
+    else if !is_signed_in(&session) {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+    else {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm != 60 {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+
+        let page = get_page(&req);
+        let limit = 20;
+        let _stats = HelpVote::get_stats();
+        let next_page_number = if _stats.len() > (page * limit) as usize { page + 1 } else { 0 };
+        let object_list: Vec<HelpVoteStat> = _stats
+            .into_iter()
+            .skip(((page - 1) * limit) as usize)
+            .take(limit as usize)
+            .collect();
+
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/help/votes.stpl")]
+            struct Template {
+                request_user:     User,
+                object_list:      Vec<HelpVoteStat>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/help/votes.stpl")]
+            struct Template {
+                object_list:      Vec<HelpVoteStat>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+// все голоса и отзывы по одной статье справки
+pub async fn help_item_votes_page(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use crate::utils::{get_device_and_ajax, get_page, get_first_load_page};
+    use crate::schema::items::dsl::items;
+
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    let _connection = establish_connection();
+    let _item = items
+        .filter(schema::items::id.eq(*_id))
+        .filter(schema::items::types.eq(6))
+        .first::<Item>(&_connection);
+    if _item.is_err() {
+        return not_found(req, session).await;
+    }
+    let _item = _item.expect("E");
+
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            _item.title.clone() + &" | Отзывы о статье справки".to_string(),
+            "вебсервисы.рф: Отзывы о статье справки".to_string(),
+            "/help_votes/".to_string() + &_item.id.to_string() + &"/".to_string(),
+            _item.get_image(),
+            template_types,
+        ).await
+    }
+    else if !is_signed_in(&session) {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+    else {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm != 60 {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+
+        let page = get_page(&req);
+        let limit: i64 = 20;
+        let offset = (page as i64 - 1) * limit;
+        let object_list = HelpVote::get_item_votes(_item.id, limit, offset);
+        let next_page_number = if HelpVote::get_item_votes(_item.id, 1, offset + limit).len() > 0 { page + 1 } else { 0 };
+
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/help/item_votes.stpl")]
+            struct Template {
+                request_user:     User,
+                object:           Item,
+                object_list:      Vec<HelpVote>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                object:           _item,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/help/item_votes.stpl")]
+            struct Template {
+                object:           Item,
+                object_list:      Vec<HelpVote>,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                object:           _item,
+                object_list:      object_list,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
@@ -11,6 +11,14 @@ pub mod auth;
 pub mod tag_progs;
 pub mod search_progs;
 pub mod help_progs;
+pub mod comment_progs;
+pub mod revision_progs;
+pub mod preview_progs;
+pub mod redirect_progs;
+pub mod discount_progs;
+pub mod feed_progs;
+pub mod sitemap_progs;
+pub mod trash_progs;
 
 pub use self::{
     work_progs::*,
@@ -25,5 +33,13 @@ pub use self::{
     tag_progs::*,
     search_progs::*,
     help_progs::*,
+    comment_progs::*,
+    revision_progs::*,
+    preview_progs::*,
+    redirect_progs::*,
+    discount_progs::*,
+    feed_progs::*,
+    sitemap_progs::*,
+    trash_progs::*,
     auth::*,
 };
@@ -28,6 +28,11 @@ use crate::models::{
     NewOrder,
     OrderFile,
     NewOrderFile,
+    OrderStatusHistory,
+    OrderLine,
+    OrderChange,
+    OrderDocument,
+    EditOrder,
 };
 use actix_session::Session;
 use actix_multipart::Multipart;
@@ -44,11 +49,15 @@ pub fn order_routes(config: &mut web::ServiceConfig) {
         .route(web::get().to(create_order_page))
         .route(web::post().to(create_order))
     );
-    //config.service(web::resource("/edit_order/{id}/")
-    //    .route(web::get().to(edit_order_page))
-    //    .route(web::post().to(edit_order))
-    //);
+    config.service(web::resource("/edit_order/{id}/")
+        .route(web::get().to(edit_order_page))
+        .route(web::post().to(edit_order))
+    );
     config.route("/delete_order/{id}/", web::get().to(delete_order));
+    config.route("/change_order_status/{id}/", web::post().to(change_order_status));
+    config.route("/create_order_document/{id}/{types}/", web::post().to(create_order_document));
+    config.route("/order_document/{id}/", web::get().to(order_document_page));
+    config.route("/order_document_pdf/{id}/", web::get().to(order_document_pdf));
 }
 
 pub async fn get_orders_page(req: HttpRequest, session: Session) -> actix_web::Result<HttpResponse> {
@@ -257,7 +266,8 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
             template_types,
         ).await
     }
-    else if user_id != _order.user_id {
+    // заказ видят заказчик и админы, которые ведут его по статусам
+    else if user_id != _order.user_id && !(is_signed_in(&session) && get_request_user_data(&session).perm == 60) {
         Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Информация о заказчике не найдена"))
     }
     else {
@@ -267,6 +277,10 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
             .filter(schema::order_files::order_id.eq(&_order_id))
             .load::<OrderFile>(&_connection)
             .expect("E");
+        let _history = _order.get_status_history();
+        let _lines = _order.get_lines();
+        let _changes = _order.get_changes();
+        let _documents = _order.get_documents();
 
         if is_signed_in(&session) {
             let _request_user = get_request_user_data(&session);
@@ -277,6 +291,10 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
                     request_user:   User,
                     object:         Order,
                     files:          Vec<OrderFile>,
+                    history:        Vec<OrderStatusHistory>,
+                    lines:          Vec<OrderLine>,
+                    changes:        Vec<OrderChange>,
+                    documents:      Vec<OrderDocument>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -284,6 +302,10 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
                     request_user:   _request_user,
                     object:         _order,
                     files:          _files,
+                    history:        _history,
+                    lines:          _lines,
+                    changes:        _changes,
+                    documents:      _documents,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -297,12 +319,20 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
                 struct Template {
                     object:         Order,
                     files:          Vec<OrderFile>,
+                    history:        Vec<OrderStatusHistory>,
+                    lines:          Vec<OrderLine>,
+                    changes:        Vec<OrderChange>,
+                    documents:      Vec<OrderDocument>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
                 let body = Template {
                     object:         _order,
                     files:          _files,
+                    history:        _history,
+                    lines:          _lines,
+                    changes:        _changes,
+                    documents:      _documents,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -318,12 +348,20 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
                 struct Template {
                     object:         Order,
                     files:          Vec<OrderFile>,
+                    history:        Vec<OrderStatusHistory>,
+                    lines:          Vec<OrderLine>,
+                    changes:        Vec<OrderChange>,
+                    documents:      Vec<OrderDocument>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
                 let body = Template {
                     object:         _order,
                     files:          _files,
+                    history:        _history,
+                    lines:          _lines,
+                    changes:        _changes,
+                    documents:      _documents,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -337,12 +375,20 @@ pub async fn get_order_page(session: Session, req: HttpRequest, _id: web::Path<i
                 struct Template {
                     object:         Order,
                     files:          Vec<OrderFile>,
+                    history:        Vec<OrderStatusHistory>,
+                    lines:          Vec<OrderLine>,
+                    changes:        Vec<OrderChange>,
+                    documents:      Vec<OrderDocument>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
                 let body = Template {
                     object:         _order,
                     files:          _files,
+                    history:        _history,
+                    lines:          _lines,
+                    changes:        _changes,
+                    documents:      _documents,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -370,16 +416,7 @@ pub async fn create_order_page(req: HttpRequest) -> actix_web::Result<HttpRespon
 }
 
 pub async fn create_order(conn: ConnectionInfo, req: HttpRequest, mut payload: Multipart) -> impl Responder {
-    use crate::schema::serve::dsl::serve;
-    use crate::models::{
-        NewTechCategoriesItem,
-        Serve,
-        NewServeItems,
-    };
-    use crate::utils::{
-        order_form,
-        get_price_acc_values,
-    };
+    use crate::utils::order_form;
 
     let _connection = establish_connection();
     let user_id = get_or_create_cookie_user_id(conn, &req).await;
@@ -400,6 +437,7 @@ pub async fn create_order(conn: ConnectionInfo, req: HttpRequest, mut payload: M
             .values(&new_order)
             .get_result::<Order>(&_connection)
             .expect("E.");
+        OrderStatusHistory::create(_order.id, 1, None, None);
 
         for file in form.files.iter() {
             let new_file = NewOrderFile::create (
@@ -412,61 +450,171 @@ pub async fn create_order(conn: ConnectionInfo, req: HttpRequest, mut payload: M
                 .expect("E.");
         };
 
-        // создаем опции услуги и записываем id опций в вектор.
-        let mut serve_ids = Vec::new();
-        for serve_id in form.serve_list.iter() {
-            let new_serve_form = NewServeItems {
-                serve_id: *serve_id,
-                item_id:  form.object_id,
-                types:    form.types,
-            };
-            diesel::insert_into(schema::serve_items::table)
-                .values(&new_serve_form)
-                .execute(&_connection)
-                .expect("Error.");
-            serve_ids.push(*serve_id);
-        }
+        // промокод пишется до строк, чтобы скидка сразу его учла
+        let _order = match Order::get_valid_promo(&form.promo_code, _order.types) {
+            Some(_code) => _order.set_promo_code(Some(_code)),
+            None => _order,
+        };
+        // выбранные опции копируются в строки заказа,
+        // цена и скидка считаются уже по строкам
+        _order.set_lines(&form.serve_list);
+    }
+    HttpResponse::Ok()
+}
 
-        // получаем опции, чтобы создать связи с их тех. категорией.
-        // это надо отрисовки тех категорий услуги, которые активны
-        let _serves = serve
-            .filter(schema::serve::id.eq_any(serve_ids))
-            .load::<Serve>(&_connection)
-            .expect("E");
+pub async fn edit_order_page(req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use schema::{
+        orders::dsl::orders,
+        order_files::dsl::order_files,
+    };
 
-        let mut tech_cat_ids = Vec::new();
-        let mut order_price = 0;
-        for _serve in _serves.iter() {
-            if !tech_cat_ids.iter().any(|&i| i==_serve.tech_cat_id) {
-                tech_cat_ids.push(_serve.tech_cat_id);
-            }
-            order_price += _serve.price;
-        }
+    let _connection = establish_connection();
+    let _order = orders
+        .filter(schema::orders::id.eq(*_id))
+        .first::<Order>(&_connection)
+        .expect("E");
+    let user_id = get_cookie_user_id(&req).await;
+    if user_id == 0 || user_id != _order.user_id {
+        return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Информация о заказчике не найдена"));
+    }
+    if !_order.is_editable() {
+        return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Заказ уже нельзя изменить"));
+    }
 
-        for id in tech_cat_ids.iter() {
-            let new_cat = NewTechCategoriesItem {
-                category_id: *id,
-                item_id:     form.object_id,
-                types:       form.types,
-                is_active:   1,
-            };
-            diesel::insert_into(schema::tech_categories_items::table)
-                .values(&new_cat)
-                .execute(&_connection)
-                .expect("Error.");
+    let _files = order_files
+        .filter(schema::order_files::order_id.eq(_order.id))
+        .load::<OrderFile>(&_connection)
+        .expect("E");
+    let _lines = _order.get_lines();
+    let template_types = get_template(&req);
+    #[derive(TemplateOnce)]
+    #[template(path = "desctop/pages/edit_order.stpl")]
+    struct Template {
+        object:         Order,
+        files:          Vec<OrderFile>,
+        lines:          Vec<OrderLine>,
+        template_types: i16,
+    }
+    let body = Template {
+        object:         _order,
+        files:          _files,
+        lines:          _lines,
+        template_types: template_types,
+    }
+    .render_once()
+    .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+}
+
+// правка заказа его владельцем (куки пользователем), пока заказ
+// в статусе "новый" или "рассмотрен". Цена пересчитывается так же,
+// как при создании, каждая правка пишется в order_changes.
+pub async fn edit_order(req: HttpRequest, mut payload: Multipart, _id: web::Path<i32>) -> impl Responder {
+    use schema::{
+        orders::dsl::orders,
+        order_files::dsl::order_files,
+    };
+    use crate::utils::{
+        order_form,
+        sanitize_plain,
+        sanitize_plain_opt,
+    };
+
+    let _connection = establish_connection();
+    let _order = orders
+        .filter(schema::orders::id.eq(*_id))
+        .first::<Order>(&_connection)
+        .expect("E");
+    let user_id = get_cookie_user_id(&req).await;
+    if user_id == 0 || user_id != _order.user_id || !_order.is_editable() {
+        return HttpResponse::Ok();
+    }
+
+    let form = order_form(payload.borrow_mut(), user_id).await;
+    let mut changes: Vec<String> = Vec::new();
+    let old_price = _order.price;
+
+    let _edit = EditOrder {
+        username:    sanitize_plain(&form.username),
+        email:       sanitize_plain(&form.email),
+        description: sanitize_plain_opt(&form.description),
+    };
+    if _edit.username != _order.username {
+        changes.push("Имя: ".to_string() + &_order.username + &" -> ".to_string() + &_edit.username);
+    }
+    if _edit.email != _order.email {
+        changes.push("Почта: ".to_string() + &_order.email + &" -> ".to_string() + &_edit.email);
+    }
+    if _edit.description != _order.description {
+        changes.push("Описание изменено".to_string());
+    }
+    diesel::update(&_order)
+        .set(&_edit)
+        .execute(&_connection)
+        .expect("E");
+
+    // убрать можно только файлы этого заказа
+    let _delete_files = order_files
+        .filter(schema::order_files::order_id.eq(_order.id))
+        .filter(schema::order_files::id.eq_any(&form.delete_files))
+        .load::<OrderFile>(&_connection)
+        .expect("E");
+    for _file in _delete_files.iter() {
+        std::fs::remove_file(&_file.src).ok();
+        diesel::delete(_file).execute(&_connection).expect("E");
+        changes.push("Удален файл ".to_string() + &_file.src);
+    }
+    for file in form.files.iter() {
+        let new_file = NewOrderFile::create (
+            _order.id,
+            file.to_string()
+        );
+        diesel::insert_into(schema::order_files::table)
+            .values(&new_file)
+            .execute(&_connection)
+            .expect("E.");
+        changes.push("Добавлен файл ".to_string() + file);
+    }
+
+    // промокод меняется только по флагу promo_change: тогда пустое поле
+    // убирает его, а неверный код не меняет ничего
+    let mut _order = _order;
+    let _code = Order::get_valid_promo(&form.promo_code, _order.types);
+    if form.promo_change && (form.promo_code.is_none() || _code.is_some()) && _code != _order.promo_code {
+        changes.push("Промокод: ".to_string() + &_order.promo_code.clone().unwrap_or("-".to_string()) + &" -> ".to_string() + &_code.clone().unwrap_or("-".to_string()));
+        _order = _order.set_promo_code(_code).update_price();
+    }
+
+    // опции меняются только по флагу serve_change - так можно
+    // прислать и пустой список, убрав последнюю опцию
+    let mut new_price = _order.price;
+    if form.serve_change {
+        let old_lines = _order.get_lines();
+        let old_ids: Vec<i32> = old_lines.iter().filter_map(|l| l.serve_id).collect();
+        let mut new_ids = form.serve_list.clone();
+        new_ids.sort();
+        new_ids.dedup();
+        let mut old_sorted = old_ids.clone();
+        old_sorted.sort();
+        if new_ids != old_sorted {
+            let _order = _order.set_lines(&new_ids);
+            let new_lines = _order.get_lines();
+            for line in old_lines.iter() {
+                if !new_lines.iter().any(|l| l.serve_id == line.serve_id) {
+                    changes.push("Убрана опция ".to_string() + &line.name);
+                }
+            }
+            for line in new_lines.iter() {
+                if !old_lines.iter().any(|l| l.serve_id == line.serve_id) {
+                    changes.push("Добавлена опция ".to_string() + &line.name);
+                }
+            }
+            new_price = _order.price;
         }
+    }
 
-        // фух. Связи созданы все, но надо еще посчитать цену
-        // услуги для калькулятора. Как? А  это будет сумма всех
-        // цен выбранных опций.
-        let price_acc = get_price_acc_values(&order_price);
-        diesel::update(&_order)
-            .set((
-                schema::orders::price.eq(order_price),
-                schema::orders::price_acc.eq(price_acc),
-            ))
-            .get_result::<Order>(&_connection)
-            .expect("Error.");
+    if changes.len() > 0 {
+        OrderChange::create(_order.id, user_id, changes, old_price, new_price);
     }
     HttpResponse::Ok()
 }
@@ -483,27 +631,169 @@ pub async fn delete_order(req: HttpRequest, _id: web::Path<i32>) -> impl Respond
 
     let user_id = get_cookie_user_id(&req).await;
 
-    if user_id == _order.user_id {
+    // заказ, взятый в работу, заказчик удалить уже не может
+    if user_id == _order.user_id && _order.is_editable() {
         use crate::schema::{
-            serve_items::dsl::serve_items,
-            tech_categories_items::dsl::tech_categories_items,
+            order_lines::dsl::order_lines,
+            order_status_history::dsl::order_status_history,
+            order_changes::dsl::order_changes,
+            order_documents::dsl::order_documents,
         };
 
-        diesel::delete (
-            serve_items
-                .filter(schema::serve_items::item_id.eq(_order_id))
-                .filter(schema::serve_items::types.eq(7))
-            )
+        diesel::delete(order_lines.filter(schema::order_lines::order_id.eq(_order_id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(order_changes.filter(schema::order_changes::order_id.eq(_order_id)))
             .execute(&_connection)
             .expect("E");
-        diesel::delete(
-            tech_categories_items
-                .filter(schema::tech_categories_items::item_id.eq(_order_id))
-                .filter(schema::tech_categories_items::types.eq(7))
-            )
+        diesel::delete(order_documents.filter(schema::order_documents::order_id.eq(_order_id)))
+            .execute(&_connection)
+            .expect("E");
+        diesel::delete(order_status_history.filter(schema::order_status_history::order_id.eq(_order_id)))
             .execute(&_connection)
             .expect("E");
         diesel::delete(&_order).execute(&_connection).expect("E");
     }
     HttpResponse::Ok()
 }
+
+// перевод заказа в следующий статус. Переход проверяется
+// по get_order_next_statuses, комментарий видит заказчик.
+pub async fn change_order_status(session: Session, mut payload: Multipart, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use schema::orders::dsl::orders;
+    use crate::utils::order_status_form;
+
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm == 60 {
+            let _connection = establish_connection();
+            let _order = orders
+                .filter(schema::orders::id.eq(*_id))
+                .first::<Order>(&_connection)
+                .expect("E");
+            let form = order_status_form(payload.borrow_mut()).await;
+            let comment = form.comment.trim().to_string();
+            let changed = _order.change_status (
+                form.status,
+                Some(_request_user.id),
+                if comment.is_empty() { None } else { Some(comment) },
+            );
+            if !changed {
+                return Ok(HttpResponse::BadRequest().body(""));
+            }
+            return Ok(HttpResponse::Ok().body(""));
+        }
+    }
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+}
+
+// документы заказа видят те же, кто видит сам заказ
+async fn is_order_visible(session: &Session, req: &HttpRequest, order: &Order) -> bool {
+    let user_id = get_cookie_user_id(req).await;
+    return user_id == order.user_id || (is_signed_in(session) && get_request_user_data(session).perm == 60);
+}
+
+// выдача КП (types 1) или счета (types 2) по заказу. Номер выдается
+// только здесь и только админом, см. OrderDocument::create - заказчик
+// лишь открывает уже выданные. Ответ - адрес документа.
+pub async fn create_order_document(session: Session, param: web::Path<(i32,i16)>) -> impl Responder {
+    use schema::orders::dsl::orders;
+
+    if !is_signed_in(&session) || get_request_user_data(&session).perm != 60 {
+        return HttpResponse::Ok().body("Permission Denied.");
+    }
+    if !OrderDocument::is_valid_type(param.1) {
+        return HttpResponse::NotFound().body("Документ не найден");
+    }
+    let _connection = establish_connection();
+    let _order = orders
+        .filter(schema::orders::id.eq(param.0))
+        .first::<Order>(&_connection)
+        .expect("E");
+    let _document = OrderDocument::create(&_order, param.1);
+    HttpResponse::Ok().body("/order_document/".to_string() + &_document.id.to_string() + &"/".to_string())
+}
+
+// документ, который может видеть текущий пользователь, вместе с заказом
+async fn get_visible_document(session: &Session, req: &HttpRequest, document_id: i32) -> Option<(Order, OrderDocument)> {
+    use schema::{
+        orders::dsl::orders,
+        order_documents::dsl::order_documents,
+    };
+
+    let _connection = establish_connection();
+    let _document = order_documents
+        .filter(schema::order_documents::id.eq(document_id))
+        .first::<OrderDocument>(&_connection)
+        .ok()?;
+    let _order = orders
+        .filter(schema::orders::id.eq(_document.order_id))
+        .first::<Order>(&_connection)
+        .expect("E");
+    if !is_order_visible(session, req, &_order).await {
+        return None;
+    }
+    return Some((_order, _document));
+}
+
+// выданный документ для печати из браузера. Строки берутся из
+// снимка в документе, а не из текущего заказа.
+pub async fn order_document_page(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use crate::utils::{
+        get_document_groups,
+        CompanyRequisites,
+        DocumentGroup,
+    };
+
+    let (_order, _document) = match get_visible_document(&session, &req, *_id).await {
+        Some(_pair) => _pair,
+        None => return Ok(HttpResponse::NotFound().content_type("text/html; charset=utf-8").body("Документ не найден")),
+    };
+    let _groups = get_document_groups(_document.get_lines());
+    let _man_hours: i32 = _groups.iter().map(|g| g.man_hours).sum();
+    let template_types = get_template(&req);
+    #[derive(TemplateOnce)]
+    #[template(path = "desctop/pages/order_document.stpl")]
+    struct Template {
+        object:         Order,
+        document:       OrderDocument,
+        groups:         Vec<DocumentGroup>,
+        man_hours:      i32,
+        requisites:     CompanyRequisites,
+        template_types: i16,
+    }
+    let body = Template {
+        object:         _order,
+        document:       _document,
+        groups:         _groups,
+        man_hours:      _man_hours,
+        requisites:     CompanyRequisites::load(),
+        template_types: template_types,
+    }
+    .render_once()
+    .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+}
+
+// тот же документ файлом PDF
+pub async fn order_document_pdf(session: Session, req: HttpRequest, _id: web::Path<i32>) -> HttpResponse {
+    use actix_web::http::header;
+    use crate::utils::{
+        get_document_groups,
+        render_document_pdf,
+        CompanyRequisites,
+    };
+
+    let (_order, _document) = match get_visible_document(&session, &req, *_id).await {
+        Some(_pair) => _pair,
+        None => return HttpResponse::NotFound().body("Документ не найден"),
+    };
+    let _groups = get_document_groups(_document.get_lines());
+    return match render_document_pdf(&_order, &_document, &_groups, &CompanyRequisites::load()) {
+        Some(_pdf) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .append_header((header::CONTENT_DISPOSITION, "attachment; filename=\"".to_string() + &_document.get_file_name() + &"\"".to_string()))
+            .body(_pdf),
+        None => HttpResponse::InternalServerError().body("Не удалось создать PDF"),
+    };
+}
@@ -16,6 +16,7 @@ use crate::models::{
     Tag,
     StatPage,
     Cat,
+    CatNode,
 };
 use crate::utils::{
     establish_connection,
@@ -68,7 +69,42 @@ pub fn pages_routes(config: &mut web::ServiceConfig) {
 }
 
 
+// постоянное перенаправление, если старая ссылка известна, иначе 404
+pub async fn redirect_or_not_found(req: HttpRequest, session: Session, url: Option<String>) -> actix_web::Result<HttpResponse> {
+    use crate::utils::percent_encode_url;
+
+    match url {
+        Some(_url) => Ok(HttpResponse::MovedPermanently().append_header(("Location", percent_encode_url(&_url))).finish()),
+        None => not_found(req, session).await,
+    }
+}
+
+// плоский или устаревший путь категории - 301 на полный вложенный
+// адрес. Путь запроса приходит в %-кодировке, а адрес категории
+// собран из slug как есть, поэтому сравниваем раскодированный.
+// None - запрос уже пришел на правильный адрес.
+pub fn get_category_redirect(req: &HttpRequest, url: &str) -> Option<HttpResponse> {
+    use crate::utils::{percent_decode, percent_encode_url};
+
+    if percent_decode(req.path()) == url {
+        return None;
+    }
+    return Some(HttpResponse::MovedPermanently().append_header(("Location", percent_encode_url(url))).finish());
+}
+
 pub async fn not_found(req: HttpRequest, session: Session) -> actix_web::Result<HttpResponse> {
+    use crate::models::Redirect;
+
+    // адреса старого сайта и прочие ручные перенаправления
+    if let Some(_redirect) = Redirect::get_for_path(req.path(), req.query_string()) {
+        _redirect.plus_hit();
+        let mut response = match _redirect.is_permanent() {
+            true => HttpResponse::MovedPermanently(),
+            false => HttpResponse::Found(),
+        };
+        return Ok(response.append_header(("Location", crate::utils::percent_encode_url(&_redirect.target))).finish());
+    }
+
     let (is_desctop, is_ajax) = get_device_and_ajax(&req);
 
     let template_types = get_template(&req);
@@ -1156,6 +1192,11 @@ pub async fn create_category_page(session: Session, req: HttpRequest) -> actix_w
             let _cats = categories
                 .load::<Categories>(&_connection)
                 .expect("Error");
+            // деревья всех типов для выбора родителя
+            let mut _tree = Vec::new();
+            for types in 1..7 {
+                _tree.extend(Categories::get_tree(types, 0));
+            }
 
             if is_desctop {
                 #[derive(TemplateOnce)]
@@ -1163,12 +1204,14 @@ pub async fn create_category_page(session: Session, req: HttpRequest) -> actix_w
                 struct Template {
                     request_user:   User,
                     cats:           Vec<Categories>,
+                    tree:           Vec<CatNode>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
                 let body = Template {
                     request_user:   _request_user,
                     cats:           _cats,
+                    tree:           _tree,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -1181,11 +1224,13 @@ pub async fn create_category_page(session: Session, req: HttpRequest) -> actix_w
                 #[template(path = "mobile/pages/create_category.stpl")]
                 struct Template {
                     cats:           Vec<Categories>,
+                    tree:           Vec<CatNode>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
                 let body = Template {
                     cats:           _cats,
+                    tree:           _tree,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -1231,6 +1276,9 @@ pub async fn edit_category_page(session: Session, req: HttpRequest, _id: web::Pa
             let _cats = schema::categories::table
                 .load::<Categories>(&_connection)
                 .expect("Error");
+            // в родители годится любая категория того же типа,
+            // кроме самой категории и ее подкатегорий
+            let _tree = Categories::get_tree(_cat.types, _cat.id);
 
             if is_desctop {
                 #[derive(TemplateOnce)]
@@ -1239,6 +1287,7 @@ pub async fn edit_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:   User,
                     cat:            Categories,
                     cats:           Vec<Categories>,
+                    tree:           Vec<CatNode>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -1246,6 +1295,7 @@ pub async fn edit_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:   _request_user,
                     cat:            _cat,
                     cats:           _cats,
+                    tree:           _tree,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -1259,12 +1309,14 @@ pub async fn edit_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     cat:            Categories,
                     cats:           Vec<Categories>,
+                    tree:           Vec<CatNode>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
                 let body = Template {
                     cat:            _cat,
                     cats:           _cats,
+                    tree:           _tree,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -0,0 +1,251 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+    error::InternalError,
+    http::StatusCode,
+};
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    is_signed_in,
+    get_request_user_data,
+    get_device_and_ajax,
+    get_first_load_page,
+    get_template,
+    check_preview_signature,
+};
+use crate::schema;
+use crate::models::{
+    Item,
+    ItemPreview,
+    NewItemPreview,
+    User,
+};
+use actix_session::Session;
+use sailfish::TemplateOnce;
+
+
+pub fn preview_routes(config: &mut web::ServiceConfig) {
+    config.route("/preview/{id}/{expires}/{signature}/", web::get().to(preview_page));
+    config.route("/item_previews/{id}/", web::get().to(item_previews_page));
+    config.route("/create_preview/{id}/", web::get().to(create_preview));
+    config.route("/revoke_preview/{id}/", web::get().to(revoke_preview));
+}
+
+fn get_item(item_id: i32) -> Item {
+    use crate::schema::items::dsl::items;
+
+    let _connection = establish_connection();
+    return items
+        .filter(schema::items::id.eq(item_id))
+        .first::<Item>(&_connection)
+        .expect("E");
+}
+
+// черновик по подписанной ссылке. Аккаунт не нужен, но ссылка
+// должна быть не отозвана, не просрочена и с верной подписью.
+pub async fn preview_page(session: Session, req: HttpRequest, param: web::Path<(i32, i64, String)>) -> actix_web::Result<HttpResponse> {
+    use crate::schema::item_previews::dsl::item_previews;
+
+    let (_preview_id, _expires, _signature) = param.into_inner();
+    let _connection = establish_connection();
+    let _preview = item_previews
+        .filter(schema::item_previews::id.eq(_preview_id))
+        .first::<ItemPreview>(&_connection);
+
+    let _preview = match _preview {
+        Ok(_ok) => _ok,
+        Err(_error) => return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Ссылка недействительна.")),
+    };
+    if !_preview.is_valid()
+        || _preview.get_expires_ts() != _expires
+        || !check_preview_signature(_preview.id, _preview.item_id, _expires, &_signature) {
+        return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Ссылка недействительна."));
+    }
+
+    let mut _item = get_item(_preview.item_id);
+    if _item.types == 4 {
+        use crate::utils::render_wiki_links;
+        _item.content = _item.content.as_deref().map(render_wiki_links);
+    }
+    let template_types = get_template(&req);
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            _item.title.clone() + &" | Черновик".to_string(),
+            _item.title.clone() + &" | Черновик: вебсервисы.рф".to_string(),
+            _preview.get_url(),
+            _item.get_image(),
+            template_types,
+        ).await
+    }
+    else {
+        _preview.plus_view();
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/previews/item.stpl")]
+            struct Template {
+                object:         Item,
+                preview:        ItemPreview,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                object:         _item,
+                preview:        _preview,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/previews/item.stpl")]
+            struct Template {
+                object:         Item,
+                preview:        ItemPreview,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                object:         _item,
+                preview:        _preview,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+pub async fn item_previews_page(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    let _item = get_item(*_id);
+    let template_types = get_template(&req);
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Ссылки на черновик ".to_string() + &_item.title,
+            "вебсервисы.рф: Ссылки на черновик ".to_string() + &_item.title,
+            "/item_previews/".to_string() + &_item.id.to_string() + &"/".to_string(),
+            _item.get_image(),
+            template_types,
+        ).await
+    }
+    else if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if !_request_user.is_superuser() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        let object_list = ItemPreview::get_previews(_item.id);
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/previews/list.stpl")]
+            struct Template {
+                request_user:   User,
+                item:           Item,
+                object_list:    Vec<ItemPreview>,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                request_user:   _request_user,
+                item:           _item,
+                object_list:    object_list,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/previews/list.stpl")]
+            struct Template {
+                item:           Item,
+                object_list:    Vec<ItemPreview>,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                item:           _item,
+                object_list:    object_list,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+    else {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+}
+
+// выдаем ссылку на ?hours=72 часа (не больше месяца), в ответе - сама ссылка
+pub async fn create_preview(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Params {
+        pub hours: Option<i64>,
+    }
+
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::is_preview_enabled;
+
+            if !is_preview_enabled() {
+                return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Ссылки на черновики отключены: не задан SECRET_KEY."));
+            }
+            let _item = get_item(*_id);
+            let mut hours: i64 = 72;
+            let params_some = web::Query::<Params>::from_query(&req.query_string());
+            if params_some.is_ok() {
+                let params = params_some.unwrap();
+                if params.hours.is_some() {
+                    let _hours = params.hours.unwrap();
+                    if _hours > 0 && _hours <= 720 {
+                        hours = _hours;
+                    }
+                }
+            }
+            let _preview = NewItemPreview::create(_item.id, _request_user.id, hours);
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(_preview.get_url()));
+        }
+    }
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+}
+
+pub async fn revoke_preview(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::schema::item_previews::dsl::item_previews;
+
+            let _connection = establish_connection();
+            let _preview = item_previews
+                .filter(schema::item_previews::id.eq(*_id))
+                .first::<ItemPreview>(&_connection);
+            if _preview.is_ok() {
+                _preview.expect("E").revoke();
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
@@ -48,8 +48,10 @@ pub fn progs_routes(config: &mut web::ServiceConfig) {
     config.route("/create_item/", web::post().to(create_item));
     config.route("/edit_item/{id}/", web::post().to(edit_item));
     config.route("/delete_item/{id}/", web::get().to(delete_item));
+    config.route("/clone_item/{id}/", web::get().to(clone_item));
     config.route("/publish_item/{id}/", web::get().to(publish_item));
     config.route("/hide_item/{id}/", web::get().to(hide_item));
+    config.route("/schedule_item/{id}/", web::post().to(schedule_item));
     config.route("/edit_content_item/{id}/", web::post().to(edit_content_item));
 
     config.route("/create_category/", web::post().to(create_category));
@@ -303,14 +305,14 @@ pub async fn object_history(conn: ConnectionInfo, req: HttpRequest, id: web::Pat
 pub async fn create_feedback(mut payload: actix_multipart::Multipart) -> impl Responder {
     use crate::schema::feedbacks;
     use crate::models::NewFeedback;
-    use crate::utils::feedback_form;
+    use crate::utils::{feedback_form, sanitize_plain};
 
     let _connection = establish_connection();
     let form = feedback_form(payload.borrow_mut()).await;
     let new_feedback = NewFeedback {
-        username: form.username.clone(),
-        email:    form.email.clone(),
-        message:  form.message.clone()
+        username: sanitize_plain(&form.username),
+        email:    sanitize_plain(&form.email),
+        message:  sanitize_plain(&form.message),
     };
     let _new_feedback = diesel::insert_into(feedbacks::table)
         .values(&new_feedback)
@@ -331,25 +333,29 @@ pub async fn create_item(session: Session, mut payload: Multipart) -> impl Respo
                 NewCategory,
                 NewItem,
                 NewTagItems,
+                DiscountRule,
+                DiscountInput,
             };
             use crate::utils::{
                 item_form,
-                get_price_acc_values,
+                sanitize_basic_opt,
+                get_unique_item_slug,
             };
 
             let _connection = establish_connection();
 
             let form = item_form(payload.borrow_mut(), _request_user.id).await;
             let types = form.types;
+            let slug = get_unique_item_slug(&form.slug, &form.title, types, 0);
             let new_item = NewItem::create (
                 form.title.clone(),
-                form.description.clone(),
+                sanitize_basic_opt(&form.description),
                 form.link.clone(),
                 form.main_image.clone(),
                 _request_user.id,
                 form.position,
                 types,
-                form.slug.clone(),
+                slug,
             );
 
             let _item = diesel::insert_into(schema::items::table)
@@ -368,6 +374,7 @@ pub async fn create_item(session: Session, mut payload: Multipart) -> impl Respo
                     .execute(&_connection)
                     .expect("E.");
             };
+            _item.update_main_category(form.main_category);
             for tag_id in form.tags_list.iter() {
                 let new_tag = NewTagItems {
                     tag_id: *tag_id,
@@ -443,7 +450,12 @@ pub async fn create_item(session: Session, mut payload: Multipart) -> impl Respo
             // фух. Связи созданы все, но надо еще посчитать цену
             // услуги для калькулятора. Как? А  это будет сумма всех
             // цен выбранных опций.
-            let price_acc = get_price_acc_values(&item_price);
+            // скидку считает движок правил из discount_rules
+            let price_acc = DiscountRule::get_discount(&DiscountInput {
+                types:      types,
+                lines:      _serves.iter().map(|s| (s.tech_cat_id, s.price)).collect(),
+                promo_code: None,
+            });
             diesel::update(&_item)
                 .set((
                     schema::items::price.eq(item_price),
@@ -478,10 +490,14 @@ pub async fn edit_item(session: Session, mut payload: Multipart, _id: web::Path<
                 NewCategory,
                 NewTagItems,
                 EditItem,
+                SlugRedirect,
+                DiscountRule,
+                DiscountInput,
             };
             use crate::utils::{
                 item_form,
-                get_price_acc_values,
+                sanitize_basic_opt,
+                get_unique_item_slug,
             };
 
             let _connection = establish_connection();
@@ -550,19 +566,23 @@ pub async fn edit_item(session: Session, mut payload: Multipart, _id: web::Path<
                 .expect("E");
 
             let form = item_form(payload.borrow_mut(), _request_user.id).await;
+            let slug = get_unique_item_slug(&form.slug, &form.title, _item.types, _item.id);
             let _new_item = EditItem {
                 title:       form.title.clone(),
-                description: form.description.clone(),
+                description: sanitize_basic_opt(&form.description),
                 link:        form.link.clone(),
                 image:       form.main_image.clone(),
                 position:    form.position,
-                slug:        form.slug.clone(),
+                slug:        slug.clone(),
             };
 
+            _item.create_initial_revision();
             diesel::update(&_item)
                 .set(_new_item)
                 .execute(&_connection)
                 .expect("E");
+            SlugRedirect::create(_item.types, false, &_item.slug, &slug);
+            Item::save_revision(_item.id, _request_user.id, 1);
 
             for category_id in form.category_list.iter() {
                 let new_category = NewCategory {
@@ -587,6 +607,7 @@ pub async fn edit_item(session: Session, mut payload: Multipart, _id: web::Path<
                         .expect("Error.");
                 }
             };
+            _item.update_main_category(form.main_category);
             for tag_id in form.tags_list.iter() {
                 let new_tag = NewTagItems {
                     tag_id: *tag_id,
@@ -609,6 +630,9 @@ pub async fn edit_item(session: Session, mut payload: Multipart, _id: web::Path<
                 }
             }
 
+            // теги и категории могли поменяться - похожие объекты пересчитаем
+            Item::clear_related_cache(_item.id);
+
             // создаем связь с тех категориями, которые будут
             // расширять списки опций, предлагая доп возможности и услуги
             for cat_id in form.close_tech_cats_list.iter() {
@@ -671,7 +695,12 @@ pub async fn edit_item(session: Session, mut payload: Multipart, _id: web::Path<
             // фух. Связи созданы все, но надо еще посчитать цену
             // услуги для калькулятора. Как? А  это будет сумма всех
             // цен выбранных опций.
-            let price_acc = get_price_acc_values(&item_price);
+            // скидку считает движок правил из discount_rules
+            let price_acc = DiscountRule::get_discount(&DiscountInput {
+                types:      _item.types,
+                lines:      _serves.iter().map(|s| (s.tech_cat_id, s.price)).collect(),
+                promo_code: None,
+            });
             diesel::update(&_item)
                 .set((
                     schema::items::price.eq(item_price),
@@ -688,11 +717,16 @@ pub async fn create_category(session: Session, mut payload: Multipart) -> impl R
     if is_signed_in(&session) {
         let _request_user = get_request_user_data(&session);
         if _request_user.perm == 60 {
-            use crate::utils::category_form;
+            use crate::utils::{category_form, get_unique_category_slug};
             use crate::models::NewCategories;
 
             let _connection = establish_connection();
             let form = category_form(payload.borrow_mut(), _request_user.id).await;
+            let slug = get_unique_category_slug(&form.slug, &form.name, form.types, 0);
+            let parent_id = match Categories::is_valid_parent(0, form.parent_id, form.types) {
+                true => Some(form.parent_id),
+                false => None,
+            };
             let new_cat = NewCategories {
                 name:        form.name.clone(),
                 description: Some(form.description.clone()),
@@ -703,8 +737,9 @@ pub async fn create_category(session: Session, mut payload: Multipart) -> impl R
                 height:      0.0,
                 seconds:     0,
                 types:       form.types,
-                slug:        form.slug,
+                slug:        slug,
                 now_u:       0,
+                parent_id:   parent_id,
             };
             diesel::insert_into(schema::categories::table)
                 .values(&new_cat)
@@ -722,7 +757,8 @@ pub async fn edit_category(session: Session, mut payload: Multipart, _id: web::P
     if is_signed_in(&session) {
         let _request_user = get_request_user_data(&session);
         if _request_user.perm == 60 {
-            use crate::utils::category_form;
+            use crate::utils::{category_form, get_unique_category_slug};
+            use crate::models::SlugRedirect;
 
             let _connection = establish_connection();
             let _cat_id: i32 = *_id;
@@ -732,18 +768,26 @@ pub async fn edit_category(session: Session, mut payload: Multipart, _id: web::P
                 .expect("E");
 
             let form = category_form(payload.borrow_mut(), _request_user.id).await;
+            let slug = get_unique_category_slug(&form.slug, &form.name, _category[0].types, _category[0].id);
+            // свою подкатегорию родителем сделать нельзя
+            let parent_id = match Categories::is_valid_parent(_category[0].id, form.parent_id, _category[0].types) {
+                true => Some(form.parent_id),
+                false => None,
+            };
             let _new_cat = EditCategories {
                 name:        form.name.clone(),
                 description: Some(form.description.clone()),
                 position:    form.position,
                 image:       Some(form.image.clone()),
-                slug:        form.slug,
+                slug:        slug.clone(),
+                parent_id:   parent_id,
             };
 
             diesel::update(&_category[0])
                 .set(_new_cat)
                 .execute(&_connection)
                 .expect("E");
+            SlugRedirect::create(_category[0].types, true, &_category[0].slug, &slug);
         }
     }
     HttpResponse::Ok()
@@ -765,22 +809,26 @@ pub async fn edit_content_item(session: Session, mut payload: Multipart, _id: we
             use crate::utils::content_form;
 
             let form = content_form(payload.borrow_mut()).await;
-            diesel::update(&_item)
-            .set(schema::items::content.eq(form.content.clone()))
-            .execute(&_connection)
+            let content = Item::render_content(&form.content, &form.content_md);
+            _item.create_initial_revision();
+            let _item = diesel::update(&_item)
+            .set((
+                schema::items::content.eq(content),
+                schema::items::content_md.eq(form.content_md.clone()),
+            ))
+            .get_result::<Item>(&_connection)
             .expect("E");
+            _item.update_wiki_links();
+            Item::save_revision(_item.id, _request_user.id, 2);
         }
     }
     HttpResponse::Ok().body("")
 }
 
+// объект уходит в корзину, стирается он фоновой задачей
+// или вручную со страницы /trash/
 pub async fn delete_item(session: Session, _id: web::Path<i32>) -> impl Responder {
-    use crate::schema::{
-        items::dsl::items,
-        tags_items::dsl::tags_items,
-        category::dsl::category,
-        files::dsl::files,
-    };
+    use crate::schema::items::dsl::items;
 
     if is_signed_in(&session) {
         let _request_user = get_request_user_data(&session);
@@ -791,68 +839,32 @@ pub async fn delete_item(session: Session, _id: web::Path<i32>) -> impl Responde
                 .filter(schema::items::id.eq(_item_id))
                 .first::<Item>(&_connection)
                 .expect("E");
+            _item.move_to_trash();
+        }
+    }
+    HttpResponse::Ok()
+}
 
-            let _src_list = files
-                .filter(schema::files::item_id.eq(_item_id))
-                .filter(schema::files::item_types.eq(_item.types))
-                .select(schema::files::src)
-                .load::<String>(&_connection)
-                .expect("E");
+// копия объекта со всеми связями. В ответе id черновика,
+// чтобы сразу открыть его редактирование
+pub async fn clone_item(session: Session, _id: web::Path<i32>) -> impl Responder {
+    use crate::schema::items::dsl::items;
 
-            for f in _src_list.iter() {
-                std::fs::remove_file(f);
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm == 60 {
+            let _connection = establish_connection();
+            let _item = items
+                .filter(schema::items::id.eq(*_id))
+                .filter(schema::items::deleted_at.is_null())
+                .first::<Item>(&_connection);
+            if _item.is_ok() {
+                let _new_item = _item.expect("E").duplicate(_request_user.id);
+                return HttpResponse::Ok().body(_new_item.id.to_string());
             }
-
-            diesel::delete (
-                files
-                    .filter(schema::files::item_id.eq(_item_id))
-                    .filter(schema::files::item_types.eq(_item.types))
-                )
-                .execute(&_connection)
-                .expect("E");
-            diesel::delete (
-                tags_items
-                    .filter(schema::tags_items::item_id.eq(_item_id))
-                    .filter(schema::tags_items::types.eq(_item.types))
-                )
-                .execute(&_connection)
-                .expect("E");
-            diesel::delete (
-                category
-                    .filter(schema::category::item_id.eq(_item_id))
-                    .filter(schema::category::types.eq(_item.types))
-                )
-                .execute(&_connection)
-                .expect("E");
-            diesel::delete(&_item).execute(&_connection).expect("E");
-
-            let _categories = _item.get_categories_obj().expect("E");
-            //_categories = match cats_res {
-            //    Ok(_ok) => _ok,
-            //    Err(_error) => Vec::new(),
-            //};
-            let _tags = _item.get_tags_obj().expect("E");
-            //_tags = match tags_res {
-            //    Ok(_list) => _list,
-            //    Err(_error) => Vec::new(),
-            //};
-
-            for _category in _categories.iter() {
-                diesel::update(_category)
-                    .set(schema::categories::count.eq(_category.count - 1))
-                    .execute(&_connection)
-                    .expect("Error.");
-            };
-            for _tag in _tags.iter() {
-                diesel::update(_tag)
-                    .set(schema::tags::count.eq(_tag.count - 1))
-                    .execute(&_connection)
-                    .expect("Error.");
-            };
-
         }
     }
-    HttpResponse::Ok()
+    HttpResponse::Ok().finish()
 }
 
 pub async fn delete_category(session: Session, _id: web::Path<i32>) -> impl Responder {
@@ -863,10 +875,11 @@ pub async fn delete_category(session: Session, _id: web::Path<i32>) -> impl Resp
         if _request_user.perm == 60 {
             let _connection = establish_connection();
             let _cat_id: i32 = *_id;
-
-            diesel::delete(categories.filter(schema::categories::id.eq(_cat_id)))
-                .execute(&_connection)
+            let _category = categories
+                .filter(schema::categories::id.eq(_cat_id))
+                .first::<Categories>(&_connection)
                 .expect("E");
+            _category.move_to_trash();
         }
     }
     HttpResponse::Ok()
@@ -953,7 +966,17 @@ pub async fn delete_file(session: Session, _id: web::Path<i32>) -> impl Responde
                 .filter(schema::files::id.eq(_file_id))
                 .first::<File>(&_connection)
                 .expect("E");
-            std::fs::remove_file(_file.src).expect("E");
+            // копии объекта (Item::duplicate) ссылаются на тот же файл -
+            // с диска удаляем, только если на него больше никто не ссылается
+            let _shared = files
+                .filter(schema::files::src.eq(&_file.src))
+                .filter(schema::files::id.ne(_file_id))
+                .select(schema::files::id)
+                .first::<i32>(&_connection)
+                .is_ok();
+            if !_shared {
+                std::fs::remove_file(&_file.src).ok();
+            }
 
             diesel::delete(files.filter(schema::files::id.eq(_file_id)))
                 .execute(&_connection)
@@ -976,38 +999,7 @@ pub async fn publish_item(session: Session, _id: web::Path<i32>) -> impl Respond
                 .first::<Item>(&_connection)
                 .expect("E");
 
-            diesel::update(&_item)
-                .set(schema::items::is_active.eq(true))
-                .execute(&_connection)
-                .expect("Error.");
-
-            let _categories: Vec<Categories>;
-            let _tags: Vec<Tag>;
-
-            let tags_o = _item.get_tags_obj().expect("E");
-            let categories_o = _item.get_categories_obj().expect("E");
-            let cats_res = block(move || categories_o).await;
-            let tags_res = block(move || tags_o).await;
-            _categories = match cats_res {
-                Ok(_ok) => _ok,
-                Err(_error) => Vec::new(),
-            };
-            for _category in _categories.iter() {
-                diesel::update(_category)
-                    .set(schema::categories::count.eq(_category.count + 1))
-                    .execute(&_connection)
-                    .expect("Error.");
-            }
-            _tags = match tags_res {
-                Ok(_list) => _list,
-                Err(_error) => Vec::new(),
-            };
-            for _tag in _tags.iter() {
-                diesel::update(_tag)
-                    .set(schema::tags::count.eq(_tag.count + 1))
-                    .execute(&_connection)
-                    .expect("Error.");
-            }
+            _item.publish();
         }
     }
     HttpResponse::Ok()
@@ -1025,39 +1017,35 @@ pub async fn hide_item(session: Session, _id: web::Path<i32>) -> impl Responder
                 .first::<Item>(&_connection)
                 .expect("E");
 
-            diesel::update(&_item)
-                .set(schema::items::is_active.eq(true))
-                .execute(&_connection)
-                .expect("Error.");
+            _item.hide();
+        }
+    }
+    HttpResponse::Ok()
+}
 
-            let _categories: Vec<Categories>;
-            let _tags: Vec<Tag>;
+// расписание публикации: пустое поле снимает расписание
+pub async fn schedule_item(session: Session, mut payload: Multipart, _id: web::Path<i32>) -> impl Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm == 60 {
+            use crate::schema::items::dsl::items;
+            use crate::utils::schedule_form;
 
-            let _categories_0 = _item.get_categories_obj().expect("E");
-            let _tags_0 = _item.get_tags_obj().expect("E");
-            let cats_res = block(move || _categories_0).await;
-            let tags_res = block(move || _tags_0).await;
+            let _connection = establish_connection();
+            let _id: i32 = *_id;
+            let _item = items
+                .filter(schema::items::id.eq(_id))
+                .first::<Item>(&_connection)
+                .expect("E");
 
-            _categories = match cats_res {
-                Ok(_ok) => _ok,
-                Err(_error) => Vec::new(),
-            };
-            for _category in _categories.iter() {
-                diesel::update(_category)
-                    .set(schema::categories::count.eq(_category.count - 1))
-                    .execute(&_connection)
-                    .expect("Error.");
-            }
-            _tags = match tags_res {
-                Ok(_list) => _list,
-                Err(_error) => Vec::new(),
-            };
-            for _tag in _tags.iter() {
-                diesel::update(_tag)
-                    .set(schema::tags::count.eq(_tag.count - 1))
-                    .execute(&_connection)
-                    .expect("Error.");
-            }
+            let form = schedule_form(payload.borrow_mut()).await;
+            diesel::update(&_item)
+                .set((
+                    schema::items::publish_at.eq(form.publish_at),
+                    schema::items::unpublish_at.eq(form.unpublish_at),
+                ))
+                .execute(&_connection)
+                .expect("E");
         }
     }
     HttpResponse::Ok()
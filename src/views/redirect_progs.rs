@@ -0,0 +1,187 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+    error::InternalError,
+    http::StatusCode,
+};
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    is_signed_in,
+    get_request_user_data,
+    get_device_and_ajax,
+    get_first_load_page,
+    get_page,
+    get_template,
+};
+use crate::schema;
+use crate::models::{
+    Redirect,
+    User,
+};
+use actix_session::Session;
+use actix_multipart::Multipart;
+use std::borrow::BorrowMut;
+use sailfish::TemplateOnce;
+
+
+pub fn redirect_routes(config: &mut web::ServiceConfig) {
+    config.route("/redirects/", web::get().to(redirects_page));
+    config.route("/create_redirect/", web::post().to(create_redirect));
+    config.route("/import_redirects/", web::post().to(import_redirects));
+    config.route("/edit_redirect/{id}/", web::post().to(edit_redirect));
+    config.route("/delete_redirect/{id}/", web::get().to(delete_redirect));
+}
+
+pub async fn redirects_page(session: Session, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Перенаправления".to_string(),
+            "вебсервисы.рф: Перенаправления".to_string(),
+            "/redirects/".to_string(),
+            "/static/images/dark/store.jpg".to_string(),
+            template_types,
+        ).await
+    }
+    else if !is_signed_in(&session) {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+    else {
+        let _request_user = get_request_user_data(&session);
+        if !_request_user.is_superuser() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        let (object_list, next_page_number) = Redirect::get_redirects_list(get_page(&req), 20);
+        let count = Redirect::count_redirects();
+
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/pages/redirects.stpl")]
+            struct Template {
+                request_user:     User,
+                object_list:      Vec<Redirect>,
+                count:            i64,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                request_user:     _request_user,
+                object_list:      object_list,
+                count:            count,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/pages/redirects.stpl")]
+            struct Template {
+                object_list:      Vec<Redirect>,
+                count:            i64,
+                next_page_number: i32,
+                is_ajax:          i32,
+                template_types:   i16,
+            }
+            let body = Template {
+                object_list:      object_list,
+                count:            count,
+                next_page_number: next_page_number,
+                is_ajax:          is_ajax,
+                template_types:   template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+pub async fn create_redirect(session: Session, mut payload: Multipart) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::redirect_form;
+
+            let form = redirect_form(payload.borrow_mut()).await;
+            Redirect::save(&form.source, &form.target, form.status);
+        }
+    }
+    HttpResponse::Ok()
+}
+
+// массовая загрузка: по строке "старый_адрес новый_адрес [301|302]"
+pub async fn import_redirects(session: Session, mut payload: Multipart) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::utils::redirects_import_form;
+
+            let list = redirects_import_form(payload.borrow_mut()).await;
+            for form in list.iter() {
+                Redirect::save(&form.source, &form.target, form.status);
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn edit_redirect(session: Session, mut payload: Multipart, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::schema::redirects::dsl::redirects;
+            use crate::utils::redirect_form;
+            use crate::models::EditRedirect;
+
+            let _connection = establish_connection();
+            let _redirect = redirects
+                .filter(schema::redirects::id.eq(*_id))
+                .first::<Redirect>(&_connection)
+                .expect("E");
+
+            let form = redirect_form(payload.borrow_mut()).await;
+            let _target = form.target.trim().to_string();
+            if !_target.is_empty() {
+                let _edit = EditRedirect {
+                    source: Redirect::normalize_source(&form.source),
+                    target: _target,
+                    status: if form.status == 302 { 302 } else { 301 },
+                };
+                diesel::update(&_redirect)
+                    .set(_edit)
+                    .execute(&_connection)
+                    .expect("E");
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn delete_redirect(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            use crate::schema::redirects::dsl::redirects;
+
+            let _connection = establish_connection();
+            diesel::delete(redirects.filter(schema::redirects::id.eq(*_id)))
+                .execute(&_connection)
+                .expect("E");
+        }
+    }
+    HttpResponse::Ok()
+}
@@ -0,0 +1,261 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+    error::InternalError,
+    http::StatusCode,
+};
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    is_signed_in,
+    get_request_user_data,
+    get_device_and_ajax,
+    get_first_load_page,
+    get_page,
+    get_template,
+    FieldDiff,
+};
+use crate::schema;
+use crate::models::{
+    Item,
+    ItemRevision,
+    User,
+};
+use actix_session::Session;
+use sailfish::TemplateOnce;
+
+
+pub fn revision_routes(config: &mut web::ServiceConfig) {
+    config.route("/item_revisions/{id}/", web::get().to(item_revisions_page));
+    config.route("/item_revisions_diff/{id}/", web::get().to(item_revisions_diff_page));
+    config.route("/restore_revision/{id}/", web::get().to(restore_revision));
+}
+
+fn get_item(item_id: i32) -> Item {
+    use crate::schema::items::dsl::items;
+
+    let _connection = establish_connection();
+    return items
+        .filter(schema::items::id.eq(item_id))
+        .first::<Item>(&_connection)
+        .expect("E");
+}
+
+fn get_revision(revision_id: i32) -> Option<ItemRevision> {
+    use crate::schema::item_revisions::dsl::item_revisions;
+
+    let _connection = establish_connection();
+    return item_revisions
+        .filter(schema::item_revisions::id.eq(revision_id))
+        .first::<ItemRevision>(&_connection)
+        .ok();
+}
+
+pub async fn item_revisions_page(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    let _item = get_item(*_id);
+    let template_types = get_template(&req);
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "История изменений ".to_string() + &_item.title,
+            "вебсервисы.рф: История изменений ".to_string() + &_item.title,
+            "/item_revisions/".to_string() + &_item.id.to_string() + &"/".to_string(),
+            _item.get_image(),
+            template_types,
+        ).await
+    }
+    else if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm == 60 || _request_user.id == _item.user_id {
+            let (object_list, next_page_number) = ItemRevision::get_revisions_list(_item.id, get_page(&req), 20);
+            if is_desctop {
+                #[derive(TemplateOnce)]
+                #[template(path = "desctop/revisions/list.stpl")]
+                struct Template {
+                    request_user:     User,
+                    item:             Item,
+                    object_list:      Vec<ItemRevision>,
+                    next_page_number: i32,
+                    is_ajax:          i32,
+                    template_types:   i16,
+                }
+                let body = Template {
+                    request_user:     _request_user,
+                    item:             _item,
+                    object_list:      object_list,
+                    next_page_number: next_page_number,
+                    is_ajax:          is_ajax,
+                    template_types:   template_types,
+                }
+                .render_once()
+                .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+                Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+            }
+            else {
+                #[derive(TemplateOnce)]
+                #[template(path = "mobile/revisions/list.stpl")]
+                struct Template {
+                    item:             Item,
+                    object_list:      Vec<ItemRevision>,
+                    next_page_number: i32,
+                    is_ajax:          i32,
+                    template_types:   i16,
+                }
+                let body = Template {
+                    item:             _item,
+                    object_list:      object_list,
+                    next_page_number: next_page_number,
+                    is_ajax:          is_ajax,
+                    template_types:   template_types,
+                }
+                .render_once()
+                .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+                Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+            }
+        }
+        else {
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+        }
+    }
+    else {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+}
+
+// сравнение двух ревизий объекта: ?first=1&second=2.
+// если ревизии не указаны, сравниваем две последние.
+pub async fn item_revisions_diff_page(session: Session, req: HttpRequest, _id: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Params {
+        pub first:  Option<i32>,
+        pub second: Option<i32>,
+    }
+
+    let _item = get_item(*_id);
+    let template_types = get_template(&req);
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Сравнение изменений ".to_string() + &_item.title,
+            "вебсервисы.рф: Сравнение изменений ".to_string() + &_item.title,
+            "/item_revisions_diff/".to_string() + &_item.id.to_string() + &"/".to_string(),
+            _item.get_image(),
+            template_types,
+        ).await
+    }
+    else if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.perm != 60 && _request_user.id != _item.user_id {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+
+        let mut second: Option<ItemRevision> = None;
+        let mut first: Option<ItemRevision> = None;
+        let params_some = web::Query::<Params>::from_query(&req.query_string());
+        if params_some.is_ok() {
+            let params = params_some.unwrap();
+            if params.second.is_some() {
+                second = get_revision(params.second.unwrap());
+            }
+            if params.first.is_some() {
+                first = get_revision(params.first.unwrap());
+            }
+        }
+        if second.is_none() {
+            second = ItemRevision::get_revisions(_item.id, 1, 0).into_iter().next();
+        }
+        if second.is_none() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Изменений пока нет."));
+        }
+        let second = second.unwrap();
+        if first.is_none() {
+            first = second.get_previous();
+        }
+        let first = match first {
+            Some(_first) => _first,
+            None => return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Нет ревизии для сравнения.")),
+        };
+        if first.item_id != _item.id || second.item_id != _item.id {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+
+        let diff_list = first.get_diff(&second);
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/revisions/diff.stpl")]
+            struct Template {
+                request_user:   User,
+                item:           Item,
+                first:          ItemRevision,
+                second:         ItemRevision,
+                diff_list:      Vec<FieldDiff>,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                request_user:   _request_user,
+                item:           _item,
+                first:          first,
+                second:         second,
+                diff_list:      diff_list,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/revisions/diff.stpl")]
+            struct Template {
+                item:           Item,
+                first:          ItemRevision,
+                second:         ItemRevision,
+                diff_list:      Vec<FieldDiff>,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                item:           _item,
+                first:          first,
+                second:         second,
+                diff_list:      diff_list,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+    else {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+}
+
+pub async fn restore_revision(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _revision = get_revision(*_id);
+        if _revision.is_some() {
+            let _revision = _revision.unwrap();
+            let _item = get_item(_revision.item_id);
+            let _request_user = get_request_user_data(&session);
+            if _request_user.perm == 60 || _request_user.id == _item.user_id {
+                _revision.restore(_request_user.id);
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
@@ -12,6 +12,8 @@ use crate::utils::{
     is_signed_in,
     get_request_user_data,
     get_first_load_page,
+    get_first_load_page_with_meta,
+    PageMeta,
     get_template,
 };
 use actix_session::Session;
@@ -28,14 +30,17 @@ use crate::models::{
     Cat,
     SmallTag,
     CatDetail,
+    RelatedItem,
+    SlugRedirect,
 };
+use crate::views::{redirect_or_not_found, get_category_redirect};
 use sailfish::TemplateOnce;
 
 
 pub fn service_routes(config: &mut web::ServiceConfig) {
     config.route("/service_categories/", web::get().to(service_categories_page));
     config.service(web::resource("/service/{cat_slug}/{service_slug}/").route(web::get().to(get_service_page)));
-    config.service(web::resource("/services/{slug}/").route(web::get().to(service_category_page)));
+    config.service(web::resource("/services/{slug:.+}/").route(web::get().to(service_category_page)));
 }
 
 
@@ -49,18 +54,30 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
     let _item_id: String = param.1.clone();
     let _cat_id: String = param.0.clone();
 
-    let _item = items
+    let _item_res = items
         .filter(schema::items::slug.eq(&_item_id))
-        .first::<Item>(&_connection)
-        .expect("E");
+        .filter(schema::items::types.eq(2))
+        .filter(schema::items::deleted_at.is_null())
+        .first::<Item>(&_connection);
+    // старая ссылка на объект или категорию - перенаправляем на новую
+    if _item_res.is_err() || !SlugRedirect::category_exists(2, &_cat_id) {
+        return redirect_or_not_found(req, session, SlugRedirect::get_item_url(2, &_cat_id, &_item_id)).await;
+    }
+    let _item = _item_res.expect("E");
+    // объект открыт через чужую категорию - ведем на канонический адрес
+    if _item.get_main_category_id().is_some() && !_item.has_category_slug(&_cat_id) {
+        return redirect_or_not_found(req, session, Some(_item.get_url())).await;
+    }
     if is_ajax == 0 {
-        get_first_load_page (
+        get_first_load_page_with_meta (
             &session,
             is_desctop,
-            _item.title.clone() + &" | Услуга".to_string(),
-            _item.title.clone() + &" | Услуга: вебсервисы.рф".to_string(),
-            "/service/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
-            _item.get_image(),
+            PageMeta::for_item(
+                &_item,
+                _item.title.clone() + &" | Услуга".to_string(),
+                _item.title.clone() + &" | Услуга: вебсервисы.рф".to_string(),
+                "/service/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
+            ),
             template_types,
         ).await
     }
@@ -94,6 +111,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
         };
 
         let (prev, next) = _category.get_featured_items(_item.types, _item.id);
+        let related = _item.get_related_items(6);
 
         if is_signed_in(&session) {
             let _request_user = get_request_user_data(&session);
@@ -121,6 +139,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -132,6 +151,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -150,6 +170,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -161,6 +182,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -192,6 +214,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -202,6 +225,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -219,6 +243,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -229,6 +254,7 @@ pub async fn get_service_page(session: Session, req: HttpRequest, param: web::Pa
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -244,13 +270,16 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
     use crate::schema::categories::dsl::categories;
     use crate::utils::get_device_and_ajax;
 
-    let _cat_id: String = _id.clone();
+    // вложенный адрес "родитель/категория" - ищем по последней части
+    let _path: String = _id.clone();
+    let _cat_id: String = _path.rsplit('/').next().unwrap_or("").to_string();
     let _connection = establish_connection();
     let template_types = get_template(&req);
 
     let _category = categories
         .filter(schema::categories::slug.eq(&_cat_id))
         .filter(schema::categories::types.eq(2))
+        .filter(schema::categories::deleted_at.is_null())
         .select((
             schema::categories::name,
             schema::categories::slug,
@@ -262,8 +291,20 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
             schema::categories::seconds,
             schema::categories::now_u,
         ))
-        .first::<CatDetail>(&_connection)
-        .expect("E");
+        .first::<CatDetail>(&_connection);
+    if _category.is_err() {
+        return redirect_or_not_found(req, session, SlugRedirect::get_category_url(2, &_cat_id)).await;
+    }
+    let mut _category = _category.expect("E");
+
+    // плоский или устаревший путь - ведем на полный вложенный адрес
+    let _url = Categories::get_category_url(_category.id, 2);
+    if let Some(_response) = get_category_redirect(&req, &_url) {
+        return Ok(_response);
+    }
+    // в счетчике учитываются и объекты подкатегорий
+    _category.count = Categories::get_total_count(_category.id, 2);
+    let _breadcrumbs = Categories::get_breadcrumbs(_category.id);
 
     let cat_image: String;
     if _category.image.is_some() {
@@ -280,7 +321,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
             is_desctop,
             _category.name.clone() + &" | Категория услуг ".to_string(),
             _category.name.clone() + &" | Категория услуг - вебсервисы.рф".to_string(),
-            "/services/".to_string() + &_category.slug.clone() + &"/".to_string(),
+            _url.clone(),
             cat_image,
             template_types,
         ).await
@@ -319,6 +360,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 struct Template {
                     request_user:     User,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     object_list:      Vec<Service>,
                     next_page_number: i32,
                     is_ajax:          i32,
@@ -327,6 +369,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 let body = Template {
                     request_user:     _request_user,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     object_list:      object_list,
                     next_page_number: next_page_number,
                     is_ajax:          is_ajax,
@@ -342,6 +385,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Service>,
                     next_page_number: i32,
@@ -351,6 +395,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -374,6 +419,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 #[template(path = "desctop/services/anon_category.stpl")]
                 struct Template {
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     object_list:      Vec<Service>,
                     next_page_number: i32,
                     is_ajax:          i32,
@@ -381,6 +427,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 }
                 let body = Template {
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     object_list:      object_list,
                     next_page_number: next_page_number,
                     is_ajax:          is_ajax,
@@ -396,6 +443,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Service>,
                     next_page_number: i32,
@@ -405,6 +453,7 @@ pub async fn service_category_page(session: Session, req: HttpRequest, _id: web:
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -0,0 +1,52 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+};
+use crate::vars::site_url;
+use crate::utils::{
+    get_xml_response,
+    get_sitemap_urls,
+    get_sitemap_pages_count,
+    render_urlset,
+    render_sitemap_index,
+    render_robots,
+    SITEMAP_LIMIT,
+};
+
+
+pub fn sitemap_routes(config: &mut web::ServiceConfig) {
+    config.route("/sitemap.xml", web::get().to(sitemap_page));
+    config.route("/sitemap-{page}.xml", web::get().to(sitemap_part_page));
+    config.route("/robots.txt", web::get().to(robots_page));
+}
+
+// пока адресов меньше 50 000, отдаем их одним файлом,
+// иначе - индекс со ссылками на части
+pub async fn sitemap_page(req: HttpRequest) -> HttpResponse {
+    let urls = get_sitemap_urls();
+    let body = if urls.len() > SITEMAP_LIMIT {
+        render_sitemap_index(&site_url(), &urls)
+    }
+    else {
+        render_urlset(&site_url(), &urls)
+    };
+    get_xml_response(&req, body, "application/xml; charset=utf-8")
+}
+
+pub async fn sitemap_part_page(req: HttpRequest, page: web::Path<usize>) -> HttpResponse {
+    let _page = page.into_inner();
+    let urls = get_sitemap_urls();
+    if _page < 1 || _page > get_sitemap_pages_count(urls.len()) {
+        return HttpResponse::NotFound().body("Sitemap not found.");
+    }
+    let end = std::cmp::min(_page * SITEMAP_LIMIT, urls.len());
+    let body = render_urlset(&site_url(), &urls[(_page - 1) * SITEMAP_LIMIT..end]);
+    get_xml_response(&req, body, "application/xml; charset=utf-8")
+}
+
+pub async fn robots_page() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(render_robots(&site_url()))
+}
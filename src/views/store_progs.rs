@@ -12,6 +12,8 @@ use crate::utils::{
     is_signed_in,
     get_request_user_data,
     get_first_load_page,
+    get_first_load_page_with_meta,
+    PageMeta,
     get_template,
 };
 use actix_session::Session;
@@ -28,14 +30,17 @@ use crate::models::{
     Cat,
     SmallTag,
     CatDetail,
+    RelatedItem,
+    SlugRedirect,
 };
+use crate::views::{redirect_or_not_found, get_category_redirect};
 use sailfish::TemplateOnce;
 
 
 pub fn store_routes(config: &mut web::ServiceConfig) {
     config.route("/store_categories/", web::get().to(store_categories_page));
     config.service(web::resource("/store/{cat_slug}/{store_slug}/").route(web::get().to(get_store_page)));
-    config.service(web::resource("/stores/{slug}/").route(web::get().to(store_category_page)));
+    config.service(web::resource("/stores/{slug:.+}/").route(web::get().to(store_category_page)));
 }
 
 
@@ -49,18 +54,30 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
     let _cat_id: String = param.0.clone();
     let template_types = get_template(&req);
 
-    let _item = items
+    let _item_res = items
         .filter(schema::items::slug.eq(&_item_id))
-        .first::<Item>(&_connection)
-        .expect("E");
+        .filter(schema::items::types.eq(3))
+        .filter(schema::items::deleted_at.is_null())
+        .first::<Item>(&_connection);
+    // старая ссылка на объект или категорию - перенаправляем на новую
+    if _item_res.is_err() || !SlugRedirect::category_exists(3, &_cat_id) {
+        return redirect_or_not_found(req, session, SlugRedirect::get_item_url(3, &_cat_id, &_item_id)).await;
+    }
+    let _item = _item_res.expect("E");
+    // объект открыт через чужую категорию - ведем на канонический адрес
+    if _item.get_main_category_id().is_some() && !_item.has_category_slug(&_cat_id) {
+        return redirect_or_not_found(req, session, Some(_item.get_url())).await;
+    }
     if is_ajax == 0 {
-        get_first_load_page (
+        get_first_load_page_with_meta (
             &session,
             is_desctop,
-            _item.title.clone() + &" | Товар".to_string(),
-            _item.title.clone() + &" | Товар: вебсервисы.рф".to_string(),
-            "/store/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
-            _item.get_image(),
+            PageMeta::for_item(
+                &_item,
+                _item.title.clone() + &" | Товар".to_string(),
+                _item.title.clone() + &" | Товар: вебсервисы.рф".to_string(),
+                "/store/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
+            ),
             template_types,
         ).await
     }
@@ -90,6 +107,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
         };
 
         let (prev, next) = _category.get_featured_items(_item.types, _item.id);
+        let related = _item.get_related_items(6);
 
         if is_signed_in(&session) {
             let _request_user = get_request_user_data(&session);
@@ -115,6 +133,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     category:       Categories,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -124,6 +143,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     category:       _category,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -142,6 +162,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -153,6 +174,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -182,6 +204,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     category:       Categories,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -190,6 +213,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     category:       _category,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -207,6 +231,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -217,6 +242,7 @@ pub async fn get_store_page(session: Session, req: HttpRequest, param: web::Path
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -232,13 +258,16 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
     use crate::schema::categories::dsl::categories;
     use crate::utils::get_device_and_ajax;
 
-    let _cat_id: String = _id.clone();
+    // вложенный адрес "родитель/категория" - ищем по последней части
+    let _path: String = _id.clone();
+    let _cat_id: String = _path.rsplit('/').next().unwrap_or("").to_string();
     let _connection = establish_connection();
     let template_types = get_template(&req);
 
     let _category = categories
         .filter(schema::categories::slug.eq(&_cat_id))
         .filter(schema::categories::types.eq(3))
+        .filter(schema::categories::deleted_at.is_null())
         .select((
             schema::categories::name,
             schema::categories::slug,
@@ -250,8 +279,20 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
             schema::categories::seconds,
             schema::categories::now_u,
         ))
-        .first::<CatDetail>(&_connection)
-        .expect("E");
+        .first::<CatDetail>(&_connection);
+    if _category.is_err() {
+        return redirect_or_not_found(req, session, SlugRedirect::get_category_url(3, &_cat_id)).await;
+    }
+    let mut _category = _category.expect("E");
+
+    // плоский или устаревший путь - ведем на полный вложенный адрес
+    let _url = Categories::get_category_url(_category.id, 3);
+    if let Some(_response) = get_category_redirect(&req, &_url) {
+        return Ok(_response);
+    }
+    // в счетчике учитываются и объекты подкатегорий
+    _category.count = Categories::get_total_count(_category.id, 3);
+    let _breadcrumbs = Categories::get_breadcrumbs(_category.id);
 
     let cat_image: String;
     if _category.image.is_some() {
@@ -268,7 +309,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
             is_desctop,
             _category.name.clone() + &" | Категория товаров ".to_string(),
             _category.name.clone() + &" | Категория товаров - вебсервисы.рф".to_string(),
-            "/stores/".to_string() + &_category.slug.clone() + &"/".to_string(),
+            _url.clone(),
             cat_image,
             template_types,
         ).await
@@ -308,6 +349,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                     request_user:     User,
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Store>,
                     next_page_number: i32,
@@ -318,6 +360,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                     request_user:     _request_user,
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -334,6 +377,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Store>,
                     next_page_number: i32,
@@ -343,6 +387,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -367,6 +412,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Store>,
                     next_page_number: i32,
@@ -376,6 +422,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -392,6 +439,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Store>,
                     next_page_number: i32,
@@ -401,6 +449,7 @@ pub async fn store_category_page(session: Session, req: HttpRequest, _id: web::P
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
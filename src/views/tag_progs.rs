@@ -1550,9 +1550,18 @@ pub async fn delete_tag(session: Session, _id: web::Path<i32>) -> impl Responder
         if _request_user.perm == 60 {
             use crate::schema::tags::dsl::tags;
             use crate::schema::tags_items::dsl::tags_items;
+            use crate::models::Item;
 
             let _connection = establish_connection();
             let _tag_id: i32 = *_id;
+            let _item_ids = tags_items
+                .filter(schema::tags_items::tag_id.eq(_tag_id))
+                .select(schema::tags_items::item_id)
+                .load::<i32>(&_connection)
+                .expect("E");
+            for item_id in _item_ids.iter() {
+                Item::clear_related_cache(*item_id);
+            }
             diesel::delete(tags_items.filter(schema::tags_items::tag_id.eq(_tag_id))).execute(&_connection).expect("E");
             diesel::delete(tags.filter(schema::tags::id.eq(_tag_id))).execute(&_connection).expect("E");
         }
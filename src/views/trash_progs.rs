@@ -0,0 +1,176 @@
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    web,
+    error::InternalError,
+    http::StatusCode,
+};
+use crate::diesel::{
+    RunQueryDsl,
+    ExpressionMethods,
+    QueryDsl,
+};
+use crate::utils::{
+    establish_connection,
+    is_signed_in,
+    get_request_user_data,
+    get_device_and_ajax,
+    get_first_load_page,
+    get_template,
+};
+use crate::schema;
+use crate::models::{
+    Item,
+    Categories,
+    User,
+};
+use actix_session::Session;
+use sailfish::TemplateOnce;
+
+
+pub fn trash_routes(config: &mut web::ServiceConfig) {
+    config.route("/trash/", web::get().to(trash_page));
+    config.route("/restore_item/{id}/", web::get().to(restore_item));
+    config.route("/restore_category/{id}/", web::get().to(restore_category));
+    config.route("/purge_item/{id}/", web::get().to(purge_item));
+    config.route("/purge_category/{id}/", web::get().to(purge_category));
+}
+
+// корзина: удаленные объекты и категории с датой окончательного удаления
+pub async fn trash_page(session: Session, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let (is_desctop, is_ajax) = get_device_and_ajax(&req);
+    let template_types = get_template(&req);
+    if is_ajax == 0 {
+        get_first_load_page (
+            &session,
+            is_desctop,
+            "Корзина".to_string(),
+            "вебсервисы.рф: Корзина".to_string(),
+            "/trash/".to_string(),
+            "/static/images/dark/store.jpg".to_string(),
+            template_types,
+        ).await
+    }
+    else if !is_signed_in(&session) {
+        Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."))
+    }
+    else {
+        let _request_user = get_request_user_data(&session);
+        if !_request_user.is_superuser() {
+            return Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body("Permission Denied."));
+        }
+        let item_list = Item::get_trash();
+        let category_list = Categories::get_trash();
+
+        if is_desctop {
+            #[derive(TemplateOnce)]
+            #[template(path = "desctop/pages/trash.stpl")]
+            struct Template {
+                request_user:   User,
+                item_list:      Vec<Item>,
+                category_list:  Vec<Categories>,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                request_user:   _request_user,
+                item_list:      item_list,
+                category_list:  category_list,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+        else {
+            #[derive(TemplateOnce)]
+            #[template(path = "mobile/pages/trash.stpl")]
+            struct Template {
+                item_list:      Vec<Item>,
+                category_list:  Vec<Categories>,
+                is_ajax:        i32,
+                template_types: i16,
+            }
+            let body = Template {
+                item_list:      item_list,
+                category_list:  category_list,
+                is_ajax:        is_ajax,
+                template_types: template_types,
+            }
+            .render_once()
+            .map_err(|e| InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR))?;
+            Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body))
+        }
+    }
+}
+
+fn get_trash_item(item_id: i32) -> Option<Item> {
+    use crate::schema::items::dsl::items;
+
+    let _connection = establish_connection();
+    return items
+        .filter(schema::items::id.eq(item_id))
+        .filter(schema::items::deleted_at.is_not_null())
+        .first::<Item>(&_connection)
+        .ok();
+}
+fn get_trash_category(cat_id: i32) -> Option<Categories> {
+    use crate::schema::categories::dsl::categories;
+
+    let _connection = establish_connection();
+    return categories
+        .filter(schema::categories::id.eq(cat_id))
+        .filter(schema::categories::deleted_at.is_not_null())
+        .first::<Categories>(&_connection)
+        .ok();
+}
+
+pub async fn restore_item(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            if let Some(_item) = get_trash_item(*_id) {
+                _item.restore_from_trash();
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn restore_category(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            if let Some(_category) = get_trash_category(*_id) {
+                _category.restore_from_trash();
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+// удалить из корзины сразу, не дожидаясь фоновой задачи
+pub async fn purge_item(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            if let Some(_item) = get_trash_item(*_id) {
+                _item.purge();
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
+
+pub async fn purge_category(session: Session, _id: web::Path<i32>) -> impl actix_web::Responder {
+    if is_signed_in(&session) {
+        let _request_user = get_request_user_data(&session);
+        if _request_user.is_superuser() {
+            if let Some(_category) = get_trash_category(*_id) {
+                _category.purge();
+            }
+        }
+    }
+    HttpResponse::Ok()
+}
@@ -12,6 +12,8 @@ use crate::utils::{
     is_signed_in,
     get_request_user_data,
     get_first_load_page,
+    get_first_load_page_with_meta,
+    PageMeta,
     get_template,
 };
 use actix_session::Session;
@@ -28,14 +30,17 @@ use crate::models::{
     Cat,
     SmallTag,
     CatDetail,
+    RelatedItem,
+    SlugRedirect,
 };
+use crate::views::{redirect_or_not_found, get_category_redirect};
 use sailfish::TemplateOnce;
 
 
 pub fn wiki_routes(config: &mut web::ServiceConfig) {
     config.route("/wiki_categories/", web::get().to(wiki_categories_page));
     config.service(web::resource("/wiki/{cat_slug}/{wiki_slug}/").route(web::get().to(get_wiki_page)));
-    config.service(web::resource("/wikis/{slug}/").route(web::get().to(wiki_category_page)));
+    config.service(web::resource("/wikis/{slug:.+}/").route(web::get().to(wiki_category_page)));
 }
 
 
@@ -49,18 +54,30 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
     let _item_id: String = param.1.clone();
     let _cat_id: String = param.0.clone();
 
-    let _item = items
+    let _item_res = items
         .filter(schema::items::slug.eq(&_item_id))
-        .first::<Item>(&_connection)
-        .expect("E");
+        .filter(schema::items::types.eq(4))
+        .filter(schema::items::deleted_at.is_null())
+        .first::<Item>(&_connection);
+    // старая ссылка на объект или категорию - перенаправляем на новую
+    if _item_res.is_err() || !SlugRedirect::category_exists(4, &_cat_id) {
+        return redirect_or_not_found(req, session, SlugRedirect::get_item_url(4, &_cat_id, &_item_id)).await;
+    }
+    let _item = _item_res.expect("E");
+    // объект открыт через чужую категорию - ведем на канонический адрес
+    if _item.get_main_category_id().is_some() && !_item.has_category_slug(&_cat_id) {
+        return redirect_or_not_found(req, session, Some(_item.get_url())).await;
+    }
     if is_ajax == 0 {
-        get_first_load_page (
+        get_first_load_page_with_meta (
             &session,
             is_desctop,
-            _item.title.clone() + &" | Обучающая статья".to_string(),
-            _item.title.clone() + &" | Обучающая статья: вебсервисы.рф".to_string(),
-            "/wiki/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
-            _item.get_image(),
+            PageMeta::for_item(
+                &_item,
+                _item.title.clone() + &" | Обучающая статья".to_string(),
+                _item.title.clone() + &" | Обучающая статья: вебсервисы.рф".to_string(),
+                "/wiki/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
+            ),
             template_types,
         ).await
     }
@@ -69,6 +86,20 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
             categories::dsl::categories,
         };
         use crate::models::FeaturedItem;
+        use crate::utils::{
+            render_wiki_links,
+            add_heading_anchors,
+            get_reading_time,
+            TocItem,
+        };
+
+        // [[ссылки]] раскрываются при каждом показе, так новые
+        // и переименованные статьи подхватываются сразу
+        let mut _item = _item;
+        _item.content = _item.content.as_deref().map(render_wiki_links);
+        let backlinks = _item.get_backlinks();
+        let toc = add_heading_anchors(&mut _item.content);
+        let reading_time = get_reading_time(&_item.content);
 
         let _category = categories
             .filter(schema::categories::slug.eq(&_cat_id))
@@ -90,6 +121,7 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
         };
 
         let (prev, next) = _category.get_featured_items(_item.types, _item.id);
+        let related = _item.get_related_items(6);
 
         if is_signed_in(&session) {
             let _request_user = get_request_user_data(&session);
@@ -117,6 +149,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
+                    backlinks:      Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -128,6 +164,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
+                    backlinks:      backlinks,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -146,6 +186,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
+                    backlinks:      Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -157,6 +201,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
+                    backlinks:      backlinks,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -188,6 +236,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
+                    backlinks:      Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -198,6 +250,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
+                    backlinks:      backlinks,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -215,6 +271,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
+                    toc:            Vec<TocItem>,
+                    reading_time:   i16,
+                    backlinks:      Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -225,6 +285,10 @@ pub async fn get_wiki_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
+                    toc:            toc,
+                    reading_time:   reading_time,
+                    backlinks:      backlinks,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -240,13 +304,16 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
     use crate::schema::categories::dsl::categories;
     use crate::utils::get_device_and_ajax;
 
-    let _cat_id: String = _id.clone();
+    // вложенный адрес "родитель/категория" - ищем по последней части
+    let _path: String = _id.clone();
+    let _cat_id: String = _path.rsplit('/').next().unwrap_or("").to_string();
     let _connection = establish_connection();
     let template_types = get_template(&req);
 
     let _category = categories
         .filter(schema::categories::slug.eq(&_cat_id))
         .filter(schema::categories::types.eq(4))
+        .filter(schema::categories::deleted_at.is_null())
         .select((
             schema::categories::name,
             schema::categories::slug,
@@ -258,8 +325,20 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
             schema::categories::seconds,
             schema::categories::now_u,
         ))
-        .first::<CatDetail>(&_connection)
-        .expect("E");
+        .first::<CatDetail>(&_connection);
+    if _category.is_err() {
+        return redirect_or_not_found(req, session, SlugRedirect::get_category_url(4, &_cat_id)).await;
+    }
+    let mut _category = _category.expect("E");
+
+    // плоский или устаревший путь - ведем на полный вложенный адрес
+    let _url = Categories::get_category_url(_category.id, 4);
+    if let Some(_response) = get_category_redirect(&req, &_url) {
+        return Ok(_response);
+    }
+    // в счетчике учитываются и объекты подкатегорий
+    _category.count = Categories::get_total_count(_category.id, 4);
+    let _breadcrumbs = Categories::get_breadcrumbs(_category.id);
 
     let cat_image: String;
     if _category.image.is_some() {
@@ -276,7 +355,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
             is_desctop,
             _category.name.clone() + &" | Категория обучения ".to_string(),
             _category.name.clone() + &" | Категория обучения - вебсервисы.рф".to_string(),
-            "/wikis/".to_string() + &_category.slug.clone() + &"/".to_string(),
+            _url.clone(),
             cat_image,
             template_types,
         ).await
@@ -316,6 +395,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     User,
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Wiki>,
                     next_page_number: i32,
@@ -326,6 +406,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     _request_user,
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -342,6 +423,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Wiki>,
                     next_page_number: i32,
@@ -351,6 +433,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -375,6 +458,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Wiki>,
                     next_page_number: i32,
@@ -384,6 +468,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -400,6 +485,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Wiki>,
                     next_page_number: i32,
@@ -409,6 +495,7 @@ pub async fn wiki_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
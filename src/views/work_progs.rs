@@ -12,6 +12,8 @@ use crate::utils::{
     is_signed_in,
     get_request_user_data,
     get_first_load_page,
+    get_first_load_page_with_meta,
+    PageMeta,
     get_template,
 };
 use actix_session::Session;
@@ -28,14 +30,17 @@ use crate::models::{
     Cat,
     SmallTag,
     CatDetail,
+    RelatedItem,
+    SlugRedirect,
 };
+use crate::views::{redirect_or_not_found, get_category_redirect};
 use sailfish::TemplateOnce;
 
 
 pub fn work_routes(config: &mut web::ServiceConfig) {
     config.route("/work_categories/", web::get().to(work_categories_page));
     config.service(web::resource("/work/{cat_slug}/{work_slug}/").route(web::get().to(get_work_page)));
-    config.service(web::resource("/works/{slug}/").route(web::get().to(work_category_page)));
+    config.service(web::resource("/works/{slug:.+}/").route(web::get().to(work_category_page)));
 }
 
 
@@ -49,19 +54,31 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
     let _item_id: String = param.1.clone();
     let _cat_id: String = param.0.clone();
 
-    let _item = items
+    let _item_res = items
         .filter(schema::items::slug.eq(&_item_id))
-        .first::<Item>(&_connection)
-        .expect("E");
+        .filter(schema::items::types.eq(5))
+        .filter(schema::items::deleted_at.is_null())
+        .first::<Item>(&_connection);
+    // старая ссылка на объект или категорию - перенаправляем на новую
+    if _item_res.is_err() || !SlugRedirect::category_exists(5, &_cat_id) {
+        return redirect_or_not_found(req, session, SlugRedirect::get_item_url(5, &_cat_id, &_item_id)).await;
+    }
+    let _item = _item_res.expect("E");
+    // объект открыт через чужую категорию - ведем на канонический адрес
+    if _item.get_main_category_id().is_some() && !_item.has_category_slug(&_cat_id) {
+        return redirect_or_not_found(req, session, Some(_item.get_url())).await;
+    }
     let title = _item.title.clone();
     if is_ajax == 0 {
-        get_first_load_page (
+        get_first_load_page_with_meta (
             &session,
             is_desctop,
-            title.clone() + &" | Работа".to_string(),
-            title.clone() + &" | Работа: вебсервисы.рф".to_string(),
-            "/work/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
-            _item.get_image(),
+            PageMeta::for_item(
+                &_item,
+                title.clone() + &" | Работа".to_string(),
+                title.clone() + &" | Работа: вебсервисы.рф".to_string(),
+                "/work/".to_string() + &_cat_id.to_string() + &"/".to_string() + &_item_id.to_string() + &"/".to_string(),
+            ),
             template_types,
         ).await
     }
@@ -92,6 +109,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
         };
 
         let (prev, next) = _category.get_featured_items(_item.types, _item.id);
+        let related = _item.get_related_items(6);
 
         if is_signed_in(&session) {
             let _request_user = get_request_user_data(&session);
@@ -117,6 +135,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     category:       Categories,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -126,6 +145,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     category:       _category,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -144,6 +164,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -155,6 +176,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -184,6 +206,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     category:       Categories,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -192,6 +215,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     category:       _category,
                     prev:            prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -209,6 +233,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       Vec<SmallTag>,
                     prev:           Option<FeaturedItem>,
                     next:           Option<FeaturedItem>,
+                    related:        Vec<RelatedItem>,
                     is_ajax:        i32,
                     template_types: i16,
                 }
@@ -219,6 +244,7 @@ pub async fn get_work_page(session: Session, req: HttpRequest, param: web::Path<
                     all_tags:       _tags,
                     prev:           prev,
                     next:           next,
+                    related:        related,
                     is_ajax:        is_ajax,
                     template_types: template_types,
                 }
@@ -234,13 +260,16 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
     use crate::schema::categories::dsl::categories;
     use crate::utils::get_device_and_ajax;
 
-    let _cat_id: String = _id.clone();
+    // вложенный адрес "родитель/категория" - ищем по последней части
+    let _path: String = _id.clone();
+    let _cat_id: String = _path.rsplit('/').next().unwrap_or("").to_string();
     let _connection = establish_connection();
     let template_types = get_template(&req);
 
     let _category = categories
         .filter(schema::categories::slug.eq(&_cat_id))
         .filter(schema::categories::types.eq(5))
+        .filter(schema::categories::deleted_at.is_null())
         .select((
             schema::categories::name,
             schema::categories::slug,
@@ -252,8 +281,20 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
             schema::categories::seconds,
             schema::categories::now_u,
         ))
-        .first::<CatDetail>(&_connection)
-        .expect("E");
+        .first::<CatDetail>(&_connection);
+    if _category.is_err() {
+        return redirect_or_not_found(req, session, SlugRedirect::get_category_url(5, &_cat_id)).await;
+    }
+    let mut _category = _category.expect("E");
+
+    // плоский или устаревший путь - ведем на полный вложенный адрес
+    let _url = Categories::get_category_url(_category.id, 5);
+    if let Some(_response) = get_category_redirect(&req, &_url) {
+        return Ok(_response);
+    }
+    // в счетчике учитываются и объекты подкатегорий
+    _category.count = Categories::get_total_count(_category.id, 5);
+    let _breadcrumbs = Categories::get_breadcrumbs(_category.id);
 
     let cat_image: String;
     if _category.image.is_some() {
@@ -270,7 +311,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
             is_desctop,
             _category.name.clone() + &" | Категория работ ".to_string(),
             _category.name.clone() + &" | Категория работ - вебсервисы.рф".to_string(),
-            "/works/".to_string() + &_category.slug.clone() + &"/".to_string(),
+            _url.clone(),
             cat_image,
             template_types,
         ).await
@@ -310,6 +351,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     User,
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     object_list:      Vec<Work>,
                     next_page_number: i32,
                     is_ajax:          i32,
@@ -319,6 +361,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                     request_user:     _request_user,
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     object_list:      object_list,
                     next_page_number: next_page_number,
                     is_ajax:          is_ajax,
@@ -334,6 +377,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Work>,
                     next_page_number: i32,
@@ -343,6 +387,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,
@@ -367,6 +412,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     object_list:      Vec<Work>,
                     next_page_number: i32,
                     is_ajax:          i32,
@@ -375,6 +421,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     object_list:      object_list,
                     next_page_number: next_page_number,
                     is_ajax:          is_ajax,
@@ -390,6 +437,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 struct Template {
                     all_tags:         Vec<SmallTag>,
                     category:         CatDetail,
+                    breadcrumbs:      Vec<Cat>,
                     cats:             Vec<Cat>,
                     object_list:      Vec<Work>,
                     next_page_number: i32,
@@ -399,6 +447,7 @@ pub async fn work_category_page(session: Session, req: HttpRequest, _id: web::Pa
                 let body = Template {
                     all_tags:         _tags,
                     category:         _category,
+                    breadcrumbs:      _breadcrumbs,
                     cats:             _cats,
                     object_list:      object_list,
                     next_page_number: next_page_number,